@@ -0,0 +1,45 @@
+//! Runs the same 20-node graph flattened into one schedule and with every
+//! `parallel!` group compiled as its own nested component. `cargo bench`.
+use ceres::{new, parallel, serial, Builder, ComponentFn, BUFFER_SIZE};
+use std::time::Instant;
+
+const BLOCKS: u32 = 20_000;
+
+fn gain(amount: f32) -> impl FnOnce(&mut Builder<()>) -> ComponentFn<()> {
+    move |_builder| Box::new(move |_runtime, input, output, _sample_rate| {
+        for (out, &sample) in output.iter_mut().zip(input) {
+            *out = sample * amount;
+        }
+    })
+}
+
+// Four weighted branches
+fn group() -> impl FnOnce(&mut Builder<()>) -> ComponentFn<()> {
+    parallel!((0.25, gain(0.9)), (0.25, gain(1.1)), (0.25, gain(0.8)), (0.25, gain(1.2)))
+}
+
+fn time(name: &str, graph: impl FnOnce(&mut Builder<()>) -> ComponentFn<()>) {
+    let (_bus, builder) = new::<()>();
+    let mut runtime = builder.build(graph);
+    let input = [0.5; BUFFER_SIZE];
+    let mut output = [0.0; BUFFER_SIZE];
+
+    let start = Instant::now();
+    for _ in 0..BLOCKS {
+        runtime.tick(48_000.0, None, &input, &mut output);
+    }
+    let per_block = start.elapsed() / BLOCKS;
+    println!("{name:>8}: {per_block:?} per {BUFFER_SIZE}-sample block ({})", output[0]);
+}
+
+fn main() {
+    // 4 x (4 branches + 1 gain) = 20 components
+    time("flat", serial!(
+        parallel!((0.25, gain(0.9)), (0.25, gain(1.1)), (0.25, gain(0.8)), (0.25, gain(1.2))), gain(1.0),
+        parallel!((0.25, gain(0.9)), (0.25, gain(1.1)), (0.25, gain(0.8)), (0.25, gain(1.2))), gain(1.0),
+        parallel!((0.25, gain(0.9)), (0.25, gain(1.1)), (0.25, gain(0.8)), (0.25, gain(1.2))), gain(1.0),
+        parallel!((0.25, gain(0.9)), (0.25, gain(1.1)), (0.25, gain(0.8)), (0.25, gain(1.2))), gain(1.0)
+    ));
+    // Called through a function, each group is an opaque node with its own schedule
+    time("nested", serial!(group(), gain(1.0), group(), gain(1.0), group(), gain(1.0), group(), gain(1.0)));
+}
@@ -1,13 +1,228 @@
 //ceres-dsp/ceres-macros/src/lib.rs
 use proc_macro::TokenStream;
-use quote::quote;
+use quote::{quote, quote_spanned};
 use syn::{parse_macro_input, DeriveInput, Data, Fields};
 use syn::spanned::Spanned;
 
+// Options collected from `#[param(...)]` on a single field
+#[derive(Default)]
+struct ParamAttrs {
+    mod_limit: Option<f32>,
+    default: Option<f32>,
+    min: Option<f32>,
+    max: Option<f32>,
+    smooth_ms: Option<f32>,
+    unit_min: Option<f32>,
+    unit_max: Option<f32>,
+    exp: bool,
+    block_rate: bool,
+    audio_rate: bool,
+    raw: bool,
+}
+
+impl ParamAttrs {
+    fn range(&self) -> (f32, f32) {
+        (self.min.unwrap_or(0.0), self.max.unwrap_or(1.0))
+    }
+}
+
+fn parse_number(meta: &syn::meta::ParseNestedMeta, option: &str) -> syn::Result<f32> {
+    match meta.value()?.parse::<syn::Expr>()? {
+        syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Float(lit), .. }) => lit.base10_parse::<f32>(),
+        syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Int(lit), .. }) => lit.base10_parse::<f32>(),
+        syn::Expr::Unary(syn::ExprUnary { op: syn::UnOp::Neg(_), expr, .. }) => match *expr {
+            syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Float(lit), .. }) => Ok(-lit.base10_parse::<f32>()?),
+            syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Int(lit), .. }) => Ok(-lit.base10_parse::<f32>()?),
+            expr => Err(syn::Error::new(expr.span(), format!("{} must be a number", option))),
+        },
+        expr => Err(syn::Error::new(expr.span(), format!("{} must be a number", option))),
+    }
+}
+
+fn parse_param_attrs(field: &syn::Field) -> syn::Result<ParamAttrs> {
+    let mut attrs = ParamAttrs::default();
+    for attr in field.attrs.iter().filter(|attr| attr.path().is_ident("param")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("mod_limit") {
+                let limit = parse_number(&meta, "mod_limit")?;
+                if limit < 0.0 {
+                    return Err(meta.error("mod_limit must not be negative"));
+                }
+                attrs.mod_limit = Some(limit);
+                Ok(())
+            } else if meta.path.is_ident("default") {
+                let default = parse_number(&meta, "default")?;
+                if !(0.0..=1.0).contains(&default) {
+                    return Err(meta.error("default must be within 0.0..=1.0"));
+                }
+                attrs.default = Some(default);
+                Ok(())
+            } else if meta.path.is_ident("min") || meta.path.is_ident("max") {
+                let option = if meta.path.is_ident("min") { "min" } else { "max" };
+                let bound = parse_number(&meta, option)?;
+                if !(0.0..=1.0).contains(&bound) {
+                    return Err(meta.error(format!("{} must be within 0.0..=1.0", option)));
+                }
+                if option == "min" {
+                    attrs.min = Some(bound);
+                } else {
+                    attrs.max = Some(bound);
+                }
+                Ok(())
+            } else if meta.path.is_ident("smooth_ms") {
+                let time = parse_number(&meta, "smooth_ms")?;
+                if time <= 0.0 {
+                    return Err(meta.error("smooth_ms must be positive"));
+                }
+                attrs.smooth_ms = Some(time);
+                Ok(())
+            } else if meta.path.is_ident("unit_min") {
+                attrs.unit_min = Some(parse_number(&meta, "unit_min")?);
+                Ok(())
+            } else if meta.path.is_ident("unit_max") {
+                attrs.unit_max = Some(parse_number(&meta, "unit_max")?);
+                Ok(())
+            } else if meta.path.is_ident("exp") {
+                attrs.exp = true;
+                Ok(())
+            } else if meta.path.is_ident("block_rate") {
+                attrs.block_rate = true;
+                Ok(())
+            } else if meta.path.is_ident("audio_rate") {
+                attrs.audio_rate = true;
+                Ok(())
+            } else if meta.path.is_ident("raw") {
+                attrs.raw = true;
+                Ok(())
+            } else {
+                Err(meta.error("unknown param option"))
+            }
+        })?;
+    }
+
+    let (min, max) = attrs.range();
+    if min >= max {
+        return Err(syn::Error::new(field.span(), "param min must be below max"));
+    }
+    if let Some(default) = attrs.default {
+        if !(min..=max).contains(&default) {
+            return Err(syn::Error::new(field.span(), "param default must be within min..=max"));
+        }
+    }
+    if attrs.block_rate && attrs.audio_rate {
+        return Err(syn::Error::new(field.span(), "a param can't be both block_rate and audio_rate"));
+    }
+    if attrs.block_rate && attrs.smooth_ms.is_some() {
+        return Err(syn::Error::new(field.span(), "a block_rate param can't be smoothed"));
+    }
+    match (attrs.unit_min, attrs.unit_max) {
+        (Some(unit_min), Some(unit_max)) => {
+            if unit_min == unit_max {
+                return Err(syn::Error::new(field.span(), "param unit_min and unit_max must differ"));
+            }
+            if attrs.exp && (unit_min <= 0.0 || unit_max <= 0.0) {
+                return Err(syn::Error::new(field.span(), "an exp param's unit range must be positive"));
+            }
+        }
+        (None, None) if attrs.exp => {
+            return Err(syn::Error::new(field.span(), "exp needs unit_min and unit_max"));
+        }
+        (None, None) => {}
+        _ => return Err(syn::Error::new(field.span(), "param unit_min and unit_max go together")),
+    }
+    Ok(attrs)
+}
+
+fn fixed_value_name(field: &syn::Ident) -> syn::Ident {
+    syn::Ident::new(&format!("{}_fixed", field), field.span())
+}
+
+fn smoother_name(field: &syn::Ident) -> syn::Ident {
+    syn::Ident::new(&format!("{}_smoother", field), field.span())
+}
+
+// Macros only see tokens, so this is a syntactic check: `f32` itself or the
+// fully qualified `std::primitive::f32` / `core::primitive::f32`. A type that is
+// merely named `f32` in some other module is rejected.
+fn is_f32(ty: &syn::Type) -> bool {
+    let syn::Type::Path(type_path) = ty else {
+        return false;
+    };
+    if type_path.qself.is_some() {
+        return false;
+    }
+    let segments: Vec<_> = type_path.path.segments.iter()
+        .map(|segment| match segment.arguments {
+            syn::PathArguments::None => Some(segment.ident.to_string()),
+            _ => None,
+        })
+        .collect::<Option<_>>()
+        .unwrap_or_default();
+    match segments.iter().map(String::as_str).collect::<Vec<_>>().as_slice() {
+        ["f32"] => type_path.path.leading_colon.is_none(),
+        ["std" | "core", "primitive", "f32"] => true,
+        _ => false,
+    }
+}
+
+/// Generates the modulation runtime and accessor for a struct of `f32` parameters.
+///
+/// Every field must be written as `f32` (or `std::primitive::f32` /
+/// `core::primitive::f32`). The check is purely syntactic, so a type alias such as
+/// `type Amp = f32;` is rejected unless the field is marked `#[param(raw)]`; raw
+/// fields skip the check and are instead verified to be `f32` by the compiler.
+///
+/// Field options, written `#[param(...)]`:
+/// - `mod_limit = x` clamps the summed modulation to `-x..=x` before it is added to the base
+/// - `default = x` sets the base value the runtime starts from (otherwise `min`, or `0.0`)
+/// - `min = x` / `max = x` narrow the range the modulated value is clamped to
+///   (otherwise `0.0..=1.0`), reported by `Parameters::param_range`
+/// - `smooth_ms = x` glides the base value toward new settings with a one-pole
+///   smoother of that time constant; modulation is added after smoothing, unsmoothed
+/// - `unit_min = x` / `unit_max = x` give the natural values (Hz, dB, ms) that
+///   normalized 0.0 and 1.0 stand for, used by `Parameters::to_normalized` and
+///   `Parameters::from_normalized`; without them the natural unit is the normalized value
+/// - `exp` makes that mapping exponential rather than linear, for frequencies and times
+/// - `block_rate` computes the modulated value once per block, at its first sample,
+///   and holds it, for parameters that are expensive to react to (filter coefficients)
+/// - `audio_rate` marks a parameter meant to be modulated at audio rate, like an FM
+///   or PM index. It's computed every sample, as fields are by default, and can't be
+///   combined with `block_rate`
+/// - `raw` accepts an alias of `f32` in place of the literal type
+///
+/// The runtime gets a `set_<field>` method per field, clamped to the field's range,
+/// as a typed alternative to `Runtime::set_base`; reach it with `Runtime::parameters_mut`.
+///
+/// `#[parameters(serde)]` also derives `Serialize`/`Deserialize` on the struct so its
+/// base values can be stored as presets. It requires the ceres `serde` feature.
 #[proc_macro_attribute]
-pub fn parameters(_args: TokenStream, input: TokenStream) -> TokenStream {
-    let input = parse_macro_input!(input as DeriveInput);
+pub fn parameters(args: TokenStream, input: TokenStream) -> TokenStream {
+    let mut derive_serde = false;
+    let args_parser = syn::meta::parser(|meta| {
+        if meta.path.is_ident("serde") {
+            derive_serde = true;
+            Ok(())
+        } else {
+            Err(meta.error("unknown parameters option"))
+        }
+    });
+    parse_macro_input!(args with args_parser);
 
+    let mut input = parse_macro_input!(input as DeriveInput);
+
+    // Read the per-field options, then strip `#[param]` so the struct we emit compiles
+    let mut field_attrs = Vec::new();
+    if let Data::Struct(data) = &mut input.data {
+        for field in data.fields.iter_mut() {
+            match parse_param_attrs(field) {
+                Ok(attrs) => field_attrs.push(attrs),
+                Err(err) => return err.to_compile_error().into(),
+            }
+            field.attrs.retain(|attr| !attr.path().is_ident("param"));
+        }
+    }
+
+    let vis = &input.vis;
     let struct_name = &input.ident;
     let runtime_name = syn::Ident::new(&format!("{}Runtime", struct_name), struct_name.span());
     let accessor_name = syn::Ident::new(&format!("{}Accessor", struct_name), struct_name.span());
@@ -23,24 +238,23 @@ pub fn parameters(_args: TokenStream, input: TokenStream) -> TokenStream {
     };
     
     // Validate f32 fields
-    for field in fields.iter() {
+    for (field, attrs) in fields.iter().zip(field_attrs.iter()) {
         let field_name = field.ident.as_ref().unwrap();
-        if let syn::Type::Path(type_path) = &field.ty {
-            if let Some(segment) = type_path.path.segments.last() {
-                if segment.ident != "f32" {
-                    return syn::Error::new(
-                        field.span(), 
-                        format!("Parameter field '{}' must be f32", field_name)
-                    ).to_compile_error().into();
-                }
-            }
-        } else {
+        if !attrs.raw && !is_f32(&field.ty) {
             return syn::Error::new(
-                field.span(), 
-                format!("Parameter field '{}' must be f32", field_name)
+                field.ty.span(), 
+                format!("Parameter field '{}' must be f32 (use #[param(raw)] for an alias of f32)", field_name)
             ).to_compile_error().into();
         }
     }
+
+    // Raw fields are checked by the type system instead: this only compiles if the type is f32
+    let raw_type_checks = fields.iter().zip(field_attrs.iter())
+        .filter(|(_, attrs)| attrs.raw)
+        .map(|(field, _)| {
+            let ty = &field.ty;
+            quote_spanned! { ty.span() => const _: fn(#ty) -> f32 = |value| value; }
+        });
     
     let field_names: Vec<_> = fields.iter().map(|f| &f.ident).collect();
     
@@ -50,15 +264,15 @@ pub fn parameters(_args: TokenStream, input: TokenStream) -> TokenStream {
     }).collect();
     
     let mod_fields = mod_field_names.iter().map(|mod_name| {
-        quote! { #mod_name: Option<::ceres::ModulationRouting> }
+        quote! { #mod_name: ::ceres::ParamModulation }
     });
     
     // Generate route methods
     let route_methods = field_names.iter().zip(mod_field_names.iter()).map(|(name, mod_name)| {
         let method_name = syn::Ident::new(&format!("route_{}", name.as_ref().unwrap()), name.span());
         quote! {
-            fn #method_name(&mut self, source_index: usize, amount: f32) {
-                self.#mod_name = Some(::ceres::ModulationRouting { source_index, amount });
+            fn #method_name(&mut self, routing: ::ceres::ModulationRouting) {
+                self.#mod_name.route(routing);
             }
         }
     });
@@ -67,31 +281,165 @@ pub fn parameters(_args: TokenStream, input: TokenStream) -> TokenStream {
     let route_arms = field_names.iter().zip(mod_field_names.iter()).map(|(name, _)| {
         let name_str = name.as_ref().unwrap().to_string();
         let method_name = syn::Ident::new(&format!("route_{}", name.as_ref().unwrap()), name.span());
-        quote! { #name_str => self.#method_name(source_index, amount) }
+        quote! { #name_str => { self.#method_name(routing); true } }
+    });
+    
+    // Generate set_param_map match arms
+    let map_arms = field_names.iter().zip(mod_field_names.iter()).map(|(name, mod_name)| {
+        let name_str = name.as_ref().unwrap().to_string();
+        quote! { #name_str => { self.#mod_name.set_map(map); true } }
+    });
+    
+    // Typed counterparts of `set_base`, clamped like the computed values
+    let setters = field_names.iter().zip(field_attrs.iter()).map(|(name, attrs)| {
+        let setter = syn::Ident::new(&format!("set_{}", name.as_ref().unwrap()), name.span());
+        let (min, max) = attrs.range();
+        quote! {
+            #vis fn #setter(&mut self, value: f32) {
+                self.base.#name = value.clamp(#min, #max);
+            }
+        }
+    });
+
+    let base_arms = field_names.iter().map(|name| {
+        let name_str = name.as_ref().unwrap().to_string();
+        quote! { #name_str => { self.base.#name = value; true } }
+    });
+
+    let unroute_arms = field_names.iter().zip(mod_field_names.iter()).map(|(name, mod_name)| {
+        let name_str = name.as_ref().unwrap().to_string();
+        quote! { #name_str => { self.#mod_name.unroute(source_index); true } }
+    });
+
+    let modulated_arms = field_names.iter().zip(mod_field_names.iter()).map(|(name, mod_name)| {
+        let name_str = name.as_ref().unwrap().to_string();
+        quote! { #name_str => Some(!self.#mod_name.is_empty()) }
     });
     
     // Generate update logic
-    let update_fields = field_names.iter().zip(mod_field_names.iter()).map(|(name, mod_name)| {
+    let update_fields = field_names.iter().zip(mod_field_names.iter()).zip(field_attrs.iter()).map(|((name, mod_name), attrs)| {
+        // Bound the modulation contribution itself before it is added to the base value
+        let limit_modulation = attrs.mod_limit.map(|limit| {
+            quote! { let #name = #name.clamp(-#limit, #limit); }
+        });
+        let fixed_name = fixed_value_name(name.as_ref().unwrap());
+        let (min, max) = attrs.range();
+        let base = match attrs.smooth_ms {
+            Some(_) => {
+                let smoother = smoother_name(name.as_ref().unwrap());
+                quote! { self.#smoother.next(self.base.#name) }
+            }
+            None => quote! { self.base.#name },
+        };
+        // Block-rate fields hold what the first sample computed
+        let held = attrs.block_rate.then(|| quote! { None if i > 0 => self.computed_values[0].#name, });
         quote! {
-            let #name = self.#mod_name
-                .as_ref()
-                .map(|routing| {
-                    let modulator_value = sources[routing.source_index].get_value(i);
-                    modulator_value * routing.amount
-                })
-                .unwrap_or(0.0);
-            let #name = (self.base.#name + #name).clamp(0.0, 1.0);
+            let #name = match #fixed_name {
+                Some(value) => value,
+                #held
+                None => {
+                    let (#name, pinned) = self.#mod_name.evaluate(sources, i);
+                    #limit_modulation
+                    let #name = pinned.unwrap_or(#base + #name).clamp(#min, #max);
+                    self.#mod_name.apply_map(#name)
+                }
+            };
         }
     });
+
+    // Unrouted fields hold the same value for the whole block, so it's computed once
+    let fixed_fields = field_names.iter().zip(mod_field_names.iter()).zip(field_attrs.iter()).map(|((name, mod_name), attrs)| {
+        let fixed_name = fixed_value_name(name.as_ref().unwrap());
+        let (min, max) = attrs.range();
+        // A smoothed field is only fixed once it has reached its base
+        let settled = attrs.smooth_ms.map(|_| {
+            let smoother = smoother_name(name.as_ref().unwrap());
+            quote! { && self.#smoother.settle(self.base.#name) }
+        });
+        quote! {
+            let #fixed_name = if self.#mod_name.is_empty() #settled {
+                Some(self.#mod_name.apply_map(self.base.#name.clamp(#min, #max)))
+            } else {
+                None
+            };
+        }
+    });
+    let fixed_names: Vec<_> = field_names.iter().map(|name| fixed_value_name(name.as_ref().unwrap())).collect();
+
+    let (smoother_names, smooth_times): (Vec<_>, Vec<_>) = field_names.iter().zip(field_attrs.iter())
+        .filter_map(|(name, attrs)| Some((smoother_name(name.as_ref().unwrap()), attrs.smooth_ms?)))
+        .unzip();
     
+    let name_strs: Vec<_> = field_names.iter().map(|name| name.as_ref().unwrap().to_string()).collect();
+    let range_arms = name_strs.iter().zip(field_attrs.iter()).map(|(name_str, attrs)| {
+        let (min, max) = attrs.range();
+        quote! { #name_str => Some((#min, #max)) }
+    });
+
+    // Natural units are clamped to the declared range, so both directions stay in 0..1
+    let (to_normalized_arms, from_normalized_arms): (Vec<_>, Vec<_>) = name_strs.iter().zip(field_attrs.iter()).map(|(name_str, attrs)| {
+        let Some((unit_min, unit_max)) = attrs.unit_min.zip(attrs.unit_max) else {
+            return (
+                quote! { #name_str => Some(natural.clamp(0.0, 1.0)) },
+                quote! { #name_str => Some(normalized.clamp(0.0, 1.0)) },
+            );
+        };
+        let (low, high) = (unit_min.min(unit_max), unit_min.max(unit_max));
+        if attrs.exp {
+            let log_ratio = (unit_max / unit_min).ln();
+            (
+                quote! { #name_str => Some((natural.clamp(#low, #high) / #unit_min).ln() / #log_ratio) },
+                quote! { #name_str => Some(#unit_min * (normalized.clamp(0.0, 1.0) * #log_ratio).exp()) },
+            )
+        } else {
+            let span = unit_max - unit_min;
+            (
+                quote! { #name_str => Some((natural.clamp(#low, #high) - #unit_min) / #span) },
+                quote! { #name_str => Some(#unit_min + normalized.clamp(0.0, 1.0) * #span) },
+            )
+        }
+    }).unzip();
+
+    let defaults = field_attrs.iter().map(|attrs| attrs.default.unwrap_or(attrs.range().0));
+
+    // Missing fields fall back to their defaults so older presets still load
+    let serde_derive = if !derive_serde {
+        quote! {}
+    } else if cfg!(feature = "serde") {
+        quote! {
+            #[derive(::ceres::serde::Serialize, ::ceres::serde::Deserialize)]
+            #[serde(crate = "::ceres::serde", default)]
+        }
+    } else {
+        return syn::Error::new(struct_name.span(), "#[parameters(serde)] requires the ceres `serde` feature")
+            .to_compile_error().into();
+    };
+
     let expanded = quote! {
-        #[derive(Clone, Copy, Default)]
+        #[derive(Clone, Copy)]
+        #serde_derive
         #input
+
+        impl Default for #struct_name {
+            fn default() -> Self {
+                Self {
+                    #(#field_names: #defaults,)*
+                }
+            }
+        }
+
+        #(#raw_type_checks)*
         
-        struct #runtime_name<E> {
+        #vis struct #runtime_name<E> {
             base: #struct_name,
             #(#mod_fields,)*
+            #(#smoother_names: ::ceres::ParamSmoother,)*
             computed_values: [#struct_name; ::ceres::BUFFER_SIZE],
+            // How many of `computed_values` the last update computed
+            len: usize,
+            // Smoothed peak modulation per field, for `activity`
+            activity: #struct_name,
+            _phantom: ::std::marker::PhantomData<E>,
         }
         
         impl<E> #runtime_name<E> {
@@ -99,39 +447,166 @@ pub fn parameters(_args: TokenStream, input: TokenStream) -> TokenStream {
                 let base = #struct_name::default();
                 Self {
                     base,
-                    #(#mod_field_names: None,)*
+                    #(#mod_field_names: ::ceres::ParamModulation::new(),)*
+                    #(#smoother_names: ::ceres::ParamSmoother::new(#smooth_times),)*
                     computed_values: [base; ::ceres::BUFFER_SIZE],
+                    len: ::ceres::BUFFER_SIZE,
+                    activity: #struct_name { #(#field_names: 0.0),* },
+                    _phantom: ::std::marker::PhantomData,
                 }
             }
             
             #(#route_methods)*
+
+            #(#setters)*
+
+            // Follows each field's peak modulation over the block's `len` samples:
+            // quickly on the way up, over about `ACTIVITY_RELEASE_MS` on the way down
+            fn update_activity(&mut self, sample_rate: f32, len: usize) {
+                const ACTIVITY_RELEASE_MS: f32 = 150.0;
+                let release = 1.0 - (-(len as f32) / (ACTIVITY_RELEASE_MS * 0.001 * sample_rate.max(1.0))).exp();
+                #(
+                    let peak = if self.#mod_field_names.is_empty() {
+                        0.0
+                    } else {
+                        self.computed_values[..len].iter()
+                            .map(|values| (values.#field_names - self.base.#field_names).abs())
+                            .fold(0.0f32, f32::max)
+                    };
+                    let activity = &mut self.activity.#field_names;
+                    *activity = if peak > *activity { peak } else { *activity + (peak - *activity) * release };
+                )*
+            }
         }
         
         impl<E: Send + 'static> ::ceres::ParameterRuntime<E> for #runtime_name<E> {
-            fn update(&mut self, sources: &[Box<dyn ::ceres::Modulator<E>>]) {
-                for i in 0..::ceres::BUFFER_SIZE {
+            // A struct without fields makes the fast-path pattern irrefutable
+            #[allow(irrefutable_let_patterns)]
+            fn update(&mut self, sample_rate: f32, sources: &[Box<dyn ::ceres::Modulator<E>>], len: usize) {
+                let len = len.min(::ceres::BUFFER_SIZE);
+                self.len = len;
+                #(self.#smoother_names.set_sample_rate(sample_rate);)*
+                #(#fixed_fields)*
+                if let (#(Some(#field_names),)*) = (#(#fixed_names,)*) {
+                    self.computed_values = [#struct_name { #(#field_names),* }; ::ceres::BUFFER_SIZE];
+                    self.update_activity(sample_rate, len);
+                    return;
+                }
+                for i in 0..len {
                     #(#update_fields)*
                     self.computed_values[i] = #struct_name {
                         #(#field_names: #field_names),*
                     };
                 }
+                self.update_activity(sample_rate, len);
             }
             
-            fn route_parameter(&mut self, param_name: &str, source_index: usize, amount: f32) {
+            fn route_parameter(&mut self, param_name: &str, routing: ::ceres::ModulationRouting) -> bool {
                 match param_name {
                     #(#route_arms,)*
-                    _ => {}
+                    _ => false,
+                }
+            }
+
+            fn set_param_map(&mut self, param_name: &str, map: ::ceres::ParamMap) -> bool {
+                match param_name {
+                    #(#map_arms,)*
+                    _ => false,
+                }
+            }
+
+            fn set_base(&mut self, param_name: &str, value: f32) -> bool {
+                match param_name {
+                    #(#base_arms,)*
+                    _ => false,
+                }
+            }
+
+            fn unroute_parameter(&mut self, param_name: &str, source_index: usize) -> bool {
+                match param_name {
+                    #(#unroute_arms,)*
+                    _ => false,
+                }
+            }
+
+            fn is_modulated(&self, param_name: &str) -> Option<bool> {
+                match param_name {
+                    #(#modulated_arms,)*
+                    _ => None,
+                }
+            }
+
+            fn set_source_depth(&mut self, source_index: usize, depth: f32) {
+                #(self.#mod_field_names.set_source_depth(source_index, depth);)*
+            }
+
+            fn reserve_routings(&mut self, sources: usize) {
+                #(self.#mod_field_names.reserve(sources);)*
+            }
+
+            fn routings(&self) -> Vec<(String, usize)> {
+                let mut routings = Vec::new();
+                #(routings.extend(self.#mod_field_names.sources().map(|source| (#name_strs.to_string(), source)));)*
+                routings
+            }
+
+            fn value(&self, param_name: &str) -> Option<f32> {
+                match param_name {
+                    #(#name_strs => Some(self.computed_values[0].#field_names),)*
+                    _ => None,
                 }
             }
         }
         
-        struct #accessor_name<'a> {
+        #vis struct #accessor_name<'a> {
+            base: &'a #struct_name,
             values: &'a [#struct_name; ::ceres::BUFFER_SIZE],
+            len: usize,
+            activity: &'a #struct_name,
         }
         
         impl<'a> #accessor_name<'a> {
-            fn new(values: &'a [#struct_name; ::ceres::BUFFER_SIZE]) -> Self {
-                Self { values }
+            fn new(base: &'a #struct_name, values: &'a [#struct_name; ::ceres::BUFFER_SIZE], len: usize, activity: &'a #struct_name) -> Self {
+                Self { base, values, len, activity }
+            }
+
+            /// The un-modulated values, e.g. the knob positions a UI shows next to the
+            /// modulated ones.
+            #vis fn base(&self) -> #struct_name {
+                *self.base
+            }
+
+            /// How far modulation moved `param_name` from its base at each sample of the
+            /// block (computed minus base), or `None` if there's no such parameter.
+            #vis fn modulation(&self, param_name: &str) -> Option<[f32; ::ceres::BUFFER_SIZE]> {
+                match param_name {
+                    #(#name_strs => Some(::std::array::from_fn(|i| self.values[i].#field_names - self.base.#field_names)),)*
+                    _ => None,
+                }
+            }
+
+            /// A smoothed measure of how strongly `param_name` is being modulated: the
+            /// peak distance from its base, held briefly and easing back toward 0 when
+            /// modulation stops. Meant for animating a modulation indicator; `None` if
+            /// there's no such parameter.
+            #vis fn activity(&self, param_name: &str) -> Option<f32> {
+                match param_name {
+                    #(#name_strs => Some(self.activity.#field_names),)*
+                    _ => None,
+                }
+            }
+
+            /// Sample-accurate read: every parameter's value at `offset` samples into the block,
+            /// e.g. the offset at which an event fires.
+            #vis fn at(&self, offset: usize) -> #struct_name {
+                self.values[offset % ::ceres::BUFFER_SIZE]
+            }
+
+            /// Every sample's values in order, to zip with a block:
+            /// `for (out, params) in output.iter_mut().zip(params.iter())`. Only the
+            /// block's samples are included, so a short block yields fewer.
+            #vis fn iter(&self) -> ::std::slice::Iter<'a, #struct_name> {
+                self.values[..self.len].iter()
             }
         }
         
@@ -147,12 +622,37 @@ pub fn parameters(_args: TokenStream, input: TokenStream) -> TokenStream {
             type Accessor<'a, E> = #accessor_name<'a> where E: 'a;
             type Values = #struct_name;
             
-            fn create_runtime<E: Send>() -> Self::Runtime<E> {
+            fn create_runtime<E: Send + 'static>() -> Self::Runtime<E> {
                 #runtime_name::new()
             }
             
-            fn create_accessor<E: Send>(runtime: &Self::Runtime<E>) -> Self::Accessor<'_, E> {
-                #accessor_name::new(&runtime.computed_values)
+            fn create_accessor<E: Send + 'static>(runtime: &Self::Runtime<E>) -> Self::Accessor<'_, E> {
+                #accessor_name::new(&runtime.base, &runtime.computed_values, runtime.len, &runtime.activity)
+            }
+
+            fn param_names() -> Vec<String> {
+                vec![#(#name_strs.to_string()),*]
+            }
+
+            fn param_range(param_name: &str) -> Option<(f32, f32)> {
+                match param_name {
+                    #(#range_arms,)*
+                    _ => None,
+                }
+            }
+
+            fn to_normalized(param_name: &str, natural: f32) -> Option<f32> {
+                match param_name {
+                    #(#to_normalized_arms,)*
+                    _ => None,
+                }
+            }
+
+            fn from_normalized(param_name: &str, normalized: f32) -> Option<f32> {
+                match param_name {
+                    #(#from_normalized_arms,)*
+                    _ => None,
+                }
             }
         }
     };
@@ -0,0 +1,171 @@
+//! Audio device abstraction
+//!
+//! `Engine` only needs a sample rate and somewhere to register its callback, so
+//! the device side sits behind `AudioBackend`. `CpalBackend` talks to real
+//! hardware; `MockBackend` lets tests pull buffers through the callback by hand.
+
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+
+/// Fills one device buffer with output samples.
+pub type AudioCallback = Box<dyn FnMut(&mut [f32]) + Send>;
+
+/// Told about stream errors (underruns, a device going away) with a description.
+pub type ErrorCallback = Box<dyn FnMut(&str) + Send>;
+
+pub trait AudioBackend {
+    /// Rate the callback will be driven at.
+    fn sample_rate(&self) -> f32;
+    /// Interleaved channels in each buffer handed to the callback.
+    fn channels(&self) -> usize {
+        1
+    }
+    /// Registers `callback` with the device, and `on_error` for errors the stream
+    /// reports. The stream stays paused until `play`.
+    fn build_stream(self, callback: AudioCallback, on_error: ErrorCallback) -> Box<dyn AudioStream>;
+}
+
+/// A running device stream; dropping it closes the stream.
+pub trait AudioStream {
+    fn play(&self);
+    fn pause(&self);
+}
+
+// === cpal ===
+pub struct CpalBackend {
+    device: cpal::Device,
+    config: cpal::SupportedStreamConfig,
+}
+
+impl CpalBackend {
+    /// The default host's default output device, in its default configuration.
+    pub fn default_output() -> Self {
+        let host = cpal::default_host();
+        let device = host.default_output_device()
+            .ok_or("no output device available").unwrap();
+        let config = device.default_output_config().unwrap();
+        Self { device, config }
+    }
+}
+
+impl AudioBackend for CpalBackend {
+    fn sample_rate(&self) -> f32 {
+        self.config.sample_rate().0 as f32
+    }
+
+    fn channels(&self) -> usize {
+        self.config.channels() as usize
+    }
+
+    fn build_stream(self, mut callback: AudioCallback, mut on_error: ErrorCallback) -> Box<dyn AudioStream> {
+        let stream = self.device.build_output_stream(
+            &self.config.into(),
+            move |data: &mut [f32], _: &cpal::OutputCallbackInfo| callback(data),
+            move |err| on_error(&err.to_string()),
+            None,
+        ).unwrap();
+        Box::new(stream)
+    }
+}
+
+impl AudioStream for cpal::platform::Stream {
+    fn play(&self) {
+        StreamTrait::play(self).unwrap();
+    }
+
+    fn pause(&self) {
+        StreamTrait::pause(self).unwrap();
+    }
+}
+
+// === Mock ===
+/// A backend with no device behind it. Keep a clone before handing it to the
+/// engine, then call `render` to run the callback as a device would.
+#[derive(Clone)]
+pub struct MockBackend {
+    sample_rate: f32,
+    channels: usize,
+    callback: Arc<Mutex<Option<AudioCallback>>>,
+    on_error: Arc<Mutex<Option<ErrorCallback>>>,
+    playing: Arc<AtomicBool>,
+}
+
+struct MockStream {
+    playing: Arc<AtomicBool>,
+}
+
+impl MockBackend {
+    pub fn new(sample_rate: f32) -> Self {
+        Self {
+            sample_rate,
+            channels: 1,
+            callback: Arc::new(Mutex::new(None)),
+            on_error: Arc::new(Mutex::new(None)),
+            playing: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Reports `channels` interleaved channels instead of mono.
+    pub fn with_channels(mut self, channels: usize) -> Self {
+        self.channels = channels.max(1);
+        self
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.playing.load(Ordering::Acquire)
+    }
+
+    /// Requests `frames` frames from the engine, `frames * channels` interleaved
+    /// samples. Like a real device, a stream that hasn't been started produces
+    /// silence without invoking the callback.
+    pub fn render(&self, frames: usize) -> Vec<f32> {
+        let mut buffer = vec![0.0; frames * self.channels];
+        if self.is_playing() {
+            if let Some(callback) = self.callback.lock().unwrap().as_mut() {
+                callback(&mut buffer);
+            }
+        }
+        buffer
+    }
+
+    /// Reports a stream error as a device would, e.g. an underrun.
+    pub fn inject_error(&self, message: &str) {
+        if let Some(on_error) = self.on_error.lock().unwrap().as_mut() {
+            on_error(message);
+        }
+    }
+}
+
+impl AudioBackend for MockBackend {
+    fn sample_rate(&self) -> f32 {
+        self.sample_rate
+    }
+
+    fn channels(&self) -> usize {
+        self.channels
+    }
+
+    fn build_stream(self, callback: AudioCallback, on_error: ErrorCallback) -> Box<dyn AudioStream> {
+        *self.callback.lock().unwrap() = Some(callback);
+        *self.on_error.lock().unwrap() = Some(on_error);
+        Box::new(MockStream { playing: self.playing })
+    }
+}
+
+impl AudioStream for MockStream {
+    fn play(&self) {
+        self.playing.store(true, Ordering::Release);
+    }
+
+    fn pause(&self) {
+        self.playing.store(false, Ordering::Release);
+    }
+}
+
+// Like a closed device stream, a dropped one never plays again
+impl Drop for MockStream {
+    fn drop(&mut self) {
+        self.playing.store(false, Ordering::Release);
+    }
+}
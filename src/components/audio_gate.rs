@@ -0,0 +1,20 @@
+// Drives an `AudioGate` modulator from the signal passing through.
+use crate::core::*;
+use crate::modulators::AudioGate;
+
+/// Passes audio through unchanged and follows its level into a new `AudioGate`.
+/// Route the returned handle to gate envelopes or mutes elsewhere in the graph,
+/// and set its thresholds with `get_source_mut`.
+pub fn audio_gate<E>() -> impl FnOnce(&mut Builder<E>) -> (ComponentFn<E>, ModulatorHandle<AudioGate>)
+where
+    E: Clone + Send + 'static,
+{
+    move |builder: &mut Builder<E>| {
+        let gate = builder.use_modulator::<AudioGate>();
+        let component: ComponentFn<E> = Box::new(move |runtime, input, output, sample_rate| {
+            runtime.get_source_mut(&gate).process(input, sample_rate);
+            output.copy_from_slice(input);
+        });
+        (component, gate)
+    }
+}
@@ -0,0 +1,142 @@
+// Slow automatic gain for levelling sources to a target loudness.
+//
+// Unlike the compressor this follows the long-term RMS level rather than
+// transients: the level is averaged over a window, and the gain needed to bring
+// it to the target is approached with separate attack (turning down) and release
+// (turning up) times, never boosting past the max gain.
+use crate::core::*;
+use crate::parameters;
+use super::WithParams;
+use super::compressor::smoothing_coefficient;
+
+// Below this the input counts as silence and the gain is held, so pauses
+// aren't boosted into noise
+const SILENCE_DB: f32 = -70.0;
+
+#[parameters]
+pub struct AutoGainParams {
+    /// -40..0 dBFS RMS; -18 dB by default
+    #[param(default = 0.55)]
+    pub target: f32,
+    /// 0..40 dB of boost at most
+    #[param(default = 0.5)]
+    pub max_gain: f32,
+    /// 10..1000 ms to turn down, exponential
+    #[param(default = 0.5)]
+    pub attack: f32,
+    /// 100..10000 ms to turn up, exponential
+    #[param(default = 0.5)]
+    pub release: f32,
+}
+
+impl AutoGainParams {
+    pub fn target_db(&self) -> f32 {
+        -40.0 + self.target * 40.0
+    }
+
+    pub fn max_gain_db(&self) -> f32 {
+        self.max_gain * 40.0
+    }
+
+    pub fn attack_ms(&self) -> f32 {
+        10.0 * 100f32.powf(self.attack)
+    }
+
+    pub fn release_ms(&self) -> f32 {
+        100.0 * 100f32.powf(self.release)
+    }
+}
+
+/// Level follower and gain state for one channel.
+#[derive(Clone, Copy, Debug)]
+pub struct AutoGain {
+    window_ms: f32,
+    sample_rate: f32,
+    window: f32,
+    mean_square: f32,
+    gain_db: f32,
+}
+
+impl AutoGain {
+    pub fn new(window_ms: f32, sample_rate: f32) -> Self {
+        Self { window_ms, sample_rate, window: smoothing_coefficient(window_ms, sample_rate), mean_square: 0.0, gain_db: 0.0 }
+    }
+
+    pub fn sample_rate(&self) -> f32 {
+        self.sample_rate
+    }
+
+    /// Recomputes the window for `sample_rate`, keeping the measured level and gain.
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.sample_rate = sample_rate;
+        self.window = smoothing_coefficient(self.window_ms, sample_rate);
+    }
+
+    /// Current gain in dB.
+    pub fn gain_db(&self) -> f32 {
+        self.gain_db
+    }
+
+    /// `attack` and `release` are per-sample smoothing coefficients.
+    pub fn process(&mut self, sample: f32, target_db: f32, max_gain_db: f32, attack: f32, release: f32) -> f32 {
+        self.mean_square = self.window * self.mean_square + (1.0 - self.window) * sample * sample;
+        let level_db = 10.0 * (self.mean_square + 1e-12).log10();
+        if level_db > SILENCE_DB {
+            let wanted_db = (target_db - level_db).min(max_gain_db);
+            let coefficient = if wanted_db < self.gain_db { attack } else { release };
+            self.gain_db = coefficient * self.gain_db + (1.0 - coefficient) * wanted_db;
+        }
+        sample * 10f32.powf(self.gain_db / 20.0)
+    }
+}
+
+/// Levels its input to the target RMS, measured over `window_ms`.
+pub fn auto_gain<E>(window_ms: f32) -> impl FnOnce(&mut Builder<E>) -> ComponentFn<E>
+where
+    E: Clone + Send + 'static,
+{
+    move |builder: &mut Builder<E>| auto_gain_with_params(window_ms)(builder).0
+}
+
+pub fn auto_gain_with_params<E>(window_ms: f32) -> impl FnOnce(&mut Builder<E>) -> WithParams<E, AutoGainParams>
+where
+    E: Clone + Send + 'static,
+{
+    move |builder: &mut Builder<E>| {
+        let params_handle = builder.use_parameters::<AutoGainParams>();
+        // Retuned if the stream runs at another rate
+        let mut state = AutoGain::new(window_ms, 48_000.0);
+
+        let component: ComponentFn<E> = Box::new(move |runtime, input, output, sample_rate| {
+            let params = runtime.get_parameters(&params_handle)[0];
+            if sample_rate != state.sample_rate() {
+                state.set_sample_rate(sample_rate);
+            }
+            let target_db = params.target_db();
+            let max_gain_db = params.max_gain_db();
+            let attack = smoothing_coefficient(params.attack_ms(), sample_rate);
+            let release = smoothing_coefficient(params.release_ms(), sample_rate);
+
+            for (out, &sample) in output.iter_mut().zip(input.iter()) {
+                *out = state.process(sample, target_db, max_gain_db, attack, release);
+            }
+        });
+        (component, params_handle)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::testing::{process, rms, sine};
+
+    #[test]
+    fn quiet_input_settles_at_the_target_level() {
+        let level = 10f32.powf(-37.0 / 20.0) * std::f32::consts::SQRT_2;
+        let input: Vec<f32> = sine(1_000.0, 480_000).iter().map(|sample| sample * level).collect();
+        let output = process(auto_gain(50.0), &input);
+        // Defaults target -18 dBFS RMS
+        let settled_db = 20.0 * rms(&output[output.len() - 48_000..]).log10();
+        assert!((settled_db + 18.0).abs() < 0.5, "{}", settled_db);
+    }
+}
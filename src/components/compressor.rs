@@ -0,0 +1,136 @@
+// Feed-forward soft-knee compressor.
+//
+// The gain computer works in decibels on the instantaneous input level, and the
+// resulting gain reduction is smoothed with separate attack and release times.
+// Parameters are normalized; the `*_db`/`*_ms` helpers map them to natural units.
+use crate::core::*;
+use crate::parameters;
+use super::WithParams;
+
+#[parameters]
+pub struct CompressorParams {
+    /// -60..0 dB
+    #[param(default = 0.7, unit_min = -60.0, unit_max = 0.0)]
+    pub threshold: f32,
+    /// 1:1..20:1, exponential
+    #[param(default = 0.4628, unit_min = 1.0, unit_max = 20.0, exp)]
+    pub ratio: f32,
+    /// 0.1..100 ms, exponential
+    #[param(default = 0.6667, unit_min = 0.1, unit_max = 100.0, exp)]
+    pub attack: f32,
+    /// 10..1000 ms, exponential
+    #[param(default = 0.5, unit_min = 10.0, unit_max = 1000.0, exp)]
+    pub release: f32,
+    /// 0..24 dB
+    #[param(default = 0.25, unit_min = 0.0, unit_max = 24.0)]
+    pub knee: f32,
+    /// 0..24 dB
+    #[param(unit_min = 0.0, unit_max = 24.0)]
+    pub makeup: f32,
+}
+
+impl CompressorParams {
+    pub fn threshold_db(&self) -> f32 {
+        -60.0 + self.threshold * 60.0
+    }
+
+    pub fn ratio_value(&self) -> f32 {
+        20f32.powf(self.ratio)
+    }
+
+    pub fn attack_ms(&self) -> f32 {
+        0.1 * 1000f32.powf(self.attack)
+    }
+
+    pub fn release_ms(&self) -> f32 {
+        10.0 * 100f32.powf(self.release)
+    }
+
+    pub fn knee_db(&self) -> f32 {
+        self.knee * 24.0
+    }
+
+    pub fn makeup_db(&self) -> f32 {
+        self.makeup * 24.0
+    }
+}
+
+/// Static gain curve: the gain change in dB for an input level in dB.
+pub fn compressor_gain_db(level_db: f32, threshold_db: f32, ratio: f32, knee_db: f32) -> f32 {
+    let overshoot = level_db - threshold_db;
+    if 2.0 * overshoot <= -knee_db {
+        0.0
+    } else if 2.0 * overshoot.abs() < knee_db {
+        let x = overshoot + knee_db / 2.0;
+        (1.0 / ratio - 1.0) * x * x / (2.0 * knee_db)
+    } else {
+        (1.0 / ratio - 1.0) * overshoot
+    }
+}
+
+pub(super) fn smoothing_coefficient(time_ms: f32, sample_rate: f32) -> f32 {
+    (-1.0 / (time_ms * 0.001 * sample_rate)).exp()
+}
+
+pub fn compressor<E>() -> impl FnOnce(&mut Builder<E>) -> ComponentFn<E>
+where
+    E: Clone + Send + 'static,
+{
+    move |builder: &mut Builder<E>| compressor_with_params()(builder).0
+}
+
+pub fn compressor_with_params<E>() -> impl FnOnce(&mut Builder<E>) -> WithParams<E, CompressorParams>
+where
+    E: Clone + Send + 'static,
+{
+    move |builder: &mut Builder<E>| {
+        let params_handle = builder.use_parameters::<CompressorParams>();
+        // Smoothed gain reduction in dB (always <= 0)
+        let mut envelope_db = 0.0f32;
+
+        let component: ComponentFn<E> = Box::new(move |runtime, input, output, sample_rate| {
+            let params = runtime.get_parameters(&params_handle)[0];
+            let threshold_db = params.threshold_db();
+            let ratio = params.ratio_value();
+            let knee_db = params.knee_db();
+            let makeup_db = params.makeup_db();
+            let attack = smoothing_coefficient(params.attack_ms(), sample_rate);
+            let release = smoothing_coefficient(params.release_ms(), sample_rate);
+
+            for (out, &sample) in output.iter_mut().zip(input.iter()) {
+                let level_db = 20.0 * (sample.abs() + 1e-9).log10();
+                let target_db = compressor_gain_db(level_db, threshold_db, ratio, knee_db);
+                let coefficient = if target_db < envelope_db { attack } else { release };
+                envelope_db = coefficient * envelope_db + (1.0 - coefficient) * target_db;
+                *out = sample * 10f32.powf((envelope_db + makeup_db) / 20.0);
+            }
+        });
+        (component, params_handle)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::testing::process;
+
+    #[test]
+    fn steady_state_reduction_follows_the_ratio() {
+        let params = CompressorParams::default();
+        let level_db = -6.0;
+        // Well past the knee, so the plain ratio applies
+        let overshoot = level_db - params.threshold_db();
+        assert!(overshoot > params.knee_db());
+        let expected_db = level_db + overshoot * (1.0 / params.ratio_value() - 1.0);
+
+        let input = vec![10f32.powf(level_db / 20.0); 48_000];
+        let output = process(compressor(), &input);
+        let output_db = 20.0 * output.last().unwrap().log10();
+        assert!((output_db - expected_db).abs() < 0.1, "{output_db} dB, expected {expected_db} dB");
+    }
+
+    #[test]
+    fn below_threshold_passes_unchanged() {
+        assert_eq!(compressor_gain_db(-40.0, -18.0, 4.0, 6.0), 0.0);
+    }
+}
@@ -0,0 +1,174 @@
+// Impulse-response convolution using uniformly partitioned overlap-add.
+//
+// The impulse response is split into `block_size` partitions whose spectra are
+// computed once up front. Each full block of input is transformed, pushed into a
+// frequency-domain delay line, multiplied against every partition, summed, and
+// transformed back; the second half of the result overlaps into the next block.
+// Collecting a full block before it can be convolved costs `block_size` samples
+// of latency.
+use std::sync::Arc;
+use rustfft::num_complex::Complex;
+use rustfft::{Fft, FftPlanner};
+use crate::core::*;
+
+pub struct Convolver {
+    block_size: usize,
+    forward: Arc<dyn Fft<f32>>,
+    inverse: Arc<dyn Fft<f32>>,
+    scratch: Vec<Complex<f32>>,
+
+    // Spectrum of each impulse response partition
+    partitions: Vec<Vec<Complex<f32>>>,
+    // Spectra of the most recent input blocks, newest at `delay_line_head`
+    delay_line: Vec<Vec<Complex<f32>>>,
+    delay_line_head: usize,
+
+    spectrum: Vec<Complex<f32>>,
+    input_block: Vec<f32>,
+    output_block: Vec<f32>,
+    overlap: Vec<f32>,
+    position: usize,
+}
+
+impl Convolver {
+    pub fn new(impulse_response: &[f32], block_size: usize) -> Self {
+        assert!(block_size > 0, "convolver block size must be non-zero");
+        let fft_size = block_size * 2;
+        let mut planner = FftPlanner::new();
+        let forward = planner.plan_fft_forward(fft_size);
+        let inverse = planner.plan_fft_inverse(fft_size);
+        let scratch_len = forward.get_inplace_scratch_len().max(inverse.get_inplace_scratch_len());
+        let mut scratch = vec![Complex::new(0.0, 0.0); scratch_len];
+
+        let partitions: Vec<_> = impulse_response
+            .chunks(block_size)
+            .map(|chunk| {
+                let mut spectrum = vec![Complex::new(0.0, 0.0); fft_size];
+                for (bin, &sample) in spectrum.iter_mut().zip(chunk) {
+                    bin.re = sample;
+                }
+                forward.process_with_scratch(&mut spectrum, &mut scratch);
+                spectrum
+            })
+            .collect();
+        let delay_line = vec![vec![Complex::new(0.0, 0.0); fft_size]; partitions.len().max(1)];
+
+        Self {
+            block_size,
+            forward,
+            inverse,
+            scratch,
+            partitions,
+            delay_line,
+            delay_line_head: 0,
+            spectrum: vec![Complex::new(0.0, 0.0); fft_size],
+            input_block: vec![0.0; block_size],
+            output_block: vec![0.0; block_size],
+            overlap: vec![0.0; block_size],
+            position: 0,
+        }
+    }
+
+    /// Delay in samples between an input sample and its convolved output.
+    pub fn latency(&self) -> usize {
+        self.block_size
+    }
+
+    pub fn process(&mut self, input: &[f32], output: &mut [f32]) {
+        for (out, &sample) in output.iter_mut().zip(input.iter()) {
+            self.input_block[self.position] = sample;
+            *out = self.output_block[self.position];
+            self.position += 1;
+            if self.position == self.block_size {
+                self.position = 0;
+                self.convolve_block();
+            }
+        }
+    }
+
+    pub fn reset(&mut self) {
+        for spectrum in &mut self.delay_line {
+            spectrum.fill(Complex::new(0.0, 0.0));
+        }
+        self.input_block.fill(0.0);
+        self.output_block.fill(0.0);
+        self.overlap.fill(0.0);
+        self.position = 0;
+    }
+
+    fn convolve_block(&mut self) {
+        let fft_size = self.block_size * 2;
+        let slots = self.delay_line.len();
+
+        // Transform the newest input block into the head of the delay line
+        self.delay_line_head = (self.delay_line_head + 1) % slots;
+        let newest = &mut self.delay_line[self.delay_line_head];
+        for (i, bin) in newest.iter_mut().enumerate() {
+            *bin = Complex::new(self.input_block.get(i).copied().unwrap_or(0.0), 0.0);
+        }
+        self.forward.process_with_scratch(newest, &mut self.scratch);
+
+        // Partition p is paired with the input block from p blocks ago
+        self.spectrum.fill(Complex::new(0.0, 0.0));
+        for (age, partition) in self.partitions.iter().enumerate() {
+            let past = &self.delay_line[(self.delay_line_head + slots - age) % slots];
+            for ((acc, &x), &h) in self.spectrum.iter_mut().zip(past.iter()).zip(partition.iter()) {
+                *acc += x * h;
+            }
+        }
+        self.inverse.process_with_scratch(&mut self.spectrum, &mut self.scratch);
+
+        let scale = 1.0 / fft_size as f32;
+        for i in 0..self.block_size {
+            self.output_block[i] = self.spectrum[i].re * scale + self.overlap[i];
+            self.overlap[i] = self.spectrum[i + self.block_size].re * scale;
+        }
+    }
+}
+
+/// Convolves its input with `impulse_response`, delayed by `BUFFER_SIZE` samples.
+pub fn convolver<E>(impulse_response: Vec<f32>) -> impl FnOnce(&mut Builder<E>) -> ComponentFn<E>
+where
+    E: Clone + Send + 'static,
+{
+    move |builder: &mut Builder<E>| -> ComponentFn<E> {
+        let mut convolver = Convolver::new(&impulse_response, BUFFER_SIZE);
+        builder.report_latency(convolver.latency());
+
+        Box::new(move |_runtime, input, output, _sample_rate| {
+            convolver.process(input, output);
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::testing::process;
+
+    #[test]
+    fn impulse_comes_out_as_the_impulse_response() {
+        // Longer than a block, so it spans several partitions
+        let impulse_response: Vec<f32> = (0..150).map(|i| (i as f32 * 0.37).sin() * 0.98f32.powi(i)).collect();
+        let mut convolver = Convolver::new(&impulse_response, 64);
+        let mut input = vec![0.0; 512];
+        input[0] = 1.0;
+        let mut output = vec![0.0; 512];
+        convolver.process(&input, &mut output);
+
+        let latency = convolver.latency();
+        assert!(output[..latency].iter().all(|sample| sample.abs() < 1e-6));
+        for (out, expected) in output[latency..].iter().zip(impulse_response.iter().chain(std::iter::repeat(&0.0))) {
+            assert!((out - expected).abs() < 1e-5, "{out} != {expected}");
+        }
+    }
+
+    #[test]
+    fn component_output_is_delayed_by_one_block() {
+        let mut input = vec![0.0; 1024];
+        input[0] = 1.0;
+        let output = process(convolver(vec![1.0, 0.5]), &input);
+        assert!((output[BUFFER_SIZE] - 1.0).abs() < 1e-5);
+        assert!((output[BUFFER_SIZE + 1] - 0.5).abs() < 1e-5);
+    }
+}
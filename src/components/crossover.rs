@@ -0,0 +1,250 @@
+// Linkwitz-Riley crossover for multiband processing.
+//
+// Each split is a 4th-order Linkwitz-Riley pair (two cascaded Butterworth
+// biquads per side), whose low and high outputs sum to an allpass. Splits run as
+// a tree from the lowest frequency up: each band takes the low side of its split
+// and the high side carries on to the next. A band that leaves the tree early
+// passes through the allpass of every split above it, so all bands share the
+// same phase and sum back to a flat magnitude.
+use std::f32::consts::FRAC_1_SQRT_2;
+use crate::core::*;
+use super::biquad::{Biquad, BiquadCoefficients};
+
+#[derive(Clone, Copy, Debug)]
+struct LinkwitzRiley {
+    low: [Biquad; 2],
+    high: [Biquad; 2],
+}
+
+impl LinkwitzRiley {
+    fn new(sample_rate: f32, frequency: f32) -> Self {
+        let mut filters = Self { low: [Biquad::default(); 2], high: [Biquad::default(); 2] };
+        filters.tune(sample_rate, frequency);
+        filters
+    }
+
+    fn tune(&mut self, sample_rate: f32, frequency: f32) {
+        let low = BiquadCoefficients::lowpass(sample_rate, frequency, FRAC_1_SQRT_2);
+        let high = BiquadCoefficients::highpass(sample_rate, frequency, FRAC_1_SQRT_2);
+        self.low.iter_mut().for_each(|filter| filter.set_coefficients(low));
+        self.high.iter_mut().for_each(|filter| filter.set_coefficients(high));
+    }
+
+    fn split(&mut self, sample: f32) -> (f32, f32) {
+        let low = self.low.iter_mut().fold(sample, |acc, filter| filter.process(acc));
+        let high = self.high.iter_mut().fold(sample, |acc, filter| filter.process(acc));
+        (low, high)
+    }
+}
+
+/// Splits a signal into `N` bands at `N - 1` ascending crossover frequencies.
+pub struct Crossover<const N: usize> {
+    sample_rate: f32,
+    frequencies: Vec<f32>,
+    splits: Vec<LinkwitzRiley>,
+    // Phase compensation for each band, one allpass per split above it
+    allpasses: [Vec<Biquad>; N],
+}
+
+impl<const N: usize> Crossover<N> {
+    /// Panics unless there are `N - 1` strictly ascending frequencies.
+    pub fn new(sample_rate: f32, frequencies: &[f32]) -> Self {
+        assert!(N > 0, "crossover needs at least one band");
+        assert!(frequencies.len() + 1 == N, "a {}-band crossover needs {} frequencies", N, N - 1);
+        assert!(frequencies.windows(2).all(|pair| pair[0] < pair[1]), "crossover frequencies must ascend");
+
+        let splits = frequencies.iter().map(|&frequency| LinkwitzRiley::new(sample_rate, frequency)).collect();
+        let allpasses = std::array::from_fn(|band| {
+            frequencies.iter().skip(band + 1)
+                .map(|&frequency| Biquad::new(BiquadCoefficients::allpass(sample_rate, frequency, FRAC_1_SQRT_2)))
+                .collect()
+        });
+        Self { sample_rate, frequencies: frequencies.to_vec(), splits, allpasses }
+    }
+
+    pub fn sample_rate(&self) -> f32 {
+        self.sample_rate
+    }
+
+    /// Retunes every filter for `sample_rate` in place, keeping their state.
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.sample_rate = sample_rate;
+        for (split, &frequency) in self.splits.iter_mut().zip(&self.frequencies) {
+            split.tune(sample_rate, frequency);
+        }
+        for (band, allpasses) in self.allpasses.iter_mut().enumerate() {
+            for (filter, &frequency) in allpasses.iter_mut().zip(&self.frequencies[band + 1..]) {
+                filter.set_coefficients(BiquadCoefficients::allpass(sample_rate, frequency, FRAC_1_SQRT_2));
+            }
+        }
+    }
+
+    pub fn split(&mut self, sample: f32) -> [f32; N] {
+        let mut bands = [0.0; N];
+        let mut rest = sample;
+        for (band, split) in bands.iter_mut().zip(self.splits.iter_mut()) {
+            let (low, high) = split.split(rest);
+            *band = low;
+            rest = high;
+        }
+        bands[N - 1] = rest;
+
+        for (band, allpasses) in bands.iter_mut().zip(self.allpasses.iter_mut()) {
+            *band = allpasses.iter_mut().fold(*band, |acc, filter| filter.process(acc));
+        }
+        bands
+    }
+
+    pub fn reset(&mut self) {
+        for split in &mut self.splits {
+            split.low.iter_mut().chain(split.high.iter_mut()).for_each(Biquad::reset);
+        }
+        self.allpasses.iter_mut().flatten().for_each(Biquad::reset);
+    }
+
+    // Filters built before the sample rate is known are tuned for 48 kHz, then
+    // retuned on the first block at any other rate
+    fn for_component(frequencies: &[f32]) -> Self {
+        Self::new(48_000.0, frequencies)
+    }
+
+    // Splits `input` into the band buffers, retuning first if the rate changed
+    fn split_block(&mut self, sample_rate: f32, input: &[f32], bands: &mut [[f32; BUFFER_SIZE]; N]) {
+        if sample_rate != self.sample_rate {
+            self.set_sample_rate(sample_rate);
+        }
+        for (i, &sample) in input.iter().enumerate() {
+            for (band, value) in bands.iter_mut().zip(self.split(sample)) {
+                band[i] = value;
+            }
+        }
+    }
+}
+
+/// Splits the input into `N` bands and writes each to its own bus, lowest first,
+/// for other components to read. The output is the bands summed, which is the
+/// input through the crossover's allpass. Panics at build time unless there are
+/// `N - 1` ascending frequencies.
+pub fn crossover<E, const N: usize>(frequencies: &[f32]) -> impl FnOnce(&mut Builder<E>) -> (ComponentFn<E>, [StateHandle<Bus>; N])
+where
+    E: Clone + Send + 'static,
+{
+    let mut crossover = Crossover::<N>::for_component(frequencies);
+    move |builder: &mut Builder<E>| {
+        let buses: [StateHandle<Bus>; N] = std::array::from_fn(|_| builder.use_bus());
+        let mut bands = [[0.0; BUFFER_SIZE]; N];
+
+        let component: ComponentFn<E> = Box::new(move |runtime, input, output, sample_rate| {
+            let input = &input[..input.len().min(BUFFER_SIZE)];
+            crossover.split_block(sample_rate, input, &mut bands);
+            output.fill(0.0);
+            for (bus, band) in buses.iter().zip(bands.iter()) {
+                let band = &band[..input.len()];
+                runtime.get_mut(bus).write(band);
+                for (out, &sample) in output.iter_mut().zip(band) {
+                    *out += sample;
+                }
+            }
+        });
+        (component, buses)
+    }
+}
+
+/// Runs each band of a crossover through its own component and sums the results.
+/// Usually reached through `multiband!`. Panics unless there are `N - 1`
+/// ascending frequencies.
+pub fn multiband<E, const N: usize>(frequencies: Vec<f32>, mut bands: [ComponentFn<E>; N]) -> ComponentFn<E>
+where
+    E: Clone + Send + 'static,
+{
+    let mut crossover = Crossover::<N>::for_component(&frequencies);
+    let mut band_inputs = [[0.0; BUFFER_SIZE]; N];
+    let mut band_output = [0.0; BUFFER_SIZE];
+
+    Box::new(move |runtime, input, output, sample_rate| {
+        let len = input.len().min(BUFFER_SIZE);
+        crossover.split_block(sample_rate, &input[..len], &mut band_inputs);
+
+        output.fill(0.0);
+        for (component, band_input) in bands.iter_mut().zip(band_inputs.iter()) {
+            let band_output = &mut band_output[..len];
+            band_output.fill(0.0);
+            component(runtime, &band_input[..len], band_output, sample_rate);
+            for (out, &sample) in output.iter_mut().zip(band_output.iter()) {
+                *out += sample;
+            }
+        }
+    })
+}
+/// Splits the input at the given ascending frequencies and runs one component per
+/// band, lowest first: `multiband!([200.0, 2000.0], low, mid, high)`.
+#[macro_export]
+macro_rules! multiband {
+    ([$($frequency:expr),*], $($comp:expr),+) => {
+        |builder: &mut $crate::Builder<_>| -> $crate::ComponentFn<_> {
+            $crate::components::multiband(vec![$($frequency as f32),*], [$($comp(builder)),+])
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::testing::SAMPLE_RATE;
+
+    // Magnitude at `hz` of an impulse response
+    fn magnitude(response: &[f32], hz: f32) -> f32 {
+        let (re, im) = response.iter().enumerate().fold((0.0f64, 0.0f64), |(re, im), (n, &h)| {
+            let phase = std::f64::consts::TAU * hz as f64 * n as f64 / SAMPLE_RATE as f64;
+            (re + h as f64 * phase.cos(), im - h as f64 * phase.sin())
+        });
+        (re * re + im * im).sqrt() as f32
+    }
+
+    #[test]
+    fn summed_bands_have_a_flat_magnitude() {
+        let mut crossover = Crossover::<3>::new(SAMPLE_RATE, &[200.0, 2_000.0]);
+        let response: Vec<f32> = (0..16_384)
+            .map(|n| crossover.split(if n == 0 { 1.0 } else { 0.0 }).iter().sum())
+            .collect();
+        for hz in [30.0, 100.0, 200.0, 500.0, 1_000.0, 2_000.0, 5_000.0, 12_000.0, 20_000.0] {
+            let db = 20.0 * magnitude(&response, hz).log10();
+            assert!(db.abs() < 0.1, "{db} dB at {hz} Hz");
+        }
+    }
+
+    #[test]
+    fn crossover_buses_carry_the_separate_bands() {
+        let (_bus, builder) = new::<()>();
+        let mut buses = None;
+        let mut runtime = builder.build(|builder| {
+            let (component, handles) = crossover::<(), 2>(&[1_000.0])(builder);
+            buses = Some(handles);
+            component
+        });
+        let [low, high] = buses.unwrap();
+
+        // Past the filters' settling, a 100 Hz tone sits in the low band only
+        let tone: Vec<f32> = (0..8 * BUFFER_SIZE).map(|i| (std::f32::consts::TAU * 100.0 * i as f32 / SAMPLE_RATE).sin()).collect();
+        let mut output = [0.0; BUFFER_SIZE];
+        for block in tone.chunks(BUFFER_SIZE) {
+            runtime.tick(SAMPLE_RATE, None, block, &mut output);
+        }
+        let peak = |samples: &[f32]| samples.iter().fold(0.0f32, |peak, sample| peak.max(sample.abs()));
+        assert!(peak(runtime.get(&low).read()) > 0.95);
+        assert!(peak(runtime.get(&high).read()) < 0.01);
+        let summed: Vec<f32> = runtime.get(&low).read().iter().zip(runtime.get(&high).read()).map(|(a, b)| a + b).collect();
+        assert_eq!(summed, output);
+    }
+
+    fn pass<E>(_builder: &mut Builder<E>) -> ComponentFn<E> {
+        Box::new(|_runtime, input, output, _sample_rate| output.copy_from_slice(input))
+    }
+
+    #[test]
+    #[should_panic(expected = "crossover frequencies must ascend")]
+    fn multiband_rejects_unordered_frequencies_when_built() {
+        let (_bus, mut builder) = new::<()>();
+        let _ = crate::multiband!([2_000.0, 200.0], pass, pass, pass)(&mut builder);
+    }
+}
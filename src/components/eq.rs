@@ -0,0 +1,350 @@
+// Multi-band parametric EQ built from cascaded biquads.
+//
+// Band layout (count, shape, centre values) is fixed when the component is built.
+// Each band's frequency, gain and Q are also exposed as normalized parameters on
+// `EqParams<N>`, where 0.5 means "as configured" and the rest of the range sweeps
+// around that point. `#[parameters]` can't describe N bands, so the parameter
+// runtime is written out by hand here.
+use std::marker::PhantomData;
+use crate::core::*;
+use super::WithParams;
+use super::biquad::{Biquad, BiquadCoefficients};
+
+/// Octaves a band's frequency moves either side of its configured value.
+pub const EQ_FREQUENCY_OCTAVES: f32 = 5.0;
+/// Decibels a band's gain moves either side of its configured value.
+pub const EQ_GAIN_RANGE_DB: f32 = 24.0;
+/// Octaves a band's Q moves either side of its configured value.
+pub const EQ_Q_OCTAVES: f32 = 2.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BandShape {
+    Peak,
+    LowShelf,
+    HighShelf,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct EqBand {
+    pub shape: BandShape,
+    pub frequency: f32,
+    pub gain_db: f32,
+    pub q: f32,
+}
+
+impl EqBand {
+    pub fn peak(frequency: f32, gain_db: f32, q: f32) -> Self {
+        Self { shape: BandShape::Peak, frequency, gain_db, q }
+    }
+
+    pub fn low_shelf(frequency: f32, gain_db: f32) -> Self {
+        Self { shape: BandShape::LowShelf, frequency, gain_db, q: std::f32::consts::FRAC_1_SQRT_2 }
+    }
+
+    pub fn high_shelf(frequency: f32, gain_db: f32) -> Self {
+        Self { shape: BandShape::HighShelf, frequency, gain_db, q: std::f32::consts::FRAC_1_SQRT_2 }
+    }
+
+    // Applies one band's normalized parameter values to its configured shape
+    pub fn coefficients(&self, sample_rate: f32, params: &EqBandParams) -> BiquadCoefficients {
+        let frequency = (self.frequency * 2f32.powf((params.frequency - 0.5) * 2.0 * EQ_FREQUENCY_OCTAVES))
+            .clamp(10.0, sample_rate * 0.49);
+        let gain_db = self.gain_db + (params.gain - 0.5) * 2.0 * EQ_GAIN_RANGE_DB;
+        let q = (self.q * 2f32.powf((params.q - 0.5) * 2.0 * EQ_Q_OCTAVES)).max(0.01);
+
+        match self.shape {
+            BandShape::Peak => BiquadCoefficients::peaking(sample_rate, frequency, q, gain_db),
+            BandShape::LowShelf => BiquadCoefficients::low_shelf(sample_rate, frequency, q, gain_db),
+            BandShape::HighShelf => BiquadCoefficients::high_shelf(sample_rate, frequency, q, gain_db),
+        }
+    }
+}
+
+// === Parameters ===
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EqBandParams {
+    pub frequency: f32,
+    pub gain: f32,
+    pub q: f32,
+}
+
+impl Default for EqBandParams {
+    fn default() -> Self {
+        Self { frequency: 0.5, gain: 0.5, q: 0.5 }
+    }
+}
+
+/// Routable as `"band{index}_frequency"`, `"band{index}_gain"` and `"band{index}_q"`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EqParams<const N: usize> {
+    pub bands: [EqBandParams; N],
+}
+
+impl<const N: usize> Default for EqParams<N> {
+    fn default() -> Self {
+        Self { bands: [EqBandParams::default(); N] }
+    }
+}
+
+pub struct EqParamsRuntime<E, const N: usize> {
+    base: EqParams<N>,
+    // [frequency, gain, q] routing per band
+    modulation: [[ParamModulation; 3]; N],
+    computed_values: [EqParams<N>; BUFFER_SIZE],
+    _phantom: PhantomData<E>,
+}
+
+impl<E, const N: usize> EqParamsRuntime<E, N> {
+    fn new() -> Self {
+        let base = EqParams::default();
+        Self {
+            base,
+            modulation: std::array::from_fn(|_| Default::default()),
+            computed_values: [base; BUFFER_SIZE],
+            _phantom: PhantomData,
+        }
+    }
+}
+
+fn parse_band_param(param_name: &str) -> Option<(usize, usize)> {
+    let (band, field) = param_name.strip_prefix("band")?.split_once('_')?;
+    let field = match field {
+        "frequency" => 0,
+        "gain" => 1,
+        "q" => 2,
+        _ => return None,
+    };
+    Some((band.parse().ok()?, field))
+}
+
+impl<E: Send + 'static, const N: usize> ParameterRuntime<E> for EqParamsRuntime<E, N> {
+    fn update(&mut self, _sample_rate: f32, sources: &[Box<dyn Modulator<E>>], len: usize) {
+        // With nothing routed every sample of the block is the same
+        if self.modulation.iter().flatten().all(ParamModulation::is_empty) {
+            let mut values = self.base;
+            for (band, routings) in values.bands.iter_mut().zip(self.modulation.iter()) {
+                band.frequency = routings[0].apply_map(band.frequency.clamp(0.0, 1.0));
+                band.gain = routings[1].apply_map(band.gain.clamp(0.0, 1.0));
+                band.q = routings[2].apply_map(band.q.clamp(0.0, 1.0));
+            }
+            self.computed_values = [values; BUFFER_SIZE];
+            return;
+        }
+
+        for i in 0..len.min(BUFFER_SIZE) {
+            for (band, (base, routings)) in self.base.bands.iter().zip(self.modulation.iter()).enumerate() {
+                let value = |base: f32, modulation: &ParamModulation| {
+                    let (offset, pinned) = modulation.evaluate(sources, i);
+                    modulation.apply_map(pinned.unwrap_or(base + offset).clamp(0.0, 1.0))
+                };
+                self.computed_values[i].bands[band] = EqBandParams {
+                    frequency: value(base.frequency, &routings[0]),
+                    gain: value(base.gain, &routings[1]),
+                    q: value(base.q, &routings[2]),
+                };
+            }
+        }
+    }
+
+    fn route_parameter(&mut self, param_name: &str, routing: ModulationRouting) -> bool {
+        match parse_band_param(param_name) {
+            Some((band, field)) if band < N => {
+                self.modulation[band][field].route(routing);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn set_param_map(&mut self, param_name: &str, map: ParamMap) -> bool {
+        match parse_band_param(param_name) {
+            Some((band, field)) if band < N => {
+                self.modulation[band][field].set_map(map);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn set_base(&mut self, param_name: &str, value: f32) -> bool {
+        match parse_band_param(param_name) {
+            Some((band, field)) if band < N => {
+                let band = &mut self.base.bands[band];
+                match field {
+                    0 => band.frequency = value,
+                    1 => band.gain = value,
+                    _ => band.q = value,
+                }
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn unroute_parameter(&mut self, param_name: &str, source_index: usize) -> bool {
+        match parse_band_param(param_name) {
+            Some((band, field)) if band < N => {
+                self.modulation[band][field].unroute(source_index);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn is_modulated(&self, param_name: &str) -> Option<bool> {
+        match parse_band_param(param_name) {
+            Some((band, field)) if band < N => Some(!self.modulation[band][field].is_empty()),
+            _ => None,
+        }
+    }
+
+    fn set_source_depth(&mut self, source_index: usize, depth: f32) {
+        for modulation in self.modulation.iter_mut().flatten() {
+            modulation.set_source_depth(source_index, depth);
+        }
+    }
+
+    fn reserve_routings(&mut self, sources: usize) {
+        for modulation in self.modulation.iter_mut().flatten() {
+            modulation.reserve(sources);
+        }
+    }
+
+    fn routings(&self) -> Vec<(String, usize)> {
+        let mut routings = Vec::new();
+        for (band, fields) in self.modulation.iter().enumerate() {
+            for (field, modulation) in ["frequency", "gain", "q"].iter().zip(fields) {
+                routings.extend(modulation.sources().map(|source| (format!("band{}_{}", band, field), source)));
+            }
+        }
+        routings
+    }
+
+    fn value(&self, param_name: &str) -> Option<f32> {
+        match parse_band_param(param_name) {
+            Some((band, field)) if band < N => {
+                let band = &self.computed_values[0].bands[band];
+                Some([band.frequency, band.gain, band.q][field])
+            }
+            _ => None,
+        }
+    }
+}
+
+pub struct EqParamsAccessor<'a, const N: usize> {
+    base: &'a EqParams<N>,
+    values: &'a [EqParams<N>; BUFFER_SIZE],
+}
+
+impl<'a, const N: usize> EqParamsAccessor<'a, N> {
+    /// Every band's un-modulated values.
+    pub fn base(&self) -> EqParams<N> {
+        *self.base
+    }
+
+    /// Computed minus base for `param_name` at each sample of the block.
+    pub fn modulation(&self, param_name: &str) -> Option<[f32; BUFFER_SIZE]> {
+        let (band, field) = parse_band_param(param_name).filter(|(band, _)| *band < N)?;
+        let value = |params: &EqParams<N>| {
+            let band = &params.bands[band];
+            [band.frequency, band.gain, band.q][field]
+        };
+        let base = value(self.base);
+        Some(std::array::from_fn(|i| value(&self.values[i]) - base))
+    }
+
+    /// Sample-accurate read of every band at `offset` samples into the block.
+    pub fn at(&self, offset: usize) -> EqParams<N> {
+        self.values[offset % BUFFER_SIZE]
+    }
+
+    /// Every sample's values in order.
+    pub fn iter(&self) -> std::slice::Iter<'a, EqParams<N>> {
+        self.values.iter()
+    }
+}
+
+impl<const N: usize> std::ops::Index<usize> for EqParamsAccessor<'_, N> {
+    type Output = EqParams<N>;
+    fn index(&self, index: usize) -> &Self::Output {
+        &self.values[index % BUFFER_SIZE]
+    }
+}
+
+impl<const N: usize> Parameters for EqParams<N> {
+    type Runtime<E: Send + 'static> = EqParamsRuntime<E, N>;
+    type Accessor<'a, E> = EqParamsAccessor<'a, N> where E: 'a;
+    type Values = EqParams<N>;
+
+    fn create_runtime<E: Send + 'static>() -> Self::Runtime<E> {
+        EqParamsRuntime::new()
+    }
+
+    fn create_accessor<E: Send + 'static>(runtime: &Self::Runtime<E>) -> Self::Accessor<'_, E> {
+        EqParamsAccessor { base: &runtime.base, values: &runtime.computed_values }
+    }
+
+    fn param_names() -> Vec<String> {
+        (0..N)
+            .flat_map(|band| ["frequency", "gain", "q"].map(|field| format!("band{}_{}", band, field)))
+            .collect()
+    }
+
+    fn param_range(param_name: &str) -> Option<(f32, f32)> {
+        match parse_band_param(param_name) {
+            Some((band, _)) if band < N => Some((0.0, 1.0)),
+            _ => None,
+        }
+    }
+}
+
+// === Component ===
+/// Cascaded parametric EQ. Coefficients are recomputed once per block from the
+/// block's first parameter values.
+pub fn eq<E, const N: usize>(bands: [EqBand; N]) -> impl FnOnce(&mut Builder<E>) -> ComponentFn<E>
+where
+    E: Clone + Send + 'static,
+{
+    move |builder: &mut Builder<E>| eq_with_params(bands)(builder).0
+}
+
+pub fn eq_with_params<E, const N: usize>(bands: [EqBand; N]) -> impl FnOnce(&mut Builder<E>) -> WithParams<E, EqParams<N>>
+where
+    E: Clone + Send + 'static,
+{
+    move |builder: &mut Builder<E>| {
+        let params_handle = builder.use_parameters::<EqParams<N>>();
+        let mut filters = [Biquad::default(); N];
+
+        let component: ComponentFn<E> = Box::new(move |runtime, input, output, sample_rate| {
+            let params = runtime.get_parameters(&params_handle);
+            for ((filter, band), band_params) in filters.iter_mut().zip(bands.iter()).zip(params[0].bands.iter()) {
+                filter.set_coefficients(band.coefficients(sample_rate, band_params));
+            }
+
+            for (out, &sample) in output.iter_mut().zip(input.iter()) {
+                *out = filters.iter_mut().fold(sample, |acc, filter| filter.process(acc));
+            }
+        });
+        (component, params_handle)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::testing::{amplitude_at, process, sine};
+
+    #[test]
+    fn peak_band_lifts_its_frequency_by_its_gain() {
+        let response = |hz: f32| {
+            let output = process(eq([EqBand::peak(1_000.0, 6.0, 1.0)]), &sine(hz, 48_000));
+            // Past the filter's settling time
+            amplitude_at(&output[24_000..], hz)
+        };
+        let boost_db = 20.0 * response(1_000.0).log10();
+        assert!((boost_db - 6.0).abs() < 0.2, "{boost_db} dB at the centre");
+        assert!(20.0 * response(50.0).log10() < 0.5);
+        assert!(20.0 * response(15_000.0).log10() < 0.5);
+    }
+}
@@ -0,0 +1,159 @@
+// Resonant biquad filter with modulatable cutoff and resonance.
+use crate::core::*;
+use crate::parameters;
+use super::WithParams;
+use super::biquad::{Biquad, BiquadCoefficients};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FilterKind {
+    Lowpass,
+    Highpass,
+    Bandpass,
+}
+
+#[parameters]
+pub struct BiquadFilterParams {
+    /// 20 Hz..20 kHz, exponential; 1 kHz by default
+    #[param(default = 0.5663, unit_min = 20.0, unit_max = 20000.0, exp)]
+    pub cutoff: f32,
+    /// Q of 0.5..20, exponential; 0.707 by default
+    #[param(default = 0.094, unit_min = 0.5, unit_max = 20.0, exp)]
+    pub resonance: f32,
+}
+
+impl BiquadFilterParams {
+    pub fn cutoff_hz(&self) -> f32 {
+        20.0 * 1000f32.powf(self.cutoff)
+    }
+
+    pub fn q(&self) -> f32 {
+        0.5 * 40f32.powf(self.resonance)
+    }
+
+    pub fn coefficients(&self, kind: FilterKind, sample_rate: f32) -> BiquadCoefficients {
+        let cutoff = self.cutoff_hz().min(sample_rate * 0.49);
+        match kind {
+            FilterKind::Lowpass => BiquadCoefficients::lowpass(sample_rate, cutoff, self.q()),
+            FilterKind::Highpass => BiquadCoefficients::highpass(sample_rate, cutoff, self.q()),
+            FilterKind::Bandpass => BiquadCoefficients::bandpass(sample_rate, cutoff, self.q()),
+        }
+    }
+}
+
+/// Coefficients are recomputed once per block from the block's first parameter values.
+pub fn biquad_filter<E>(kind: FilterKind) -> impl FnOnce(&mut Builder<E>) -> ComponentFn<E>
+where
+    E: Clone + Send + 'static,
+{
+    move |builder: &mut Builder<E>| biquad_filter_with_params(kind)(builder).0
+}
+
+pub fn biquad_filter_with_params<E>(kind: FilterKind) -> impl FnOnce(&mut Builder<E>) -> WithParams<E, BiquadFilterParams>
+where
+    E: Clone + Send + 'static,
+{
+    move |builder: &mut Builder<E>| {
+        let params_handle = builder.use_parameters::<BiquadFilterParams>();
+        let mut filter = Biquad::default();
+
+        let component: ComponentFn<E> = Box::new(move |runtime, input, output, sample_rate| {
+            let params = runtime.get_parameters(&params_handle);
+            filter.set_coefficients(params[0].coefficients(kind, sample_rate));
+            for (out, &sample) in output.iter_mut().zip(input.iter()) {
+                *out = filter.process(sample);
+            }
+        });
+        (component, params_handle)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::testing::{sine, SAMPLE_RATE};
+    use crate::modulators::LfoBank;
+
+    #[test]
+    fn lfo_routed_to_the_exposed_cutoff_sweeps_the_filter() {
+        let (_bus, builder) = new::<()>();
+        let mut handles = None;
+        let mut runtime = builder.build(|builder| {
+            let lfo = builder.use_modulator::<LfoBank<1>>();
+            let (filter, params) = biquad_filter_with_params(FilterKind::Lowpass)(builder);
+            handles = Some((lfo, params));
+            filter
+        });
+        let (lfo, params) = handles.unwrap();
+        runtime.get_source_mut(&lfo).set_frequency(10.0);
+        runtime.route(lfo, params, "cutoff", 0.3).unwrap();
+
+        // A 5 kHz tone is cut with the cutoff at its 1 kHz base and passes near 8 kHz
+        let input = sine(5_000.0, 20 * BUFFER_SIZE);
+        let (mut cutoffs, mut peaks) = (Vec::new(), Vec::new());
+        for block in input.chunks(BUFFER_SIZE) {
+            let mut output = [0.0; BUFFER_SIZE];
+            runtime.tick(SAMPLE_RATE, None, block, &mut output);
+            cutoffs.push(runtime.get_parameters(&params)[0].cutoff);
+            peaks.push(output.iter().fold(0.0f32, |peak, sample| peak.max(sample.abs())));
+        }
+        let range = |values: &[f32]| values.iter().fold((f32::MAX, f32::MIN), |(low, high), &v| (low.min(v), high.max(v)));
+        let (low, high) = range(&cutoffs);
+        assert!(low < 0.6 && high > 0.83, "cutoff swept {low}..{high}");
+        let (quietest, loudest) = range(&peaks[1..]);
+        assert!(quietest < 0.2 && loudest > 0.8, "peaks {quietest}..{loudest}");
+    }
+
+    #[test]
+    fn exponential_cutoff_round_trips_through_normalized() {
+        let normalized = BiquadFilterParams::to_normalized("cutoff", 1_000.0).unwrap();
+        assert!((normalized - 0.5663).abs() < 1e-3, "{}", normalized);
+        let hz = BiquadFilterParams::from_normalized("cutoff", normalized).unwrap();
+        assert!((hz - 1_000.0).abs() < 0.1, "{}", hz);
+        // Halfway on an exponential scale is the geometric mean
+        let middle = BiquadFilterParams::from_normalized("cutoff", 0.5).unwrap();
+        assert!((middle - (20.0f32 * 20_000.0).sqrt()).abs() < 0.1, "{}", middle);
+        assert_eq!(BiquadFilterParams::to_normalized("gain", 0.5), None);
+    }
+
+    // xorshift32, so every case replays from its seed
+    fn random(state: &mut u32) -> f32 {
+        *state ^= *state << 13;
+        *state ^= *state >> 17;
+        *state ^= *state << 5;
+        *state as f32 / u32::MAX as f32
+    }
+
+    // Twenty blocks of noise through a lowpass whose settings come from `seed`
+    fn filter_case(seed: u32) -> Vec<f32> {
+        let mut state = seed;
+        let (_bus, builder) = new::<()>();
+        let mut handle = None;
+        let mut runtime = builder.build(|builder| {
+            let (filter, params) = biquad_filter_with_params(FilterKind::Lowpass)(builder);
+            handle = Some(params);
+            filter
+        });
+        let params = handle.unwrap();
+        runtime.set_base(params, "cutoff", random(&mut state)).unwrap();
+        runtime.set_base(params, "resonance", random(&mut state)).unwrap();
+
+        let mut output = Vec::new();
+        for _ in 0..20 {
+            let input: Vec<f32> = (0..BUFFER_SIZE).map(|_| random(&mut state) * 2.0 - 1.0).collect();
+            let mut block = [0.0; BUFFER_SIZE];
+            runtime.process_block(SAMPLE_RATE, &[], &input, &mut block);
+            output.extend_from_slice(&block);
+        }
+        output
+    }
+
+    #[test]
+    fn any_settings_keep_the_filter_bounded_and_repeatable() {
+        for seed in 1..=64 {
+            let output = filter_case(seed);
+            // Q tops out at 20, so even resonant settings stay well inside this
+            assert!(output.iter().all(|sample| sample.is_finite() && sample.abs() < 64.0), "seed {}", seed);
+            assert_eq!(output, filter_case(seed), "seed {}", seed);
+        }
+    }
+}
@@ -0,0 +1,139 @@
+// Formant filter: parallel band-pass resonators at the first three formants of a
+// vowel, for vocal and talkbox-style effects. The vowel parameter morphs through
+// A, E, I, O and U by interpolating the formant frequencies between neighbours.
+use crate::core::*;
+use crate::parameters;
+use super::WithParams;
+use super::biquad::{Biquad, BiquadCoefficients};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Vowel {
+    A,
+    E,
+    I,
+    O,
+    U,
+}
+
+impl Vowel {
+    const ALL: [Vowel; 5] = [Vowel::A, Vowel::E, Vowel::I, Vowel::O, Vowel::U];
+
+    /// The `vowel` parameter value that selects this vowel exactly.
+    pub fn position(self) -> f32 {
+        Self::ALL.iter().position(|&vowel| vowel == self).unwrap() as f32 / (Self::ALL.len() - 1) as f32
+    }
+
+    /// First three formant frequencies in Hz (averages for an adult male voice).
+    pub fn formants(self) -> [f32; 3] {
+        match self {
+            Vowel::A => [730.0, 1090.0, 2440.0],
+            Vowel::E => [530.0, 1840.0, 2480.0],
+            Vowel::I => [270.0, 2290.0, 3010.0],
+            Vowel::O => [570.0, 840.0, 2410.0],
+            Vowel::U => [300.0, 870.0, 2240.0],
+        }
+    }
+}
+
+// Bandwidth in Hz and level of each formant's resonator
+const FORMANT_BANDWIDTHS: [f32; 3] = [80.0, 90.0, 120.0];
+const FORMANT_GAINS: [f32; 3] = [1.0, 0.5, 0.25];
+
+#[parameters]
+pub struct FormantParams {
+    /// 0 = A, 0.25 = E, 0.5 = I, 0.75 = O, 1 = U, morphing in between
+    pub vowel: f32,
+}
+
+/// Formant frequencies at `position` along A, E, I, O, U.
+pub fn vowel_formants(position: f32) -> [f32; 3] {
+    let scan = position.clamp(0.0, 1.0) * (Vowel::ALL.len() - 1) as f32;
+    let from = (scan as usize).min(Vowel::ALL.len() - 2);
+    let t = scan - from as f32;
+    let (a, b) = (Vowel::ALL[from].formants(), Vowel::ALL[from + 1].formants());
+    std::array::from_fn(|i| a[i] + (b[i] - a[i]) * t)
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Formant {
+    resonators: [Biquad; 3],
+}
+
+impl Formant {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Tunes the resonators to the vowel at `position`, see `FormantParams::vowel`.
+    pub fn set_vowel(&mut self, sample_rate: f32, position: f32) {
+        let formants = vowel_formants(position);
+        for ((resonator, frequency), bandwidth) in self.resonators.iter_mut().zip(formants).zip(FORMANT_BANDWIDTHS) {
+            let frequency = frequency.min(sample_rate * 0.49);
+            resonator.set_coefficients(BiquadCoefficients::bandpass(sample_rate, frequency, frequency / bandwidth));
+        }
+    }
+
+    pub fn process(&mut self, input: f32) -> f32 {
+        self.resonators.iter_mut().zip(FORMANT_GAINS)
+            .map(|(resonator, gain)| resonator.process(input) * gain)
+            .sum()
+    }
+
+    pub fn reset(&mut self) {
+        self.resonators.iter_mut().for_each(Biquad::reset);
+    }
+}
+
+/// Formant filter. Resonators are retuned once per block from the block's first
+/// vowel value.
+pub fn formant<E>() -> impl FnOnce(&mut Builder<E>) -> ComponentFn<E>
+where
+    E: Clone + Send + 'static,
+{
+    move |builder: &mut Builder<E>| formant_with_params()(builder).0
+}
+
+pub fn formant_with_params<E>() -> impl FnOnce(&mut Builder<E>) -> WithParams<E, FormantParams>
+where
+    E: Clone + Send + 'static,
+{
+    move |builder: &mut Builder<E>| {
+        let params_handle = builder.use_parameters::<FormantParams>();
+        let mut filter = Formant::new();
+
+        let component: ComponentFn<E> = Box::new(move |runtime, input, output, sample_rate| {
+            let params = runtime.get_parameters(&params_handle);
+            filter.set_vowel(sample_rate, params[0].vowel);
+            for (out, &sample) in output.iter_mut().zip(input.iter()) {
+                *out = filter.process(sample);
+            }
+        });
+        (component, params_handle)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::testing::{amplitude_at, sine, SAMPLE_RATE};
+
+    fn response(filter: &mut Formant, hz: f32) -> f32 {
+        filter.reset();
+        let output: Vec<f32> = sine(hz, 9_600).into_iter().map(|sample| filter.process(sample)).collect();
+        // Past the resonators' ring-up
+        amplitude_at(&output[4_800..], hz)
+    }
+
+    #[test]
+    fn vowel_a_peaks_at_its_formants() {
+        assert_eq!(vowel_formants(Vowel::A.position()), Vowel::A.formants());
+        let mut filter = Formant::new();
+        filter.set_vowel(SAMPLE_RATE, Vowel::A.position());
+        let [first, second, third] = Vowel::A.formants().map(|hz| response(&mut filter, hz));
+        assert!(first > 0.9 && second > 0.45 && third > 0.2, "{} {} {}", first, second, third);
+        // Between and beyond the formants the filter passes far less
+        for hz in [300.0, 1_700.0, 5_000.0] {
+            assert!(response(&mut filter, hz) < 0.15, "{} Hz", hz);
+        }
+    }
+}
@@ -0,0 +1,86 @@
+// Stops a NaN or infinity from one stage reaching the rest of the graph, where a
+// single bad sample in a filter or feedback loop would silence everything after it.
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use crate::core::*;
+
+/// Counts the samples its guard components have replaced. Clones share the count,
+/// so keep one on the UI side to poll.
+#[derive(Clone, Default)]
+pub struct NanGuard {
+    replaced: Arc<AtomicUsize>,
+}
+
+impl NanGuard {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn replaced(&self) -> usize {
+        self.replaced.load(Ordering::Relaxed)
+    }
+
+    pub fn reset(&self) {
+        self.replaced.store(0, Ordering::Relaxed);
+    }
+
+    /// A guard component that adds what it replaces to this count.
+    pub fn component<E>(&self) -> impl FnOnce(&mut Builder<E>) -> ComponentFn<E>
+    where
+        E: Clone + Send + 'static,
+    {
+        let replaced = self.replaced.clone();
+        move |_builder: &mut Builder<E>| -> ComponentFn<E> {
+            Box::new(move |_runtime, input, output, _sample_rate| {
+                let count = guard(input, output);
+                if count > 0 {
+                    replaced.fetch_add(count, Ordering::Relaxed);
+                }
+            })
+        }
+    }
+}
+
+/// Passes its input through with every NaN or infinite sample replaced by zero.
+pub fn nan_guard<E>() -> impl FnOnce(&mut Builder<E>) -> ComponentFn<E>
+where
+    E: Clone + Send + 'static,
+{
+    move |_builder: &mut Builder<E>| -> ComponentFn<E> {
+        Box::new(move |_runtime, input, output, _sample_rate| {
+            guard(input, output);
+        })
+    }
+}
+
+fn guard(input: &[f32], output: &mut [f32]) -> usize {
+    let mut replaced = 0;
+    for (out, &sample) in output.iter_mut().zip(input.iter()) {
+        *out = if sample.is_finite() {
+            sample
+        } else {
+            replaced += 1;
+            0.0
+        };
+    }
+    replaced
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::testing::process;
+
+    #[test]
+    fn non_finite_samples_are_zeroed_and_counted() {
+        let input = [0.5, f32::NAN, -0.25, f32::INFINITY, f32::NEG_INFINITY, 1.0];
+        let counter = NanGuard::new();
+        let output = process(counter.component(), &input);
+        assert_eq!(output, [0.5, 0.0, -0.25, 0.0, 0.0, 1.0]);
+        assert_eq!(counter.replaced(), 3);
+
+        counter.reset();
+        assert_eq!(counter.replaced(), 0);
+        assert_eq!(process(nan_guard(), &input), output);
+    }
+}
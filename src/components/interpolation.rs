@@ -0,0 +1,26 @@
+// Fractional-position reads shared by the delay and resampling components.
+//
+// Linear interpolation rolls off the top octave as the read position moves
+// between samples; Catmull-Rom uses two more neighbours and stays much flatter
+// at the cost of a few extra multiplies.
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Interpolation {
+    #[default]
+    Linear,
+    /// Catmull-Rom cubic through the two samples either side of the read position
+    Cubic,
+}
+
+impl Interpolation {
+    /// Interpolates at `t` (0..1) between `x1` and `x2`; `x0` precedes `x1` and
+    /// `x3` follows `x2`. Linear ignores the outer points.
+    pub fn interpolate(self, x0: f32, x1: f32, x2: f32, x3: f32, t: f32) -> f32 {
+        match self {
+            Interpolation::Linear => x1 + (x2 - x1) * t,
+            Interpolation::Cubic => {
+                x1 + 0.5 * t * (x2 - x0 + t * (2.0 * x0 - 5.0 * x1 + 4.0 * x2 - x3 + t * (3.0 * (x1 - x2) + x3 - x0)))
+            }
+        }
+    }
+}
@@ -0,0 +1,131 @@
+// Moog-style 4-pole ladder lowpass.
+//
+// Four one-pole stages (trapezoidal integrators, so cutoff stays accurate up
+// high) with the last stage fed back to the input through a tanh. The feedback
+// loop is solved linearly each sample for an estimate of the output, which is
+// then fed through the tanh, so resonance stays in tune without a unit delay.
+// Like the analog circuit it passes a little noise, so near full resonance it
+// self-oscillates even with no input. Passband gain drops as resonance rises;
+// there's no makeup.
+use std::f32::consts::PI;
+use crate::core::*;
+use crate::parameters;
+use super::WithParams;
+
+/// Resonance at which the loop gain reaches 4 and the filter starts to oscillate.
+pub const LADDER_SELF_OSCILLATION: f32 = 4.0 / 4.2;
+
+#[parameters]
+pub struct LadderFilterParams {
+    /// 20 Hz..20 kHz, exponential; 1 kHz by default
+    #[param(default = 0.5663, unit_min = 20.0, unit_max = 20000.0, exp)]
+    pub cutoff: f32,
+    /// 0..1, self-oscillating above `LADDER_SELF_OSCILLATION`
+    pub resonance: f32,
+}
+
+impl LadderFilterParams {
+    pub fn cutoff_hz(&self) -> f32 {
+        20.0 * 1000f32.powf(self.cutoff)
+    }
+
+    pub fn feedback(&self) -> f32 {
+        self.resonance * 4.2
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Ladder {
+    stages: [f32; 4],
+    noise: u32,
+}
+
+impl Ladder {
+    pub fn process(&mut self, input: f32, cutoff_hz: f32, feedback: f32, sample_rate: f32) -> f32 {
+        let g = (PI * cutoff_hz.min(sample_rate * 0.45) / sample_rate).tan();
+        let gain = g / (1.0 + g);
+
+        // About -120 dBFS of white noise
+        self.noise = self.noise.wrapping_mul(1664525).wrapping_add(1013904223);
+        let noise = (self.noise >> 8) as f32 / (1u32 << 24) as f32 * 2e-6 - 1e-6;
+
+        // Solve the linear loop for this sample's output, then saturate the input with it
+        let [s1, s2, s3, s4] = self.stages;
+        let g4 = gain.powi(4);
+        let state_sum = (1.0 - gain) * (gain * (gain * (gain * s1 + s2) + s3) + s4);
+        let estimate = (g4 * (input + noise) + state_sum) / (1.0 + feedback * g4);
+
+        let mut x = (input + noise - feedback * estimate).tanh();
+        for state in &mut self.stages {
+            let v = (x - *state) * gain;
+            x = v + *state;
+            *state = x + v;
+        }
+        x
+    }
+
+    pub fn reset(&mut self) {
+        self.stages = [0.0; 4];
+    }
+}
+
+pub fn ladder_filter<E>() -> impl FnOnce(&mut Builder<E>) -> ComponentFn<E>
+where
+    E: Clone + Send + 'static,
+{
+    move |builder: &mut Builder<E>| ladder_filter_with_params()(builder).0
+}
+
+pub fn ladder_filter_with_params<E>() -> impl FnOnce(&mut Builder<E>) -> WithParams<E, LadderFilterParams>
+where
+    E: Clone + Send + 'static,
+{
+    move |builder: &mut Builder<E>| {
+        let params_handle = builder.use_parameters::<LadderFilterParams>();
+        let mut ladder = Ladder::default();
+
+        let component: ComponentFn<E> = Box::new(move |runtime, input, output, sample_rate| {
+            let params = runtime.get_parameters(&params_handle);
+            for (i, (out, &sample)) in output.iter_mut().zip(input.iter()).enumerate() {
+                let p = params[i];
+                *out = ladder.process(sample, p.cutoff_hz(), p.feedback(), sample_rate);
+            }
+        });
+        (component, params_handle)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::testing::SAMPLE_RATE;
+
+    // Two seconds of the filter fed silence
+    fn ring(cutoff_hz: f32, resonance: f32) -> Vec<f32> {
+        let mut ladder = Ladder::default();
+        let feedback = LadderFilterParams { cutoff: 0.0, resonance }.feedback();
+        (0..2 * SAMPLE_RATE as usize).map(|_| ladder.process(0.0, cutoff_hz, feedback, SAMPLE_RATE)).collect()
+    }
+
+    fn peak(samples: &[f32]) -> f32 {
+        samples.iter().fold(0.0, |peak, sample| peak.max(sample.abs()))
+    }
+
+    #[test]
+    fn self_oscillates_from_silence_only_above_the_threshold() {
+        let tail = SAMPLE_RATE as usize;
+        assert!(peak(&ring(1_000.0, LADDER_SELF_OSCILLATION - 0.05)[tail..]) < 1e-4);
+        assert!(peak(&ring(1_000.0, 1.0)[tail..]) > 0.1);
+    }
+
+    #[test]
+    fn oscillation_pitch_tracks_the_cutoff() {
+        for cutoff_hz in [250.0, 1_000.0, 4_000.0] {
+            // Rising zero crossings over the last second
+            let output = ring(cutoff_hz, 1.0);
+            let tail = &output[SAMPLE_RATE as usize..];
+            let crossings = tail.windows(2).filter(|pair| pair[0] < 0.0 && pair[1] >= 0.0).count() as f32;
+            assert!((crossings / cutoff_hz - 1.0).abs() < 0.05, "{crossings} Hz for a {cutoff_hz} Hz cutoff");
+        }
+    }
+}
@@ -0,0 +1,84 @@
+//! Built-in components
+//!
+//! Each component is a function returning a builder closure, so it slots into
+//! `Builder::build`, `serial!` and `parallel!` like a hand-written component.
+//!
+//! Components with parameters also have a `*_with_params` constructor whose
+//! closure returns the `ParameterHandle` alongside the component, so modulators
+//! can be routed to it once the runtime is built:
+//!
+//! ```
+//! use ceres::components::{biquad_filter_with_params, FilterKind};
+//! use ceres::modulators::LfoBank;
+//!
+//! let (_bus, builder) = ceres::new::<()>();
+//! let mut handles = None;
+//! let mut runtime = builder.build(|builder| {
+//!     let lfo = builder.use_modulator::<LfoBank<1>>();
+//!     let (filter, params) = biquad_filter_with_params(FilterKind::Lowpass)(builder);
+//!     handles = Some((lfo, params));
+//!     filter
+//! });
+//! let (lfo, params) = handles.unwrap();
+//! runtime.route(lfo, params, "cutoff", 0.3)?;
+//! # Ok::<(), ceres::RouteError>(())
+//! ```
+
+mod audio_gate;
+mod auto_gain;
+mod biquad;
+mod compressor;
+#[cfg(feature = "rustfft")]
+mod convolver;
+mod crossover;
+mod delay;
+mod eq;
+mod filter;
+mod formant;
+mod guard;
+mod interpolation;
+mod ladder;
+mod resample;
+mod reverb;
+mod sampler;
+mod send;
+mod stereo_width;
+#[cfg(test)]
+mod testing;
+mod transient;
+mod unison;
+mod wavetable;
+
+pub use audio_gate::audio_gate;
+pub use auto_gain::{auto_gain, auto_gain_with_params, AutoGain, AutoGainParams, AutoGainParamsAccessor, AutoGainParamsRuntime};
+pub use biquad::{Biquad, BiquadCoefficients};
+pub use compressor::{compressor, compressor_with_params, compressor_gain_db, CompressorParams, CompressorParamsAccessor, CompressorParamsRuntime};
+#[cfg(feature = "rustfft")]
+pub use convolver::{convolver, Convolver};
+pub use crossover::{crossover, multiband, Crossover};
+pub use delay::{mod_delay, mod_delay_with_params, DelayLine, ModDelayParams, ModDelayParamsAccessor, ModDelayParamsRuntime};
+pub use eq::{eq, eq_with_params, BandShape, EqBand, EqBandParams, EqParams, EqParamsAccessor, EqParamsRuntime};
+pub use eq::{EQ_FREQUENCY_OCTAVES, EQ_GAIN_RANGE_DB, EQ_Q_OCTAVES};
+pub use filter::{biquad_filter, biquad_filter_with_params, BiquadFilterParams, BiquadFilterParamsAccessor, BiquadFilterParamsRuntime, FilterKind};
+pub use formant::{formant, formant_with_params, vowel_formants, Formant, FormantParams, FormantParamsAccessor, FormantParamsRuntime, Vowel};
+pub use guard::{nan_guard, NanGuard};
+pub use interpolation::Interpolation;
+pub use ladder::{ladder_filter, ladder_filter_with_params, Ladder, LadderFilterParams, LadderFilterParamsAccessor, LadderFilterParamsRuntime, LADDER_SELF_OSCILLATION};
+pub use resample::resample;
+pub use reverb::{reverb, reverb_with_params, Reverb, ReverbParams, ReverbParamsAccessor, ReverbParamsRuntime};
+pub use sampler::{sampler, SampleEvent, SampleLoader, Sampler};
+pub use send::send;
+pub use stereo_width::{stereo_width, stereo_width_with_params, StereoWidth, StereoWidthParams, StereoWidthParamsAccessor, StereoWidthParamsRuntime};
+pub use transient::{transient_shaper, transient_shaper_with_params, TransientShaper, TransientShaperParams, TransientShaperParamsAccessor, TransientShaperParamsRuntime};
+pub use unison::{unison, unison_with_params, Unison, UnisonParams, UnisonParamsAccessor, UnisonParamsRuntime};
+pub use wavetable::{wt_osc, wt_osc_synced, wt_osc_synced_with_params, wt_osc_with_params, OscSync, SyncBus, Wavetable, WtOscParams, WtOscParamsAccessor, WtOscParamsRuntime};
+
+use crate::core::{ComponentFn, ParameterHandle};
+
+/// What a `*_with_params` constructor's builder closure returns.
+pub type WithParams<E, T> = (ComponentFn<E>, ParameterHandle<T>);
+
+/// Highest sample rate components size their buffers for. They're allocated at
+/// build time, before the rate is known, so a rate change on the audio thread
+/// never allocates; above this rate, delays and tails are cut short.
+pub const MAX_SAMPLE_RATE: f32 = 192_000.0;
@@ -0,0 +1,25 @@
+// Offline sample-rate conversion, e.g. for bringing loaded samples to the
+// engine's rate before playback.
+use super::interpolation::Interpolation;
+
+/// Resamples `samples` recorded at `from_rate` to `to_rate`. There is no
+/// anti-aliasing filter, so downsampling material with content above the new
+/// Nyquist frequency will alias.
+pub fn resample(samples: &[f32], from_rate: f32, to_rate: f32, interpolation: Interpolation) -> Vec<f32> {
+    if samples.is_empty() {
+        return Vec::new();
+    }
+    let step = from_rate / to_rate;
+    let len = ((samples.len() as f32) / step).floor() as usize;
+    let last = samples.len() as isize - 1;
+    let at = |index: isize| samples[index.clamp(0, last) as usize];
+
+    (0..len)
+        .map(|i| {
+            let position = i as f32 * step;
+            let index = position.floor() as isize;
+            let t = position - index as f32;
+            interpolation.interpolate(at(index - 1), at(index), at(index + 1), at(index + 2), t)
+        })
+        .collect()
+}
@@ -0,0 +1,222 @@
+// Freeverb-style reverb: eight damped feedback combs in parallel feeding four
+// allpasses in series. Delay lengths are the classic 44.1 kHz tunings scaled
+// to the stream's rate, within buffers allocated up front.
+use crate::core::*;
+use crate::parameters;
+use super::{WithParams, MAX_SAMPLE_RATE};
+
+const COMB_TUNINGS: [usize; 8] = [1116, 1188, 1277, 1356, 1422, 1491, 1557, 1617];
+const ALLPASS_TUNINGS: [usize; 4] = [556, 441, 341, 225];
+// Keeps the summed comb outputs near unity for a full-scale input
+const INPUT_GAIN: f32 = 0.015;
+
+#[derive(Clone, Debug)]
+struct Comb {
+    // Sized for `MAX_SAMPLE_RATE`; the first `len` samples are in use
+    buffer: Vec<f32>,
+    len: usize,
+    index: usize,
+    // One-pole lowpass state in the feedback path
+    filtered: f32,
+}
+
+impl Comb {
+    fn new(capacity: usize) -> Self {
+        Self { buffer: vec![0.0; capacity.max(1)], len: capacity.max(1), index: 0, filtered: 0.0 }
+    }
+
+    fn set_len(&mut self, len: usize) {
+        self.len = len.clamp(1, self.buffer.len());
+        self.index = 0;
+    }
+
+    fn process(&mut self, input: f32, feedback: f32, damping: f32) -> f32 {
+        let output = self.buffer[self.index];
+        self.filtered = output * (1.0 - damping) + self.filtered * damping;
+        self.buffer[self.index] = input + self.filtered * feedback;
+        self.index = (self.index + 1) % self.len;
+        output
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Allpass {
+    buffer: Vec<f32>,
+    len: usize,
+    index: usize,
+}
+
+impl Allpass {
+    fn new(capacity: usize) -> Self {
+        Self { buffer: vec![0.0; capacity.max(1)], len: capacity.max(1), index: 0 }
+    }
+
+    fn set_len(&mut self, len: usize) {
+        self.len = len.clamp(1, self.buffer.len());
+        self.index = 0;
+    }
+
+    fn process(&mut self, input: f32) -> f32 {
+        let delayed = self.buffer[self.index];
+        self.buffer[self.index] = input + delayed * 0.5;
+        self.index = (self.index + 1) % self.len;
+        delayed - input
+    }
+}
+
+/// The reverb network on its own, producing only the wet signal.
+#[derive(Clone, Debug)]
+pub struct Reverb {
+    combs: Vec<Comb>,
+    allpasses: Vec<Allpass>,
+    sample_rate: f32,
+}
+
+fn scaled(tuning: usize, sample_rate: f32) -> usize {
+    (tuning as f32 * sample_rate / 44100.0).round() as usize
+}
+
+impl Reverb {
+    /// Buffers are sized for `MAX_SAMPLE_RATE` (or `sample_rate`, if higher), so
+    /// `set_sample_rate` never allocates.
+    pub fn new(sample_rate: f32) -> Self {
+        let capacity_rate = sample_rate.max(MAX_SAMPLE_RATE);
+        let mut reverb = Self {
+            combs: COMB_TUNINGS.iter().map(|&tuning| Comb::new(scaled(tuning, capacity_rate))).collect(),
+            allpasses: ALLPASS_TUNINGS.iter().map(|&tuning| Allpass::new(scaled(tuning, capacity_rate))).collect(),
+            sample_rate: 0.0,
+        };
+        reverb.set_sample_rate(sample_rate);
+        reverb
+    }
+
+    pub fn sample_rate(&self) -> f32 {
+        self.sample_rate
+    }
+
+    /// Rescales the delay lengths for `sample_rate` and clears the tail. Rates
+    /// above the one the buffers were sized for are capped at it.
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.sample_rate = sample_rate;
+        for (comb, &tuning) in self.combs.iter_mut().zip(COMB_TUNINGS.iter()) {
+            comb.set_len(scaled(tuning, sample_rate));
+        }
+        for (allpass, &tuning) in self.allpasses.iter_mut().zip(ALLPASS_TUNINGS.iter()) {
+            allpass.set_len(scaled(tuning, sample_rate));
+        }
+        self.reset();
+    }
+
+    pub fn process(&mut self, sample: f32, params: &ReverbParams) -> f32 {
+        let input = sample * INPUT_GAIN;
+        let feedback = params.feedback();
+        let damping = params.damping_amount();
+        let combs: f32 = self.combs.iter_mut().map(|comb| comb.process(input, feedback, damping)).sum();
+        self.allpasses.iter_mut().fold(combs, |acc, allpass| allpass.process(acc))
+    }
+
+    pub fn reset(&mut self) {
+        for comb in &mut self.combs {
+            comb.buffer.fill(0.0);
+            comb.filtered = 0.0;
+        }
+        for allpass in &mut self.allpasses {
+            allpass.buffer.fill(0.0);
+        }
+    }
+}
+
+#[parameters]
+pub struct ReverbParams {
+    /// Comb feedback of 0.7..0.98; larger rooms ring longer
+    #[param(default = 0.5)]
+    pub room_size: f32,
+    /// High-frequency loss in the feedback path
+    #[param(default = 0.5)]
+    pub damping: f32,
+    /// Crossfade from the dry input (0) to the reverb alone (1)
+    #[param(default = 0.3)]
+    pub wet: f32,
+}
+
+impl ReverbParams {
+    pub fn feedback(&self) -> f32 {
+        0.7 + self.room_size * 0.28
+    }
+
+    pub fn damping_amount(&self) -> f32 {
+        self.damping * 0.4
+    }
+
+    /// Approximate time for the undamped tail to fall 60 dB, from the average
+    /// comb length. Damping shortens the high end of the tail further.
+    pub fn rt60_seconds(&self) -> f32 {
+        let average_delay = COMB_TUNINGS.iter().sum::<usize>() as f32 / COMB_TUNINGS.len() as f32 / 44100.0;
+        -3.0 * average_delay / self.feedback().log10()
+    }
+}
+
+/// Blends the reverb with the dry input by the `wet` parameter.
+pub fn reverb<E>() -> impl FnOnce(&mut Builder<E>) -> ComponentFn<E>
+where
+    E: Clone + Send + 'static,
+{
+    move |builder: &mut Builder<E>| reverb_with_params()(builder).0
+}
+
+pub fn reverb_with_params<E>() -> impl FnOnce(&mut Builder<E>) -> WithParams<E, ReverbParams>
+where
+    E: Clone + Send + 'static,
+{
+    move |builder: &mut Builder<E>| {
+        let params_handle = builder.use_parameters::<ReverbParams>();
+        // Retuned in place if the stream runs at another rate
+        let mut reverb = Reverb::new(48_000.0);
+
+        let component: ComponentFn<E> = Box::new(move |runtime, input, output, sample_rate| {
+            let params = runtime.get_parameters(&params_handle);
+            if sample_rate != reverb.sample_rate() {
+                reverb.set_sample_rate(sample_rate);
+            }
+
+            for (i, (out, &sample)) in output.iter_mut().zip(input.iter()).enumerate() {
+                let p = params[i];
+                *out = sample * (1.0 - p.wet) + reverb.process(sample, &p) * p.wet;
+            }
+        });
+        (component, params_handle)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::testing::SAMPLE_RATE;
+
+    #[test]
+    fn tail_decays_60_db_in_about_the_reported_rt60() {
+        let params = ReverbParams { room_size: 0.5, damping: 0.0, wet: 1.0 };
+        let mut reverb = Reverb::new(SAMPLE_RATE);
+        let tail: Vec<f32> = (0..2 * SAMPLE_RATE as usize)
+            .map(|i| reverb.process(if i == 0 { 1.0 } else { 0.0 }, &params))
+            .collect();
+
+        // Energy in 10 ms windows, against the loudest one
+        let window = (SAMPLE_RATE * 0.01) as usize;
+        let energies: Vec<f32> = tail.chunks(window).map(|chunk| chunk.iter().map(|s| s * s).sum()).collect();
+        let peak = energies.iter().cloned().fold(0.0, f32::max);
+        let decayed = energies.iter().rposition(|&energy| energy > peak * 1e-6).unwrap();
+        let measured = (decayed * window) as f32 / SAMPLE_RATE;
+        let expected = params.rt60_seconds();
+        assert!((measured / expected - 1.0).abs() < 0.25, "measured {} s, expected {} s", measured, expected);
+    }
+
+    #[test]
+    fn changing_the_sample_rate_rescales_without_reallocating() {
+        let mut reverb = Reverb::new(48_000.0);
+        let capacity = reverb.combs[0].buffer.as_ptr();
+        reverb.set_sample_rate(96_000.0);
+        assert_eq!(reverb.combs[0].buffer.as_ptr(), capacity);
+        assert_eq!(reverb.combs[0].len, scaled(COMB_TUNINGS[0], 96_000.0));
+    }
+}
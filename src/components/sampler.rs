@@ -0,0 +1,176 @@
+// One-shot sample playback with allocation-free sample swaps.
+//
+// New samples arrive as events carrying an `Arc<[f32]>`, so the audio thread
+// only ever moves reference counts around. The buffer is built on a background
+// thread by a `SampleLoader`, which keeps a reference to every sample it hands
+// out; when the sampler drops the one it replaced, the loader's reference is
+// still there, and the memory is freed later by `SampleLoader::collect` on the
+// loader's thread instead of mid-callback.
+use std::sync::Arc;
+use crate::core::*;
+use crate::modulators::NoteEvent;
+
+pub trait SampleEvent {
+    /// The sample this event loads, if it carries one.
+    fn sample(&self) -> Option<&Arc<[f32]>>;
+}
+
+/// Builds sample buffers off the audio thread and frees them once no sampler or
+/// in-flight event references them.
+#[derive(Default)]
+pub struct SampleLoader {
+    loaded: Vec<Arc<[f32]>>,
+}
+
+impl SampleLoader {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Copies `samples` into a buffer ready to send in an event, and frees any
+    /// earlier samples that have been let go since the last call.
+    pub fn load(&mut self, samples: &[f32]) -> Arc<[f32]> {
+        self.collect();
+        let sample: Arc<[f32]> = samples.into();
+        self.loaded.push(sample.clone());
+        sample
+    }
+
+    /// Frees samples only the loader still references.
+    pub fn collect(&mut self) {
+        self.loaded.retain(|sample| Arc::strong_count(sample) > 1);
+    }
+
+    /// Samples the loader is keeping alive, including ones still in use.
+    pub fn len(&self) -> usize {
+        self.loaded.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.loaded.is_empty()
+    }
+}
+
+/// Plays a sample once from the start on each trigger, scaled by velocity.
+#[derive(Default)]
+pub struct Sampler {
+    sample: Option<Arc<[f32]>>,
+    // Loaded but waiting for the next trigger, so a playing sample isn't cut
+    pending: Option<Arc<[f32]>>,
+    position: usize,
+    gain: f32,
+}
+
+impl Sampler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Swaps in `sample` at the next trigger. Whatever it replaces is only
+    /// dropped here, so buffers should come from a `SampleLoader` to keep the
+    /// deallocation off the audio thread.
+    pub fn load(&mut self, sample: Arc<[f32]>) {
+        self.pending = Some(sample);
+    }
+
+    pub fn trigger(&mut self, velocity: f32) {
+        if let Some(sample) = self.pending.take() {
+            self.sample = Some(sample);
+        }
+        self.position = 0;
+        self.gain = velocity;
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.sample.as_ref().is_some_and(|sample| self.position < sample.len())
+    }
+
+    pub fn next_sample(&mut self) -> f32 {
+        match &self.sample {
+            Some(sample) if self.position < sample.len() => {
+                let value = sample[self.position] * self.gain;
+                self.position += 1;
+                value
+            }
+            _ => 0.0,
+        }
+    }
+}
+
+/// Plays the most recently loaded sample on each note-on. It's a generator, so its
+/// input is ignored; note-offs are too, as every trigger plays to the end.
+pub fn sampler<E>() -> impl FnOnce(&mut Builder<E>) -> ComponentFn<E>
+where
+    E: NoteEvent + SampleEvent + Clone + Send + 'static,
+{
+    move |_builder: &mut Builder<E>| {
+        let mut sampler = Sampler::new();
+
+        Box::new(move |runtime, _input, output, _sample_rate| {
+            for event in runtime.events() {
+                if let Some(sample) = event.sample() {
+                    sampler.load(sample.clone());
+                }
+                if let Some((_, velocity)) = event.note_on() {
+                    sampler.trigger(velocity);
+                }
+            }
+            for out in output.iter_mut() {
+                *out = sampler.next_sample();
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::testing::SAMPLE_RATE;
+
+    #[derive(Clone)]
+    enum Event {
+        Load(Arc<[f32]>),
+        NoteOn,
+    }
+
+    impl NoteEvent for Event {
+        fn note_on(&self) -> Option<(u8, f32)> {
+            matches!(self, Event::NoteOn).then_some((60, 1.0))
+        }
+
+        fn note_off(&self) -> Option<u8> {
+            None
+        }
+    }
+
+    impl SampleEvent for Event {
+        fn sample(&self) -> Option<&Arc<[f32]>> {
+            match self {
+                Event::Load(sample) => Some(sample),
+                Event::NoteOn => None,
+            }
+        }
+    }
+
+    #[test]
+    fn loaded_samples_play_on_the_next_note_and_are_freed_by_the_loader() {
+        let mut loader = SampleLoader::new();
+        let (_bus, builder) = new::<Event>();
+        let mut runtime = builder.build(sampler());
+        let mut output = [0.0; 4];
+        let mut tick = |runtime: &mut Runtime<Event>, event| {
+            runtime.tick(SAMPLE_RATE, event, &[0.0; 4], &mut output);
+            output
+        };
+
+        tick(&mut runtime, Some(Event::Load(loader.load(&[0.5, 0.25]))));
+        assert_eq!(tick(&mut runtime, Some(Event::NoteOn)), [0.5, 0.25, 0.0, 0.0]);
+
+        tick(&mut runtime, Some(Event::Load(loader.load(&[1.0]))));
+        assert_eq!(loader.len(), 2);
+        assert_eq!(tick(&mut runtime, Some(Event::NoteOn)), [1.0, 0.0, 0.0, 0.0]);
+        // The sampler let go of the first sample; the loader frees it off the audio thread
+        loader.collect();
+        assert_eq!(loader.len(), 1);
+    }
+}
@@ -0,0 +1,15 @@
+// Copies the signal passing through onto a bus for other parts of the graph.
+use crate::core::*;
+
+/// Writes its input to `bus` and passes it through unchanged.
+pub fn send<E>(bus: StateHandle<Bus>) -> impl FnOnce(&mut Builder<E>) -> ComponentFn<E>
+where
+    E: Clone + Send + 'static,
+{
+    move |_builder: &mut Builder<E>| -> ComponentFn<E> {
+        Box::new(move |runtime, input, output, _sample_rate| {
+            runtime.get_mut(&bus).write(input);
+            output.copy_from_slice(input);
+        })
+    }
+}
@@ -0,0 +1,124 @@
+// Mid/side stereo width.
+//
+// Components carry a single buffer, so the width component reads and writes
+// interleaved frames (L, R, ...), the layout a device buffer already has, and
+// checks `Runtime::channels` for the frame size. `StereoWidth` itself works on
+// separate samples for use inside hand-written components.
+use crate::core::*;
+use crate::parameters;
+use super::WithParams;
+
+/// Scales the side (L - R) signal against the mid (L + R): 0.0 collapses to
+/// mono, 1.0 leaves the image untouched and larger values widen it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct StereoWidth {
+    pub width: f32,
+}
+
+impl Default for StereoWidth {
+    fn default() -> Self {
+        Self { width: 1.0 }
+    }
+}
+
+impl StereoWidth {
+    pub fn new(width: f32) -> Self {
+        Self { width: width.max(0.0) }
+    }
+
+    pub fn process(&self, left: f32, right: f32) -> (f32, f32) {
+        let mid = (left + right) * 0.5;
+        let side = (left - right) * 0.5 * self.width;
+        (mid + side, mid - side)
+    }
+}
+
+#[parameters]
+pub struct StereoWidthParams {
+    /// Width of 0..2, linear; 1.0 (unchanged) by default
+    #[param(default = 0.5)]
+    pub width: f32,
+}
+
+impl StereoWidthParams {
+    pub fn width(&self) -> f32 {
+        self.width * 2.0
+    }
+}
+
+/// Processes interleaved frames, widening the first two channels of each; any
+/// further channels, and everything on a mono runtime, pass through.
+pub fn stereo_width<E>() -> impl FnOnce(&mut Builder<E>) -> ComponentFn<E>
+where
+    E: Clone + Send + 'static,
+{
+    move |builder: &mut Builder<E>| stereo_width_with_params()(builder).0
+}
+
+pub fn stereo_width_with_params<E>() -> impl FnOnce(&mut Builder<E>) -> WithParams<E, StereoWidthParams>
+where
+    E: Clone + Send + 'static,
+{
+    move |builder: &mut Builder<E>| {
+        let params_handle = builder.use_parameters::<StereoWidthParams>();
+
+        let component: ComponentFn<E> = Box::new(move |runtime, input, output, _sample_rate| {
+            output.copy_from_slice(input);
+            let channels = runtime.channels();
+            // Mono buffers have no image to change
+            if channels < 2 {
+                return;
+            }
+            let params = runtime.get_parameters(&params_handle);
+            // Each frame reads the width at its first sample's position in the block
+            for (frame, out) in output.chunks_exact_mut(channels).enumerate() {
+                let (left, right) = StereoWidth::new(params[frame * channels].width()).process(out[0], out[1]);
+                out[0] = left;
+                out[1] = right;
+            }
+        });
+        (component, params_handle)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn zero_width_is_mono_and_unit_width_passes_through() {
+        let (left, right) = StereoWidth::new(0.0).process(1.0, -0.5);
+        assert_eq!(left, 0.25);
+        assert_eq!(right, 0.25);
+        assert_eq!(StereoWidth::new(1.0).process(1.0, -0.5), (1.0, -0.5));
+    }
+
+    // Interleaved frames of (1.0, -1.0, 0.5, ...) through a runtime with `channels`
+    // channels, at width 0
+    fn collapse(channels: usize) -> Vec<f32> {
+        let (_bus, builder) = new::<()>();
+        let mut handle = None;
+        let mut runtime = builder.build(|builder| {
+            let (component, params) = stereo_width_with_params()(builder);
+            handle = Some(params);
+            component
+        });
+        runtime.set_channels(channels);
+        runtime.set_base(handle.unwrap(), "width", 0.0).unwrap();
+        let input: Vec<f32> = (0..BUFFER_SIZE / channels * channels)
+            .map(|i| [1.0, -1.0, 0.5][(i % channels).min(2)])
+            .collect();
+        let mut output = vec![0.0; input.len()];
+        runtime.tick(48_000.0, None, &input, &mut output);
+        output
+    }
+
+    #[test]
+    fn frames_are_sized_by_the_channel_count() {
+        assert!(collapse(2).iter().all(|&sample| sample == 0.0));
+        // The third channel passes through
+        assert!(collapse(3).chunks(3).all(|frame| frame == [0.0, 0.0, 0.5]));
+        // Mono passes everything through
+        assert!(collapse(1).iter().all(|&sample| sample == 1.0));
+    }
+}
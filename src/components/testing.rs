@@ -0,0 +1,31 @@
+// Helpers shared by the components' tests.
+use crate::core::*;
+
+pub const SAMPLE_RATE: f32 = 48_000.0;
+
+/// Builds `component` into a graph of its own and runs `input` through it.
+pub fn process(component: impl FnOnce(&mut Builder<()>) -> ComponentFn<()>, input: &[f32]) -> Vec<f32> {
+    let (_bus, builder) = new::<()>();
+    let mut runtime = builder.build(component);
+    let mut output = vec![0.0; input.len()];
+    runtime.tick(SAMPLE_RATE, None, input, &mut output);
+    output
+}
+
+pub fn sine(hz: f32, len: usize) -> Vec<f32> {
+    (0..len).map(|i| (std::f32::consts::TAU * hz * i as f32 / SAMPLE_RATE).sin()).collect()
+}
+
+pub fn rms(samples: &[f32]) -> f32 {
+    (samples.iter().map(|sample| sample * sample).sum::<f32>() / samples.len() as f32).sqrt()
+}
+
+/// Amplitude of the `hz` partial of `samples`, from a single DFT bin. Accurate
+/// when `samples` spans a whole number of its cycles, or many of them.
+pub fn amplitude_at(samples: &[f32], hz: f32) -> f32 {
+    let (re, im) = samples.iter().enumerate().fold((0.0f64, 0.0f64), |(re, im), (i, &sample)| {
+        let phase = std::f64::consts::TAU * hz as f64 * i as f64 / SAMPLE_RATE as f64;
+        (re + sample as f64 * phase.cos(), im + sample as f64 * phase.sin())
+    });
+    (2.0 * (re * re + im * im).sqrt() / samples.len() as f64) as f32
+}
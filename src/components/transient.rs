@@ -0,0 +1,185 @@
+// Transient shaper: boosts or cuts the attack and the sustain of a signal
+// independently of its level.
+//
+// Two pairs of envelope followers split the signal. For the attack, a fast and
+// a slow follower share a release but differ in attack time; the fast one leads
+// at every onset, and how far it leads is the transient. For the sustain, both
+// attack instantly but release at different rates; the slow one stays up through
+// the tail after the fast one has fallen. Each difference is scaled to 0..1 of
+// the level and weights its gain, so quiet and loud hits are shaped alike.
+use crate::core::*;
+use crate::parameters;
+use super::WithParams;
+use super::compressor::smoothing_coefficient;
+
+const GAIN_RANGE_DB: f32 = 24.0;
+
+#[parameters]
+pub struct TransientShaperParams {
+    /// -24..+24 dB applied to transients; 0 dB by default
+    #[param(default = 0.5, unit_min = -24.0, unit_max = 24.0)]
+    pub attack: f32,
+    /// -24..+24 dB applied to the body after them; 0 dB by default
+    #[param(default = 0.5, unit_min = -24.0, unit_max = 24.0)]
+    pub sustain: f32,
+}
+
+impl TransientShaperParams {
+    pub fn attack_db(&self) -> f32 {
+        (self.attack * 2.0 - 1.0) * GAIN_RANGE_DB
+    }
+
+    pub fn sustain_db(&self) -> f32 {
+        (self.sustain * 2.0 - 1.0) * GAIN_RANGE_DB
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Follower {
+    attack_ms: f32,
+    release_ms: f32,
+    attack: f32,
+    release: f32,
+    level: f32,
+}
+
+impl Follower {
+    fn new(attack_ms: f32, release_ms: f32, sample_rate: f32) -> Self {
+        let mut follower = Self { attack_ms, release_ms, attack: 0.0, release: 0.0, level: 0.0 };
+        follower.set_sample_rate(sample_rate);
+        follower
+    }
+
+    fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.attack = if self.attack_ms > 0.0 { smoothing_coefficient(self.attack_ms, sample_rate) } else { 0.0 };
+        self.release = smoothing_coefficient(self.release_ms, sample_rate);
+    }
+
+    fn follow(&mut self, level: f32) -> f32 {
+        let coefficient = if level > self.level { self.attack } else { self.release };
+        self.level = coefficient * self.level + (1.0 - coefficient) * level;
+        self.level
+    }
+}
+
+/// Envelope state for one channel.
+#[derive(Clone, Copy, Debug)]
+pub struct TransientShaper {
+    attack_fast: Follower,
+    attack_slow: Follower,
+    sustain_fast: Follower,
+    sustain_slow: Follower,
+    sample_rate: f32,
+}
+
+impl TransientShaper {
+    pub fn new(sample_rate: f32) -> Self {
+        Self {
+            attack_fast: Follower::new(1.0, 100.0, sample_rate),
+            attack_slow: Follower::new(30.0, 100.0, sample_rate),
+            sustain_fast: Follower::new(0.0, 40.0, sample_rate),
+            sustain_slow: Follower::new(0.0, 400.0, sample_rate),
+            sample_rate,
+        }
+    }
+
+    pub fn sample_rate(&self) -> f32 {
+        self.sample_rate
+    }
+
+    /// Recomputes the followers' coefficients for `sample_rate`, keeping their levels.
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.sample_rate = sample_rate;
+        for follower in [&mut self.attack_fast, &mut self.attack_slow, &mut self.sustain_fast, &mut self.sustain_slow] {
+            follower.set_sample_rate(sample_rate);
+        }
+    }
+
+    /// Shapes one sample, boosting or cutting its transient and sustain parts by
+    /// up to the given gains.
+    pub fn process(&mut self, sample: f32, attack_db: f32, sustain_db: f32) -> f32 {
+        let level = sample.abs();
+        let attack_fast = self.attack_fast.follow(level);
+        let attack_slow = self.attack_slow.follow(level);
+        let sustain_fast = self.sustain_fast.follow(level);
+        let sustain_slow = self.sustain_slow.follow(level);
+
+        let transient = ((attack_fast - attack_slow) / (attack_fast + 1e-9)).max(0.0);
+        let body = ((sustain_slow - sustain_fast) / (sustain_slow + 1e-9)).max(0.0);
+        let gain_db = attack_db * transient + sustain_db * body;
+        sample * 10f32.powf(gain_db / 20.0)
+    }
+
+    pub fn reset(&mut self) {
+        for follower in [&mut self.attack_fast, &mut self.attack_slow, &mut self.sustain_fast, &mut self.sustain_slow] {
+            follower.level = 0.0;
+        }
+    }
+}
+
+pub fn transient_shaper<E>() -> impl FnOnce(&mut Builder<E>) -> ComponentFn<E>
+where
+    E: Clone + Send + 'static,
+{
+    move |builder: &mut Builder<E>| transient_shaper_with_params()(builder).0
+}
+
+pub fn transient_shaper_with_params<E>() -> impl FnOnce(&mut Builder<E>) -> WithParams<E, TransientShaperParams>
+where
+    E: Clone + Send + 'static,
+{
+    move |builder: &mut Builder<E>| {
+        let params_handle = builder.use_parameters::<TransientShaperParams>();
+        // Retuned if the stream runs at another rate
+        let mut shaper = TransientShaper::new(48_000.0);
+
+        let component: ComponentFn<E> = Box::new(move |runtime, input, output, sample_rate| {
+            let params = runtime.get_parameters(&params_handle);
+            if sample_rate != shaper.sample_rate() {
+                shaper.set_sample_rate(sample_rate);
+            }
+            for (i, (out, &sample)) in output.iter_mut().zip(input.iter()).enumerate() {
+                let p = params[i];
+                *out = shaper.process(sample, p.attack_db(), p.sustain_db());
+            }
+        });
+        (component, params_handle)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::testing::{process, rms, sine, SAMPLE_RATE};
+
+    // 50 ms of silence, then a steady 200 Hz tone
+    fn burst() -> Vec<f32> {
+        let mut input = vec![0.0; 2_400];
+        input.extend(sine(200.0, 24_000));
+        input
+    }
+
+    #[test]
+    fn attack_boost_lifts_the_onset_more_than_the_body() {
+        let input = burst();
+        let mut shaper = TransientShaper::new(SAMPLE_RATE);
+        let output: Vec<f32> = input.iter().map(|&sample| shaper.process(sample, 12.0, 0.0)).collect();
+
+        let onset = 2_400..2_400 + 480;
+        let gain_at = |range: std::ops::Range<usize>| rms(&output[range.clone()]) / rms(&input[range]);
+        let body = output.len() - 4_800..output.len();
+        // The followers ripple with a low tone, so the body keeps a little of the boost
+        let (onset_gain, body_gain) = (gain_at(onset), gain_at(body));
+        assert!(onset_gain > 2.5, "{}", onset_gain);
+        assert!(body_gain < 1.5 && onset_gain > 2.0 * body_gain, "{} {}", onset_gain, body_gain);
+    }
+
+    #[test]
+    fn neutral_settings_pass_the_burst_through() {
+        let input = burst();
+        let output = process(transient_shaper(), &input);
+        for (out, sample) in output.iter().zip(&input) {
+            assert!((out - sample).abs() < 1e-6);
+        }
+    }
+}
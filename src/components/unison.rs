@@ -0,0 +1,168 @@
+// Unison: several detuned copies of a wavetable oscillator, summed.
+//
+// Voices are spread evenly across the detune range, from -detune to +detune, and
+// start at staggered phases so they don't all line up on the first cycle. On a
+// two-channel runtime they're also panned evenly across the stereo field, and the
+// output is interleaved stereo frames like `stereo_width`'s; otherwise the spread
+// is ignored and the voices are summed to mono. It's a generator, so its input is
+// ignored.
+use crate::core::*;
+use crate::parameters;
+use super::WithParams;
+use super::wavetable::Wavetable;
+
+const MAX_DETUNE_CENTS: f32 = 100.0;
+
+#[parameters]
+pub struct UnisonParams {
+    /// Normalized pitch (MIDI note / 127), middle C by default
+    #[param(default = 0.4724)]
+    pub frequency: f32,
+    /// 0 = first waveform, 1 = last
+    pub position: f32,
+    /// Outermost voices' offset of 0..100 cents, linear; 20 cents by default
+    #[param(default = 0.2, unit_min = 0.0, unit_max = 100.0)]
+    pub detune: f32,
+    /// 0 = one voice, 1 = the component's maximum; read once per block
+    #[param(default = 1.0, block_rate)]
+    pub voices: f32,
+    /// 0 = all voices centred, 1 = outermost voices hard left and right
+    #[param(default = 0.5)]
+    pub spread: f32,
+}
+
+impl UnisonParams {
+    pub fn detune_cents(&self) -> f32 {
+        self.detune * MAX_DETUNE_CENTS
+    }
+
+    /// Number of voices out of `max_voices`, at least one.
+    pub fn voice_count(&self, max_voices: usize) -> usize {
+        1 + (self.voices.clamp(0.0, 1.0) * (max_voices.max(1) - 1) as f32).round() as usize
+    }
+}
+
+/// Phases of up to a fixed number of voices reading one wavetable.
+pub struct Unison {
+    wavetable: Wavetable,
+    phases: Vec<f32>,
+}
+
+impl Unison {
+    pub fn new(wavetable: Wavetable, max_voices: usize) -> Self {
+        let max_voices = max_voices.max(1);
+        // Golden-ratio steps keep any number of voices well apart
+        let phases = (0..max_voices).map(|voice| (voice as f32 * 0.618_034).fract()).collect();
+        Self { wavetable, phases }
+    }
+
+    pub fn max_voices(&self) -> usize {
+        self.phases.len()
+    }
+
+    /// Where voice `voice` of `voices` sits in the spread, from -1 to 1.
+    pub fn voice_position(voice: usize, voices: usize) -> f32 {
+        if voices < 2 {
+            0.0
+        } else {
+            voice as f32 / (voices - 1) as f32 * 2.0 - 1.0
+        }
+    }
+
+    /// Renders one frame of `voices` voices around `hz` as a left and right
+    /// sample. `spread` of 0..1 pans the outermost voices up to hard left and
+    /// right; summed, the two sides give the mono mix.
+    pub fn process(&mut self, sample_rate: f32, hz: f32, position: f32, voices: usize, detune_cents: f32, spread: f32) -> (f32, f32) {
+        let voices = voices.clamp(1, self.phases.len());
+        let gain = 1.0 / (voices as f32).sqrt();
+        let (mut left, mut right) = (0.0, 0.0);
+        for (voice, phase) in self.phases.iter_mut().take(voices).enumerate() {
+            let offset = Self::voice_position(voice, voices);
+            let sample = self.wavetable.read(*phase, position) * gain;
+            *phase += hz * 2f32.powf(offset * detune_cents / 1200.0) / sample_rate;
+            *phase -= phase.floor();
+
+            // Equal-power pan, with a centred voice split evenly
+            let angle = (offset * spread.clamp(0.0, 1.0) + 1.0) * std::f32::consts::FRAC_PI_4;
+            left += sample * angle.cos() * std::f32::consts::FRAC_1_SQRT_2;
+            right += sample * angle.sin() * std::f32::consts::FRAC_1_SQRT_2;
+        }
+        (left, right)
+    }
+}
+
+/// Unison oscillator with up to `max_voices` voices.
+pub fn unison<E>(wavetable: Wavetable, max_voices: usize) -> impl FnOnce(&mut Builder<E>) -> ComponentFn<E>
+where
+    E: Clone + Send + 'static,
+{
+    move |builder: &mut Builder<E>| unison_with_params(wavetable, max_voices)(builder).0
+}
+
+pub fn unison_with_params<E>(wavetable: Wavetable, max_voices: usize) -> impl FnOnce(&mut Builder<E>) -> WithParams<E, UnisonParams>
+where
+    E: Clone + Send + 'static,
+{
+    move |builder: &mut Builder<E>| {
+        let params_handle = builder.use_parameters::<UnisonParams>();
+        let mut unison = Unison::new(wavetable, max_voices);
+
+        let component: ComponentFn<E> = Box::new(move |runtime, _input, output, sample_rate| {
+            let params = runtime.get_parameters(&params_handle);
+            let voices = params[0].voice_count(unison.max_voices());
+            if runtime.channels() == 2 {
+                // Each frame reads the parameters at its left sample's position
+                for (frame, out) in output.chunks_mut(2).enumerate() {
+                    let p = params[frame * 2];
+                    let (left, right) = unison.process(sample_rate, pitch_to_hz(p.frequency), p.position, voices, p.detune_cents(), p.spread);
+                    out[0] = left;
+                    if let Some(out) = out.get_mut(1) {
+                        *out = right;
+                    }
+                }
+            } else {
+                for (i, out) in output.iter_mut().enumerate() {
+                    let p = params[i];
+                    let (left, right) = unison.process(sample_rate, pitch_to_hz(p.frequency), p.position, voices, p.detune_cents(), 0.0);
+                    *out = left + right;
+                }
+            }
+        });
+        (component, params_handle)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::testing::SAMPLE_RATE;
+
+    fn sine() -> Wavetable {
+        let len = 2048;
+        Wavetable::new(vec![(0..len).map(|i| (std::f32::consts::TAU * i as f32 / len as f32).sin()).collect()])
+    }
+
+    #[test]
+    fn two_detuned_voices_beat_at_their_frequency_difference() {
+        let mut unison = Unison::new(sine(), 2);
+        let output: Vec<f32> = (0..2 * SAMPLE_RATE as usize)
+            .map(|_| {
+                let (left, right) = unison.process(SAMPLE_RATE, 440.0, 0.0, 2, 10.0, 0.0);
+                left + right
+            })
+            .collect();
+
+        // Peak level of each 5 ms window traces the beating envelope
+        let envelope: Vec<f32> = output.chunks(240).map(|window| window.iter().fold(0.0f32, |peak, s| peak.max(s.abs()))).collect();
+        let peak = envelope.iter().cloned().fold(0.0, f32::max);
+        let trough = envelope.iter().cloned().fold(f32::INFINITY, f32::min);
+        // Two voices at 1/sqrt(2) each reinforce to sqrt(2) and cancel to nothing
+        assert!((peak - std::f32::consts::SQRT_2).abs() < 0.05, "{}", peak);
+        assert!(trough < 0.15, "{}", trough);
+
+        let rises: Vec<usize> = (1..envelope.len()).filter(|&i| envelope[i - 1] < 0.7 && envelope[i] >= 0.7).collect();
+        let beat_period_s = (rises[rises.len() - 1] - rises[0]) as f32 * 0.005 / (rises.len() - 1) as f32;
+        let beat_hz = 440.0 * (2f32.powf(10.0 / 1200.0) - 2f32.powf(-10.0 / 1200.0));
+        assert!((beat_period_s * beat_hz - 1.0).abs() < 0.03, "{} s", beat_period_s);
+    }
+}
@@ -0,0 +1,228 @@
+// Wavetable oscillator: a stack of single-cycle waveforms scanned by a position
+// parameter. Reads interpolate linearly within a waveform and between neighbouring
+// waveforms. It's a generator, so its input is ignored.
+use crate::core::*;
+use crate::parameters;
+use super::WithParams;
+
+#[parameters]
+pub struct WtOscParams {
+    /// Normalized pitch (MIDI note / 127), middle C by default
+    #[param(default = 0.4724)]
+    pub frequency: f32,
+    /// 0 = first waveform, 1 = last
+    pub position: f32,
+}
+
+/// Single-cycle waveforms of equal length.
+pub struct Wavetable {
+    waveforms: Vec<Vec<f32>>,
+}
+
+impl Wavetable {
+    pub fn new(waveforms: Vec<Vec<f32>>) -> Self {
+        assert!(!waveforms.is_empty(), "wavetable needs at least one waveform");
+        let len = waveforms[0].len();
+        assert!(len > 0, "wavetable waveforms must not be empty");
+        assert!(waveforms.iter().all(|w| w.len() == len), "wavetable waveforms must have equal length");
+        Self { waveforms }
+    }
+
+    pub fn waveform_len(&self) -> usize {
+        self.waveforms[0].len()
+    }
+
+    fn read_waveform(waveform: &[f32], phase: f32) -> f32 {
+        let index = phase * waveform.len() as f32;
+        let i0 = index as usize % waveform.len();
+        let i1 = (i0 + 1) % waveform.len();
+        let frac = index.fract();
+        waveform[i0] + (waveform[i1] - waveform[i0]) * frac
+    }
+
+    /// Reads at `phase` (0..1 through a cycle) and `position` (0..1 through the table).
+    pub fn read(&self, phase: f32, position: f32) -> f32 {
+        let scan = position.clamp(0.0, 1.0) * (self.waveforms.len() - 1) as f32;
+        let w0 = scan as usize;
+        let w1 = (w0 + 1).min(self.waveforms.len() - 1);
+        let a = Self::read_waveform(&self.waveforms[w0], phase);
+        let b = Self::read_waveform(&self.waveforms[w1], phase);
+        a + (b - a) * scan.fract()
+    }
+}
+
+// === Hard Sync ===
+/// Cycle boundaries of a master oscillator for the current block, read by slaves
+/// to reset their phase. It lives in `use_state`, so a runtime has one bus, and
+/// the master has to process before its slaves each block.
+pub struct SyncBus {
+    // How far into each sample the master wrapped, as a fraction of that sample
+    wraps: [Option<f32>; BUFFER_SIZE],
+}
+
+impl Default for SyncBus {
+    fn default() -> Self {
+        Self { wraps: [None; BUFFER_SIZE] }
+    }
+}
+
+impl SyncBus {
+    /// Advances a master phasor by `increment` at sample `index`, recording
+    /// whether it completed a cycle. Masters call this for every sample.
+    pub fn advance(&mut self, index: usize, phase: &mut f32, increment: f32) {
+        *phase += increment;
+        self.wraps[index % BUFFER_SIZE] = if *phase >= 1.0 && increment > 0.0 {
+            *phase -= phase.floor();
+            Some(*phase / increment)
+        } else {
+            None
+        };
+    }
+
+    /// Advances a slave phasor, restarting it wherever the master wrapped.
+    pub fn follow(&self, index: usize, phase: &mut f32, increment: f32) {
+        match self.wraps[index % BUFFER_SIZE] {
+            Some(elapsed) => *phase = elapsed * increment,
+            None => *phase += increment,
+        }
+        *phase -= phase.floor();
+    }
+}
+
+#[derive(Clone, Copy)]
+pub enum OscSync {
+    /// Publishes this oscillator's cycle boundaries
+    Master(StateHandle<SyncBus>),
+    /// Restarts this oscillator's cycle on the master's boundaries
+    Slave(StateHandle<SyncBus>),
+}
+
+pub fn wt_osc<E>(wavetable: Wavetable) -> impl FnOnce(&mut Builder<E>) -> ComponentFn<E>
+where
+    E: Clone + Send + 'static,
+{
+    move |builder: &mut Builder<E>| wt_osc_with_params(wavetable)(builder).0
+}
+
+pub fn wt_osc_with_params<E>(wavetable: Wavetable) -> impl FnOnce(&mut Builder<E>) -> WithParams<E, WtOscParams>
+where
+    E: Clone + Send + 'static,
+{
+    move |builder: &mut Builder<E>| build_wt_osc(builder, wavetable, None)
+}
+
+/// Hard sync between oscillators: get a bus from `builder.use_state::<SyncBus>()`
+/// and build the master with `OscSync::Master` and slaves with `OscSync::Slave`.
+pub fn wt_osc_synced<E>(wavetable: Wavetable, sync: OscSync) -> impl FnOnce(&mut Builder<E>) -> ComponentFn<E>
+where
+    E: Clone + Send + 'static,
+{
+    move |builder: &mut Builder<E>| wt_osc_synced_with_params(wavetable, sync)(builder).0
+}
+
+pub fn wt_osc_synced_with_params<E>(wavetable: Wavetable, sync: OscSync) -> impl FnOnce(&mut Builder<E>) -> WithParams<E, WtOscParams>
+where
+    E: Clone + Send + 'static,
+{
+    move |builder: &mut Builder<E>| build_wt_osc(builder, wavetable, Some(sync))
+}
+
+fn build_wt_osc<E>(builder: &mut Builder<E>, wavetable: Wavetable, sync: Option<OscSync>) -> WithParams<E, WtOscParams>
+where
+    E: Clone + Send + 'static,
+{
+    let params_handle = builder.use_parameters::<WtOscParams>();
+    let mut phase = 0.0f32;
+
+    let component: ComponentFn<E> = Box::new(move |runtime, _input, output, sample_rate| {
+        let params = runtime.get_parameters(&params_handle);
+        for (i, sample) in output.iter_mut().enumerate() {
+            let p = params[i];
+            *sample = wavetable.read(phase, p.position);
+            let increment = pitch_to_hz(p.frequency) / sample_rate;
+            match sync {
+                None => {
+                    phase += increment;
+                    phase -= phase.floor();
+                }
+                Some(OscSync::Master(bus)) => runtime.get_mut(&bus).advance(i, &mut phase, increment),
+                Some(OscSync::Slave(bus)) => runtime.get(&bus).follow(i, &mut phase, increment),
+            }
+        }
+    });
+    (component, params_handle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::testing::{amplitude_at, SAMPLE_RATE};
+
+    const A4: f32 = 69.0 / 127.0;
+
+    // A sine at position 0 and a square at position 1
+    fn sine_and_square() -> Wavetable {
+        let len = 2048;
+        let sine = (0..len).map(|i| (std::f32::consts::TAU * i as f32 / len as f32).sin()).collect();
+        let square = (0..len).map(|i| if i < len / 2 { 1.0 } else { -1.0 }).collect();
+        Wavetable::new(vec![sine, square])
+    }
+
+    fn render_a4(position: f32) -> Vec<f32> {
+        let (_bus, builder) = new::<()>();
+        let mut params = None;
+        let mut runtime = builder.build(|builder| {
+            let (osc, handle) = wt_osc_with_params(sine_and_square())(builder);
+            params = Some(handle);
+            osc
+        });
+        let params = params.unwrap();
+        runtime.set_base(params, "frequency", A4).unwrap();
+        runtime.set_base(params, "position", position).unwrap();
+        runtime.render(SAMPLE_RATE, SAMPLE_RATE as usize)
+    }
+
+    #[test]
+    fn first_waveform_plays_a_pure_sine_at_the_set_pitch() {
+        let output = render_a4(0.0);
+        assert!((amplitude_at(&output, 440.0) - 1.0).abs() < 0.01);
+        for harmonic in 2..6 {
+            assert!(amplitude_at(&output, 440.0 * harmonic as f32) < 0.01);
+        }
+    }
+
+    #[test]
+    fn last_waveform_has_the_square_harmonics() {
+        let output = render_a4(1.0);
+        let fundamental = amplitude_at(&output, 440.0);
+        assert!((fundamental - 4.0 / std::f32::consts::PI).abs() < 0.02);
+        assert!((amplitude_at(&output, 1320.0) / fundamental - 1.0 / 3.0).abs() < 0.02);
+        assert!(amplitude_at(&output, 880.0) < 0.01);
+    }
+
+    #[test]
+    fn slave_restarts_its_cycle_at_each_master_boundary() {
+        // 110 Hz master, 294 Hz slave
+        let (master_increment, slave_increment) = (110.0 / SAMPLE_RATE, 294.0 / SAMPLE_RATE);
+        let mut bus = SyncBus::default();
+        let (mut master, mut slave) = (0.0f32, 0.0f32);
+        let (mut boundaries, mut slave_wraps) = (0, 0);
+        for _ in 0..8 {
+            for i in 0..BUFFER_SIZE {
+                bus.advance(i, &mut master, master_increment);
+                let previous = slave;
+                bus.follow(i, &mut slave, slave_increment);
+                if master < master_increment {
+                    boundaries += 1;
+                    // Restarted part way through the sample, as far in as the master
+                    assert!((slave / slave_increment - master / master_increment).abs() < 1e-3);
+                } else if slave < previous {
+                    slave_wraps += 1;
+                }
+            }
+        }
+        assert!(boundaries >= 4, "{boundaries}");
+        // The slave still completes cycles of its own between boundaries
+        assert!(slave_wraps >= 2 * boundaries, "{slave_wraps}");
+    }
+}
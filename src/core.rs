@@ -1,13 +1,34 @@
 //! Core framework types and traits
 
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::marker::PhantomData;
 use std::any::{Any, TypeId};
-use std::cell::UnsafeCell;
+use std::cell::{Cell, UnsafeCell};
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{fence, AtomicBool, AtomicU32, AtomicU64, Ordering};
 use crossbeam::channel::{Receiver, Sender, unbounded};
 
+// Emitted events a runtime holds at once; `Runtime::emit` drops any beyond this
+// rather than allocate on the audio thread
+const EMIT_CAPACITY: usize = 64;
+
 pub type ComponentFn<E> = Box<dyn FnMut(&mut Runtime<E>, &[f32], &mut [f32], f32) + Send>;
 
+/// Runs after every block with the runtime and the block's output, for side
+/// effects like metering or sending MIDI clock. Called on the audio thread, so it
+/// shouldn't allocate or block.
+pub type TickHook<E> = Box<dyn FnMut(&Runtime<E>, &[f32]) + Send>;
+
+/// Number of per-sample values a modulator or parameter runtime computes per tick.
+pub const BUFFER_SIZE: usize = 256;
+
+/// One block of zeros, the input for generator graphs with nothing to read.
+/// Sliced to length, it stands in for input without a buffer of its own.
+pub static SILENCE: [f32; BUFFER_SIZE] = [0.0; BUFFER_SIZE];
+
+/// Output samples at or below this magnitude (about -100 dBFS) count as silence.
+pub const SILENCE_THRESHOLD: f32 = 1e-5;
+
 // === Event Bus ===
 pub struct EventBus<E> {
     pub tx: Sender<E>,
@@ -38,6 +59,18 @@ impl<E> Clone for EventBus<E> {
     }
 }
 
+// === State Snapshots ===
+type StateCloner = fn(&(dyn Any + Send)) -> Box<dyn Any + Send>;
+
+fn clone_state<T: Clone + Send + 'static>(state: &(dyn Any + Send)) -> Box<dyn Any + Send> {
+    Box::new(state.downcast_ref::<T>().unwrap().clone())
+}
+
+/// The cloneable state of a runtime at one moment, from `Runtime::snapshot_state`.
+pub struct StateSnapshot {
+    slots: Vec<(usize, Box<dyn Any + Send>)>,
+}
+
 // === Handles ===
 pub struct StateHandle<T> {
     pub(crate) slot: usize,
@@ -89,8 +122,61 @@ impl<T> Copy for ParameterHandle<T> {}
 
 // === Traits ===
 pub trait Modulator<E>: Send + 'static {
-    fn update(&mut self, sample_rate: f32, event: Option<E>);
+    fn update(&mut self, sample_rate: f32, event: Option<&E>);
     fn get_value(&self, index: usize) -> f32;
+
+    /// What `Runtime::tick` calls, with the number of samples the block will use
+    /// (at most `BUFFER_SIZE`). Sources that move through time per sample override
+    /// this so a short block doesn't push them ahead; the default ignores `len`.
+    /// A zero-length update only carries an event ahead of a block, so the default
+    /// skips it rather than have `update` move a whole block on.
+    fn update_block(&mut self, sample_rate: f32, event: Option<&E>, len: usize) {
+        if len == 0 {
+            return;
+        }
+        self.update(sample_rate, event);
+    }
+
+    /// Whether this modulator is still producing movement, e.g. an envelope that
+    /// hasn't finished its release. Free-running sources keep the default.
+    fn is_active(&self) -> bool {
+        true
+    }
+
+    /// How often parameters read this source. Slow sources can declare a control
+    /// rate so only every k-th value is read and held in between.
+    fn rate(&self) -> ModRate {
+        ModRate::AudioRate
+    }
+
+    /// The span `get_value` moves within, e.g. for scaling a host's scope view.
+    fn range(&self) -> (f32, f32) {
+        (0.0, 1.0)
+    }
+
+    /// A short human-readable description of the current state, for debugging UIs.
+    fn debug_state(&self) -> String {
+        String::new()
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ModRate {
+    /// Read every sample
+    #[default]
+    AudioRate,
+    /// Read every k samples from the start of the block, holding in between
+    ControlRate(usize),
+}
+
+impl ModRate {
+    /// The sample whose value is in effect at `index`.
+    pub fn held_index(self, index: usize) -> usize {
+        match self {
+            ModRate::AudioRate => index,
+            ModRate::ControlRate(k) => index - index % k.max(1),
+        }
+    }
 }
 
 pub trait Parameters: Default + Send + 'static {
@@ -98,13 +184,54 @@ pub trait Parameters: Default + Send + 'static {
     type Accessor<'a, E> where E: 'a;
     type Values: Copy;
     
-    fn create_runtime<E: Send>() -> Self::Runtime<E>;
-    fn create_accessor<E: Send>(runtime: &Self::Runtime<E>) -> Self::Accessor<'_, E>;
+    fn create_runtime<E: Send + 'static>() -> Self::Runtime<E>;
+    fn create_accessor<E: Send + 'static>(runtime: &Self::Runtime<E>) -> Self::Accessor<'_, E>;
+
+    /// Every routable parameter name, in declaration order.
+    fn param_names() -> Vec<String>;
+    /// The normalized range `param_name` is clamped to, e.g. to draw a knob, or
+    /// `None` if there's no such parameter.
+    fn param_range(param_name: &str) -> Option<(f32, f32)>;
+    /// Converts a value in the parameter's natural unit (Hz, dB, ms) to its
+    /// normalized 0..1 position, or `None` if there's no such parameter. Parameters
+    /// without a declared unit are already natural.
+    fn to_normalized(param_name: &str, natural: f32) -> Option<f32> {
+        Self::param_range(param_name).map(|_| natural.clamp(0.0, 1.0))
+    }
+    /// The inverse of `to_normalized`.
+    fn from_normalized(param_name: &str, normalized: f32) -> Option<f32> {
+        Self::param_range(param_name).map(|_| normalized.clamp(0.0, 1.0))
+    }
 }
 
 pub trait ParameterRuntime<E>: Send {
-    fn update(&mut self, sources: &[Box<dyn Modulator<E>>]);
-    fn route_parameter(&mut self, param_name: &str, source_index: usize, amount: f32);
+    /// Computes the block's first `len` values. `sample_rate` and `len` are the rate
+    /// and length of the block being processed, for time-based behaviour like
+    /// smoothing, which advances `len` samples.
+    fn update(&mut self, sample_rate: f32, sources: &[Box<dyn Modulator<E>>], len: usize);
+    /// Returns `false` if `param_name` doesn't name a parameter of this runtime.
+    fn route_parameter(&mut self, param_name: &str, routing: ModulationRouting) -> bool;
+    /// Returns `false` if `param_name` doesn't name a parameter of this runtime.
+    fn set_param_map(&mut self, param_name: &str, map: ParamMap) -> bool;
+    /// Sets the un-modulated value. Returns `false` if `param_name` doesn't name a
+    /// parameter of this runtime.
+    fn set_base(&mut self, param_name: &str, value: f32) -> bool;
+    /// Removes the routing from `source_index`, if there is one. Returns `false` if
+    /// `param_name` doesn't name a parameter of this runtime.
+    fn unroute_parameter(&mut self, param_name: &str, source_index: usize) -> bool;
+    /// Whether anything is routed to the parameter, or `None` if `param_name`
+    /// doesn't name a parameter of this runtime.
+    fn is_modulated(&self, param_name: &str) -> Option<bool>;
+    /// The parameter's value at the first sample of the last computed block, or
+    /// `None` if `param_name` doesn't name a parameter of this runtime.
+    fn value(&self, param_name: &str) -> Option<f32>;
+    /// Scales every routing from `source_index`, on every parameter, by `depth`.
+    fn set_source_depth(&mut self, source_index: usize, depth: f32);
+    /// Every routing as `(param_name, source_index)`, for inspecting the graph.
+    fn routings(&self) -> Vec<(String, usize)>;
+    /// Makes room on every parameter for a routing from each of `sources` sources,
+    /// so routing on the audio thread never allocates. `Builder::build` calls it.
+    fn reserve_routings(&mut self, sources: usize);
 }
 
 // === Builder ===
@@ -112,14 +239,26 @@ pub struct Builder<E> {
     pub(crate) next_state_slot: usize,
     pub(crate) state_builders: Vec<Box<dyn FnOnce() -> Box<dyn Any + Send>>>,
     pub(crate) state_map: HashMap<TypeId, usize>,
+    pub(crate) state_cloners: HashMap<usize, StateCloner>,
     
     pub(crate) next_modulation_slot: usize,
     pub(crate) modulation_builders: Vec<Box<dyn FnOnce() -> Box<dyn ParameterRuntime<E>>>>,
     pub(crate) modulation_map: HashMap<TypeId, usize>,
+    // Bytes of every registered parameter runtime's per-sample value array
+    pub(crate) parameter_buffer_bytes: usize,
     
     pub(crate) next_source_slot: usize,
     pub(crate) modulation_sources: Vec<Box<dyn Modulator<E>>>,
     pub(crate) source_map: HashMap<TypeId, usize>,
+
+    pub(crate) check_output: bool,
+    // (parameters slot, source slot) pairs declared with `declare_feed`
+    pub(crate) feeds: Vec<(usize, usize)>,
+    // Names a `RoutingConfig` uses for parameters and modulator slots
+    pub(crate) parameter_names: HashMap<String, usize>,
+    pub(crate) source_names: HashMap<String, usize>,
+    pub(crate) tick_hooks: Vec<TickHook<E>>,
+    pub(crate) latency: usize,
     
     _phantom: PhantomData<E>,
 }
@@ -130,12 +269,20 @@ impl<E: Send + 'static> Builder<E> {
             next_state_slot: 0,
             state_builders: Vec::new(),
             state_map: HashMap::new(),
+            state_cloners: HashMap::new(),
             next_modulation_slot: 0,
             modulation_builders: Vec::new(),
             modulation_map: HashMap::new(),
+            parameter_buffer_bytes: 0,
             next_source_slot: 0,
             modulation_sources: Vec::new(),
             source_map: HashMap::new(),
+            check_output: false,
+            feeds: Vec::new(),
+            parameter_names: HashMap::new(),
+            source_names: HashMap::new(),
+            tick_hooks: Vec::new(),
+            latency: 0,
             _phantom: PhantomData,
         }
     }
@@ -151,18 +298,68 @@ impl<E: Send + 'static> Builder<E> {
         StateHandle { slot, _phantom: PhantomData }
     }
     
+    /// `use_state` for types without a `Default`, or whose initial value depends on
+    /// how the graph is built. Like `use_state` there's one slot per type, so if `T`
+    /// is already registered its existing initializer is kept and `init` is dropped.
+    pub fn use_state_with<T: Send + 'static>(&mut self, init: impl FnOnce() -> T + 'static) -> StateHandle<T> {
+        let type_id = TypeId::of::<T>();
+        let slot = match self.state_map.get(&type_id) {
+            Some(&slot) => slot,
+            None => {
+                let slot = self.new_state(init).slot;
+                self.state_map.insert(type_id, slot);
+                slot
+            }
+        };
+        StateHandle { slot, _phantom: PhantomData }
+    }
+
+    /// `use_state` for a type that can be cloned, so the slot is included in
+    /// `Runtime::snapshot_state`. Other state slots aren't captured.
+    pub fn use_cloneable_state<T: Clone + Default + Send + 'static>(&mut self) -> StateHandle<T> {
+        let handle = self.use_state::<T>();
+        self.state_cloners.insert(handle.slot, clone_state::<T>);
+        handle
+    }
+
+    /// A new `Bus`, distinct from every other, for routing a signal between
+    /// components that aren't adjacent in the graph.
+    pub fn use_bus(&mut self) -> StateHandle<Bus> {
+        self.new_state(Bus::default)
+    }
+
+    // A state slot of its own, unlike `use_state`'s one per type
+    pub(crate) fn new_state<T: Send + 'static>(&mut self, init: impl FnOnce() -> T + 'static) -> StateHandle<T> {
+        let slot = self.next_state_slot;
+        self.next_state_slot += 1;
+        self.state_builders.push(Box::new(move || Box::new(init())));
+        StateHandle { slot, _phantom: PhantomData }
+    }
+
     pub fn use_parameters<T: Parameters>(&mut self) -> ParameterHandle<T> 
     where T::Runtime<E>: ParameterRuntime<E> + 'static {
         let type_id = TypeId::of::<T>();
         let slot = *self.modulation_map.entry(type_id).or_insert_with(|| {
             let slot = self.next_modulation_slot;
             self.next_modulation_slot += 1;
+            self.parameter_buffer_bytes += BUFFER_SIZE * std::mem::size_of::<T>();
             self.modulation_builders.push(Box::new(|| Box::new(T::create_runtime::<E>())));
             slot
         });
         ParameterHandle { slot, _phantom: PhantomData }
     }
     
+    /// Like `use_parameters`, but always registers a new slot, so two stages built
+    /// from the same parameters type get independent values and routings.
+    pub fn use_parameters_instance<T: Parameters>(&mut self) -> ParameterHandle<T>
+    where T::Runtime<E>: ParameterRuntime<E> + 'static {
+        let slot = self.next_modulation_slot;
+        self.next_modulation_slot += 1;
+        self.parameter_buffer_bytes += BUFFER_SIZE * std::mem::size_of::<T>();
+        self.modulation_builders.push(Box::new(|| Box::new(T::create_runtime::<E>())));
+        ParameterHandle { slot, _phantom: PhantomData }
+    }
+    
     pub fn use_modulator<T: Modulator<E> + Default>(&mut self) -> ModulatorHandle<T> {
         let type_id = TypeId::of::<T>();
         let slot = self.next_source_slot;
@@ -173,7 +370,78 @@ impl<E: Send + 'static> Builder<E> {
         
         ModulatorHandle { slot, _phantom: PhantomData }
     }
+
+    /// Registers a modulator shared between runtimes. The first runtime to register it
+    /// takes ownership and advances it in its own `tick`; every later registration reads
+    /// the block the owner last published, so tick the owner first each cycle.
+    pub fn use_shared_modulator<T: Modulator<E>>(&mut self, shared: &Arc<SharedModulator<T>>) -> ModulatorHandle<SharedSource<T>> {
+        let type_id = TypeId::of::<SharedSource<T>>();
+        let slot = self.next_source_slot;
+        self.next_source_slot += 1;
+
+        let owned = shared.modulator.lock().unwrap().take();
+        self.modulation_sources.push(Box::new(SharedSource { owned, block: shared.block.clone(), values: [0.0; BUFFER_SIZE], active: true }));
+        self.source_map.insert(type_id, slot);
+
+        ModulatorHandle { slot, _phantom: PhantomData }
+    }
     
+    /// In debug builds, poisons the output with NaN before every tick and panics if
+    /// any of it is still NaN afterwards, catching components that never write their
+    /// output. Components must then overwrite the output rather than add into it.
+    pub fn debug_check_output(&mut self) {
+        self.check_output = true;
+    }
+
+    /// Declares that `source` is driven by the parameters behind `target`, e.g. a
+    /// component that sets an LFO's rate from a knob through `get_source_mut`. The
+    /// runtime can't see such links itself; declaring them lets `has_cycle` find
+    /// feedback loops that pass through them.
+    pub fn declare_feed<T: Parameters, S>(&mut self, target: ParameterHandle<T>, source: ModulatorHandle<S>) {
+        self.feeds.push((target.slot, source.slot));
+    }
+
+    /// Names `target` so a `RoutingConfig` can refer to it. Naming another handle
+    /// with the same name replaces the earlier one.
+    pub fn name_parameters<T: Parameters>(&mut self, target: ParameterHandle<T>, name: &str) {
+        self.parameter_names.insert(name.to_string(), target.slot);
+    }
+
+    /// Names `source` so a `RoutingConfig` can route from it.
+    pub fn name_modulator<S>(&mut self, source: ModulatorHandle<S>, name: &str) {
+        self.source_names.insert(name.to_string(), source.slot);
+    }
+
+    /// Registers `hook` to run once per block, after processing. Hooks run in the
+    /// order they were added; blocks with no samples don't run them.
+    pub fn on_tick(&mut self, hook: TickHook<E>) {
+        self.tick_hooks.push(hook);
+    }
+
+    /// Declares that the component being built delays its signal by `samples`, like
+    /// a look-ahead or a block-based FFT. Reports add up in build order, which is
+    /// right for serial chains; branches of `parallel!` with different latencies
+    /// aren't aligned with each other.
+    pub fn report_latency(&mut self, samples: usize) {
+        self.latency += samples;
+    }
+
+    /// Latency reported so far.
+    pub fn latency(&self) -> usize {
+        self.latency
+    }
+
+    /// Builds a sub-graph with `f`, returning it along with the latency it
+    /// reported, e.g. to delay a parallel dry path to match.
+    pub fn measure_latency<F>(&mut self, f: F) -> (ComponentFn<E>, usize)
+    where
+        F: FnOnce(&mut Builder<E>) -> ComponentFn<E>,
+    {
+        let before = self.latency;
+        let component = f(self);
+        (component, self.latency - before)
+    }
+
     pub fn build<F>(self, f: F) -> Runtime<E> 
     where 
         F: FnOnce(&mut Builder<E>) -> ComponentFn<E>
@@ -181,30 +449,147 @@ impl<E: Send + 'static> Builder<E> {
         let mut builder = self;
         let component = f(&mut builder);
         
+        let mut state_cloners: Vec<_> = builder.state_cloners.into_iter().collect();
+        state_cloners.sort_by_key(|(slot, _)| *slot);
+
+        let sources = builder.modulation_sources.len();
+        let modulation_targets = builder.modulation_builders
+            .into_iter()
+            .map(|builder| {
+                let mut target = builder();
+                target.reserve_routings(sources);
+                UnsafeCell::new(target)
+            })
+            .collect();
+
         Runtime {
+            state_cloners,
             states: builder.state_builders
                 .into_iter()
                 .map(|builder| UnsafeCell::new(builder()))
                 .collect(),
-            modulation_targets: builder.modulation_builders
-                .into_iter()
-                .map(|builder| UnsafeCell::new(builder()))
-                .collect(),
+            modulation_targets,
+            events: Vec::with_capacity(EMIT_CAPACITY + 1),
+            emitted: VecDeque::with_capacity(EMIT_CAPACITY),
+            emit_overflows: 0,
+            sample_rate: 0.0,
+            block_len: BUFFER_SIZE,
+            params_stale: (0..builder.next_modulation_slot).map(|_| Cell::new(true)).collect(),
+            modulation_frozen: false,
+            samples_processed: 0,
+            channels: 1,
+            #[cfg(feature = "profiling")]
+            source_timings: vec![std::time::Duration::ZERO; builder.modulation_sources.len()],
+            source_depths: vec![1.0; builder.modulation_sources.len()],
+            sources_enabled: vec![true; builder.modulation_sources.len()],
             modulation_sources: UnsafeCell::new(builder.modulation_sources),
             component: UnsafeCell::new(component),
+            check_output: builder.check_output,
+            feeds: builder.feeds,
+            parameter_names: builder.parameter_names,
+            source_names: builder.source_names,
+            parameter_buffer_bytes: builder.parameter_buffer_bytes,
+            tick_hooks: builder.tick_hooks,
+            latency: builder.latency,
+            bypassed: false,
+            bypass_mix: 0.0,
+            bypass_line: vec![0.0; builder.latency],
+            bypass_head: 0,
         }
     }
+
+    /// Builds a runtime with no audio processing, for graphs that only compute
+    /// modulation. Register parameters and modulators on the builder first; `tick`
+    /// then advances the modulators and leaves the output untouched, and
+    /// `get_parameters` reads the modulated values.
+    pub fn build_headless(self) -> Runtime<E> {
+        self.build(|_| Box::new(|_runtime, _input, _output, _sample_rate| {}))
+    }
 }
 
 // === Runtime ===
-pub struct Runtime<E: 'static> {
+/// What a tick produced, so a host can suspend processing once a graph goes idle.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TickStatus {
+    pub audible: bool,
+    pub modulators_active: bool,
+}
+
+/// Bytes a built graph holds, by category, from `Runtime::memory_footprint`.
+/// Counts are shallow: heap memory behind a value, like a `Vec` inside a state
+/// slot or a delay line a component captured, isn't included.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MemoryReport {
+    /// `use_state` slots
+    pub state: usize,
+    /// The per-sample value arrays of every parameter runtime, `BUFFER_SIZE`
+    /// parameter structs each
+    pub parameter_buffers: usize,
+    /// The rest of the parameter runtimes: base values and routing lists
+    pub parameter_runtimes: usize,
+    pub modulators: usize,
+    /// The component closures' captured values
+    pub components: usize,
+    /// What the runtime keeps itself, like the bypass delay line and the queue
+    /// of emitted events
+    pub runtime_buffers: usize,
+}
+
+impl MemoryReport {
+    pub fn total(&self) -> usize {
+        self.state + self.parameter_buffers + self.parameter_runtimes + self.modulators + self.components + self.runtime_buffers
+    }
+}
+
+impl TickStatus {
+    pub fn is_idle(&self) -> bool {
+        !self.audible && !self.modulators_active
+    }
+}
+
+pub struct Runtime<E> {
     pub(crate) states: Vec<UnsafeCell<Box<dyn Any + Send>>>,
+    // Slots registered with `use_cloneable_state`, in slot order
+    state_cloners: Vec<(usize, StateCloner)>,
     pub(crate) modulation_targets: Vec<UnsafeCell<Box<dyn ParameterRuntime<E>>>>,
     pub(crate) modulation_sources: UnsafeCell<Vec<Box<dyn Modulator<E>>>>,
     pub(crate) component: UnsafeCell<ComponentFn<E>>,
+    // The events dispatched with the current block, including ones handed to the
+    // modulators ahead of it
+    events: Vec<E>,
+    // Emitted by components, dispatched ahead of the next block
+    emitted: VecDeque<E>,
+    emit_overflows: usize,
+    // Rate and length of the current block, and which parameter runtimes haven't
+    // been updated for it
+    sample_rate: f32,
+    block_len: usize,
+    params_stale: Vec<Cell<bool>>,
+    modulation_frozen: bool,
+    samples_processed: u64,
+    channels: usize,
+    // Time spent in each source's updates
+    #[cfg(feature = "profiling")]
+    source_timings: Vec<std::time::Duration>,
+    // Per-source depth scale, kept here so routings added later pick it up too
+    source_depths: Vec<f32>,
+    sources_enabled: Vec<bool>,
+    check_output: bool,
+    feeds: Vec<(usize, usize)>,
+    parameter_names: HashMap<String, usize>,
+    source_names: HashMap<String, usize>,
+    parameter_buffer_bytes: usize,
+    latency: usize,
+    // Bypass state: the requested setting, how far the output has faded to the
+    // delayed input (0..1), and the delay line that matches `latency`
+    bypassed: bool,
+    bypass_mix: f32,
+    bypass_line: Vec<f32>,
+    bypass_head: usize,
+    tick_hooks: Vec<TickHook<E>>,
 }
 
-impl<E: 'static + Send + Clone + Copy> Runtime<E> {
+impl<E: 'static + Send + Clone> Runtime<E> {
     pub fn get<T: 'static>(&self, handle: &StateHandle<T>) -> &T {
         unsafe {
             (*self.states[handle.slot].get()).downcast_ref().unwrap()
@@ -217,121 +602,1082 @@ impl<E: 'static + Send + Clone + Copy> Runtime<E> {
         }
     }
 
-    pub fn get_source_mut<T: Modulator<E> + 'static>(&self, handle: &ModulatorHandle<T>) -> &mut T {
+    /// Estimates the memory the graph holds, e.g. to size an embedded target.
+    pub fn memory_footprint(&self) -> MemoryReport {
+        use std::mem::{size_of, size_of_val};
         unsafe {
-            let sources = &mut *self.modulation_sources.get();
-            let boxed_modulator = &mut sources[handle.slot];
-            &mut *(boxed_modulator.as_mut() as *mut dyn Modulator<E> as *mut T)
+            let state = self.states.iter().map(|slot| size_of_val(&**slot.get())).sum();
+            let runtimes: usize = self.modulation_targets.iter().map(|target| size_of_val(&**target.get())).sum();
+            let modulators = (*self.modulation_sources.get()).iter().map(|source| size_of_val(&**source)).sum();
+            let components = size_of_val(&**self.component.get());
+            MemoryReport {
+                state,
+                parameter_buffers: self.parameter_buffer_bytes,
+                parameter_runtimes: runtimes.saturating_sub(self.parameter_buffer_bytes),
+                modulators,
+                components,
+                runtime_buffers: self.bypass_line.capacity() * size_of::<f32>()
+                    + self.emitted.capacity() * size_of::<E>()
+                    + self.events.capacity() * size_of::<E>()
+                    + self.params_stale.len() * size_of::<Cell<bool>>(),
+            }
+        }
+    }
+
+    /// Copies every slot registered with `use_cloneable_state`, e.g. to push onto
+    /// an undo stack. It allocates, so take snapshots off the audio thread.
+    pub fn snapshot_state(&self) -> StateSnapshot {
+        let slots = self.state_cloners.iter()
+            .map(|&(slot, clone)| unsafe { (slot, clone(&**self.states[slot].get())) })
+            .collect();
+        StateSnapshot { slots }
+    }
+
+    /// Puts every slot captured in `snapshot` back to its captured value. The
+    /// snapshot is left intact, so it can be restored again.
+    pub fn restore_state(&mut self, snapshot: &StateSnapshot) {
+        for (slot, state) in &snapshot.slots {
+            let clone = self.state_cloners.iter()
+                .find(|(cloneable, _)| cloneable == slot)
+                .map(|&(_, clone)| clone)
+                .expect("snapshot taken from a different runtime");
+            *self.states[*slot].get_mut() = clone(&**state);
         }
     }
 
+    /// Rate of the block being processed, or of the last one outside a tick; this
+    /// is what parameter runtimes receive in `update`. 0.0 before the first tick.
+    pub fn sample_rate(&self) -> f32 {
+        self.sample_rate
+    }
+
+    /// Interleaved channels in the buffers this runtime is ticked with: 1 unless
+    /// set, and the device's channel count when run by an `Engine`.
+    pub fn channels(&self) -> usize {
+        self.channels
+    }
+
+    pub fn set_channels(&mut self, channels: usize) {
+        self.channels = channels.max(1);
+    }
+
+    /// Total latency the graph's components reported while it was built, in
+    /// samples, for hosts that compensate for it.
+    pub fn latency(&self) -> usize {
+        self.latency
+    }
+
+    /// Bypassed, the output is the input delayed by `latency`, so toggling bypass
+    /// doesn't shift timing, and the graph isn't processed; modulators keep
+    /// running. Switching crossfades over the next block.
+    pub fn set_bypass(&mut self, bypassed: bool) {
+        self.bypassed = bypassed;
+    }
+
+    pub fn is_bypassed(&self) -> bool {
+        self.bypassed
+    }
+
+    /// Samples ticked since the runtime was built or the count was last reset,
+    /// including the current block while it is being processed.
+    pub fn samples_processed(&self) -> u64 {
+        self.samples_processed
+    }
+
+    pub fn reset_samples_processed(&mut self) {
+        self.samples_processed = 0;
+    }
+
+    /// Total time each source has spent updating, in registration order (the
+    /// order of `sources`), to find the expensive one in a large patch.
+    #[cfg(feature = "profiling")]
+    pub fn source_timings(&self) -> &[std::time::Duration] {
+        &self.source_timings
+    }
+
+    #[cfg(feature = "profiling")]
+    pub fn reset_source_timings(&mut self) {
+        self.source_timings.fill(std::time::Duration::ZERO);
+    }
+
+    /// The events dispatched with the block being processed, for components that
+    /// react to them directly, like a sampler starting playback on a note-on. It
+    /// includes events handed to the modulators just ahead of the block (emitted
+    /// ones, or earlier ones at the same frame), in dispatch order. Modulators see
+    /// the same events through `update`.
+    pub fn events(&self) -> &[E] {
+        &self.events
+    }
+
+    /// Queues `event` to be dispatched before the next block, as if it came from the
+    /// event bus, so a component can turn what it hears (an onset, a level crossing)
+    /// into events that modulators pick up. Each emitted event is handed to the
+    /// modulators on its own, in the order emitted, with a zero-length update. At
+    /// most `EMIT_CAPACITY` (64) wait at once; further ones are dropped and counted
+    /// in `emit_overflows`.
+    pub fn emit(&mut self, event: E) {
+        if self.emitted.len() == EMIT_CAPACITY {
+            self.emit_overflows += 1;
+            return;
+        }
+        self.emitted.push_back(event);
+    }
+
+    /// Emitted events dropped because the queue was full.
+    pub fn emit_overflows(&self) -> usize {
+        self.emit_overflows
+    }
+
+    /// Every registered modulator, in registration order (the order of their handles),
+    /// for hosts that inspect sources without knowing their types.
+    pub fn sources(&self) -> impl Iterator<Item = &dyn Modulator<E>> {
+        // Sources are only mutated through `&mut self` (`tick`, `get_source_mut`),
+        // so nothing can change them while the iterator borrows the runtime
+        let sources = unsafe { &*self.modulation_sources.get() };
+        sources.iter().map(|source| source.as_ref())
+    }
+
+    pub fn get_source_mut<T: Modulator<E> + 'static>(&mut self, handle: &ModulatorHandle<T>) -> &mut T {
+        let boxed_modulator = &mut self.modulation_sources.get_mut()[handle.slot];
+        // The handle was issued for a `T` in this slot
+        unsafe { &mut *(boxed_modulator.as_mut() as *mut dyn Modulator<E> as *mut T) }
+    }
+
     pub fn route<S: 'static, T: Parameters + 'static>(
         &mut self, 
         source: ModulatorHandle<S>, 
         target: ParameterHandle<T>, 
         param: &str, 
         amount: f32
-    ) {
+    ) -> Result<(), RouteError> {
+        self.route_with_mode(source, target, param, amount, RoutingMode::Add)
+    }
+
+    pub fn route_with_mode<S: 'static, T: Parameters + 'static>(
+        &mut self, 
+        source: ModulatorHandle<S>, 
+        target: ParameterHandle<T>, 
+        param: &str, 
+        amount: f32,
+        mode: RoutingMode,
+    ) -> Result<(), RouteError> {
         unsafe {
+            self.params_stale[target.slot].set(true);
             let target_runtime = &mut *self.modulation_targets[target.slot].get();
-            target_runtime.route_parameter(param, source.slot, amount);
+            if target_runtime.route_parameter(param, ModulationRouting { source_index: source.slot, amount, mode }) {
+                target_runtime.set_source_depth(source.slot, self.effective_depth(source.slot));
+                Ok(())
+            } else {
+                Err(RouteError::UnknownParam(param.to_string()))
+            }
         }
     }
 
-    pub fn tick(&mut self, sample_rate: f32, event: Option<E>, input: &[f32], output: &mut [f32]) {
+    /// Registers a function applied to `param`'s normalized value after modulation, so
+    /// the accessor yields `map(value)`. Replaces any earlier map for that parameter.
+    pub fn set_param_map<T: Parameters + 'static>(
+        &mut self,
+        target: ParameterHandle<T>,
+        param: &str,
+        map: ParamMap,
+    ) -> Result<(), RouteError> {
         unsafe {
-            let sources = &mut *self.modulation_sources.get();
-
-            for modulator in sources.iter_mut() {
-                modulator.update(sample_rate, event);
+            self.params_stale[target.slot].set(true);
+            let target_runtime = &mut *self.modulation_targets[target.slot].get();
+            if target_runtime.set_param_map(param, map) {
+                Ok(())
+            } else {
+                Err(RouteError::UnknownParam(param.to_string()))
             }
-            
-            let component = &mut *self.component.get();
-            component(self, input, output, sample_rate);
         }
     }
-    
-    pub fn get_parameters<T: Parameters>(&self, handle: &ParameterHandle<T>) -> T::Accessor<'_, E> {
+
+    pub fn unroute<S: 'static, T: Parameters + 'static>(
+        &mut self,
+        source: ModulatorHandle<S>,
+        target: ParameterHandle<T>,
+        param: &str,
+    ) -> Result<(), RouteError> {
         unsafe {
-            let sources = &*self.modulation_sources.get();
-            
-            let target_boxed = &mut *self.modulation_targets[handle.slot].get();
-            let concrete_runtime = &mut *(target_boxed.as_mut() as *mut dyn ParameterRuntime<E> as *mut T::Runtime<E>);
-            
-            concrete_runtime.update(sources);
-            T::create_accessor(concrete_runtime)
+            self.params_stale[target.slot].set(true);
+            let target_runtime = &mut *self.modulation_targets[target.slot].get();
+            if target_runtime.unroute_parameter(param, source.slot) {
+                Ok(())
+            } else {
+                Err(RouteError::UnknownParam(param.to_string()))
+            }
         }
     }
-}
 
-// === Main API ===
-pub fn new<E: Clone + Send + 'static>() -> (EventBus<E>, Builder<E>) {
-    (EventBus::new(), Builder::new())
-}
+    /// While frozen, modulators aren't updated: each keeps returning its last block
+    /// of values, and events don't reach them. Audio keeps processing, so a patch
+    /// can be auditioned with its modulation held still.
+    pub fn set_modulation_frozen(&mut self, frozen: bool) {
+        self.modulation_frozen = frozen;
+    }
 
-// === Macros ===
-#[macro_export]
-macro_rules! parallel {
-    ($(($weight:expr, $comp:expr)),+) => {
-        |builder: &mut $crate::Builder<_>| -> $crate::ComponentFn<_> {
-            let mut components: Vec<(f32, $crate::ComponentFn<_>)> = vec![$(($weight as f32, $comp(builder))),+];
-            let mut temp_buffers = Vec::new();
-            
-            Box::new(move |runtime, input, output, sample_rate| {
-                if temp_buffers.len() != components.len() {
-                    temp_buffers.resize(components.len(), Vec::new());
+    /// Scales all of `source`'s routings at once, including ones made later, like
+    /// a mod wheel setting vibrato depth. 1.0 leaves each routing's amount as set.
+    pub fn set_source_depth<S: 'static>(&mut self, source: ModulatorHandle<S>, depth: f32) {
+        self.source_depths[source.slot] = depth;
+        self.apply_source_depth(source.slot);
+    }
+
+    /// A disabled source's routings contribute nothing, as if unrouted, but stay
+    /// in place so re-enabling restores them. The modulator itself keeps running.
+    pub fn set_source_enabled<S: 'static>(&mut self, source: ModulatorHandle<S>, enabled: bool) {
+        self.sources_enabled[source.slot] = enabled;
+        self.apply_source_depth(source.slot);
+    }
+
+    fn effective_depth(&self, slot: usize) -> f32 {
+        if self.sources_enabled[slot] { self.source_depths[slot] } else { 0.0 }
+    }
+
+    fn apply_source_depth(&mut self, slot: usize) {
+        let depth = self.effective_depth(slot);
+        for (target, stale) in self.modulation_targets.iter_mut().zip(&self.params_stale) {
+            target.get_mut().set_source_depth(slot, depth);
+            stale.set(true);
+        }
+    }
+
+    /// Sets the value `param` takes before modulation, e.g. from a UI knob.
+    pub fn set_base<T: Parameters + 'static>(&mut self, target: ParameterHandle<T>, param: &str, value: f32) -> Result<(), RouteError> {
+        unsafe {
+            self.params_stale[target.slot].set(true);
+            let target_runtime = &mut *self.modulation_targets[target.slot].get();
+            if target_runtime.set_base(param, value) {
+                Ok(())
+            } else {
+                Err(RouteError::UnknownParam(param.to_string()))
+            }
+        }
+    }
+
+    /// Applies a command built elsewhere; this is how `Engine` hands over UI changes.
+    pub fn apply(&mut self, command: ControlCommand) -> Result<(), RouteError> {
+        let (slot, param) = match command.0 {
+            Command::SetBase { target, param, .. } | Command::Route { target, param, .. } | Command::Unroute { target, param, .. } => (target, param),
+        };
+        self.params_stale[slot].set(true);
+        let applied = unsafe {
+            let target_runtime = &mut *self.modulation_targets[slot].get();
+            match command.0 {
+                Command::SetBase { value, .. } => target_runtime.set_base(param, value),
+                Command::Route { source, amount, mode, .. } => {
+                    let routed = target_runtime.route_parameter(param, ModulationRouting { source_index: source, amount, mode });
+                    target_runtime.set_source_depth(source, self.effective_depth(source));
+                    routed
                 }
-                for buf in &mut temp_buffers {
-                    if buf.len() != output.len() {
-                        buf.resize(output.len(), 0.0);
-                    }
+                Command::Unroute { source, .. } => target_runtime.unroute_parameter(param, source),
+            }
+        };
+        if applied {
+            Ok(())
+        } else {
+            Err(RouteError::UnknownParam(param.to_string()))
+        }
+    }
+
+    /// Applies a preset: every base value, then every routing, resolving names
+    /// registered with `Builder::name_parameters` and `Builder::name_modulator`.
+    /// Every entry is checked first, so on an error nothing has been applied.
+    pub fn apply_config(&mut self, config: &RoutingConfig) -> Result<(), RouteError> {
+        let target_slot = |name: &str| self.parameter_names.get(name).copied().ok_or_else(|| RouteError::UnknownTarget(name.to_string()));
+        let source_slot = |name: &str| self.source_names.get(name).copied().ok_or_else(|| RouteError::UnknownSource(name.to_string()));
+        let has_param = |slot: usize, param: &str| unsafe { (*self.modulation_targets[slot].get()).value(param).is_some() };
+
+        let mut bases = Vec::with_capacity(config.bases.len());
+        for base in &config.bases {
+            let target = target_slot(&base.target)?;
+            if !has_param(target, &base.param) {
+                return Err(RouteError::UnknownParam(base.param.clone()));
+            }
+            bases.push(target);
+        }
+        let mut routes = Vec::with_capacity(config.routes.len());
+        for route in &config.routes {
+            let (source, target) = (source_slot(&route.source)?, target_slot(&route.target)?);
+            if !has_param(target, &route.param) {
+                return Err(RouteError::UnknownParam(route.param.clone()));
+            }
+            routes.push((source, target));
+        }
+
+        for (base, target) in config.bases.iter().zip(bases) {
+            self.params_stale[target].set(true);
+            self.modulation_targets[target].get_mut().set_base(&base.param, base.value);
+        }
+        for (route, (source, target)) in config.routes.iter().zip(routes) {
+            self.params_stale[target].set(true);
+            let depth = self.effective_depth(source);
+            let target_runtime = self.modulation_targets[target].get_mut();
+            target_runtime.route_parameter(&route.param, ModulationRouting { source_index: source, amount: route.amount, mode: route.mode });
+            target_runtime.set_source_depth(source, depth);
+        }
+        Ok(())
+    }
+
+    /// Whether any routing targets `param`, e.g. to mark modulated knobs in a UI.
+    pub fn is_modulated<T: Parameters + 'static>(&self, target: ParameterHandle<T>, param: &str) -> Result<bool, RouteError> {
+        unsafe {
+            let target_runtime = &*self.modulation_targets[target.slot].get();
+            target_runtime.is_modulated(param).ok_or_else(|| RouteError::UnknownParam(param.to_string()))
+        }
+    }
+
+    /// Every modulation routing as `(source slot, parameters slot, param name)`,
+    /// where slots are the indices behind `ModulatorHandle` and `ParameterHandle`
+    /// in registration order. It allocates, so query it off the audio thread.
+    pub fn routing_graph(&self) -> Vec<(usize, usize, String)> {
+        self.modulation_targets.iter().enumerate()
+            .flat_map(|(target, runtime)| {
+                let runtime = unsafe { &*runtime.get() };
+                runtime.routings().into_iter().map(move |(param, source)| (source, target, param))
+            })
+            .collect()
+    }
+
+    /// Whether some source ends up modulating itself: it's routed to parameters
+    /// that drive, through links declared with `Builder::declare_feed`, a source
+    /// that leads back to it.
+    pub fn has_cycle(&self) -> bool {
+        let source_count = unsafe { (*self.modulation_sources.get()).len() };
+        // Source-to-source edges: a source reaches every source its targets feed
+        let mut edges = vec![Vec::new(); source_count];
+        for (source, target, _) in self.routing_graph() {
+            for &(fed_by, fed) in &self.feeds {
+                if fed_by == target && source < source_count {
+                    edges[source].push(fed);
                 }
-                
-                output.fill(0.0);
-                for ((weight, comp), buf) in components.iter_mut().zip(temp_buffers.iter_mut()) {
-                    buf.fill(0.0);
-                    comp(runtime, input, buf, sample_rate);
-                    
-                    for (out, &sample) in output.iter_mut().zip(buf.iter()) {
-                        *out += sample * *weight;
-                    }
+            }
+        }
+
+        // Depth-first search; 1 = on the current path, 2 = fully explored
+        fn visit(node: usize, edges: &[Vec<usize>], marks: &mut [u8]) -> bool {
+            marks[node] = 1;
+            for &next in &edges[node] {
+                if marks[next] == 1 || (marks[next] == 0 && visit(next, edges, marks)) {
+                    return true;
                 }
-            })
+            }
+            marks[node] = 2;
+            false
         }
-    };
-}
+        let mut marks = vec![0u8; source_count];
+        (0..source_count).any(|node| marks[node] == 0 && visit(node, &edges, &mut marks))
+    }
 
-#[macro_export]
-macro_rules! serial {
-    ($($comp:expr),+) => {
-        |builder: &mut $crate::Builder<_>| -> $crate::ComponentFn<_> {
-            let mut components: Vec<$crate::ComponentFn<_>> = vec![$($comp(builder)),+];
-            let mut buffer_a = Vec::new();
-            let mut buffer_b = Vec::new();
+    /// Every parameter of `target` with its current effective value (base plus
+    /// modulation, after maps), one `name = value` per line. For logging when a
+    /// patch doesn't sound the way its settings suggest.
+    pub fn dump_params<T: Parameters + 'static>(&self, target: &ParameterHandle<T>) -> String {
+        let _ = self.get_parameters(target);
+        let target_runtime = unsafe { &*self.modulation_targets[target.slot].get() };
+        T::param_names()
+            .iter()
+            .filter_map(|name| Some(format!("{} = {:.4}", name, target_runtime.value(name)?)))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// The parameter runtime behind `target`, for its typed setters
+    /// (`set_<field>` on runtimes generated by `#[parameters]`).
+    pub fn parameters_mut<T: Parameters + 'static>(&mut self, target: &ParameterHandle<T>) -> &mut T::Runtime<E> {
+        self.params_stale[target.slot].set(true);
+        let target_boxed = self.modulation_targets[target.slot].get_mut();
+        unsafe { &mut *(target_boxed.as_mut() as *mut dyn ParameterRuntime<E> as *mut T::Runtime<E>) }
+    }
+
+    /// Processes `output.len()` samples. Longer buffers than `BUFFER_SIZE` are split
+    /// into full blocks plus a shorter last one; `event` reaches the first block.
+    pub fn tick(&mut self, sample_rate: f32, event: Option<E>, input: &[f32], output: &mut [f32]) -> TickStatus {
+        if output.len() <= BUFFER_SIZE {
+            return self.tick_block(sample_rate, event, input, output);
+        }
+
+        let mut status = TickStatus { audible: false, modulators_active: false };
+        let mut event = event;
+        for (block, output) in output.chunks_mut(BUFFER_SIZE).enumerate() {
+            let start = (block * BUFFER_SIZE).min(input.len());
+            let end = (start + output.len()).min(input.len());
+            let block_status = self.tick_block(sample_rate, event.take(), &input[start..end], output);
+            status.audible |= block_status.audible;
+            status.modulators_active = block_status.modulators_active;
+        }
+        status
+    }
+
+    fn tick_block(&mut self, sample_rate: f32, event: Option<E>, input: &[f32], output: &mut [f32]) -> TickStatus {
+        self.dispatch_emitted(sample_rate);
+        self.dispatch_block(sample_rate, event, input, output)
+    }
+
+    fn dispatch_emitted(&mut self, sample_rate: f32) {
+        while let Some(event) = self.emitted.pop_front() {
+            self.dispatch_event(sample_rate, event);
+        }
+    }
+
+    // An event without audio: only the modulators see it now, with a zero-length
+    // update. Components, hooks and parameters wait for the next block, where the
+    // event shows up in `events`.
+    fn dispatch_event(&mut self, sample_rate: f32, event: E) {
+        self.events.push(event);
+        self.update_sources(sample_rate, true, 0);
+    }
+
+    fn update_sources(&mut self, sample_rate: f32, with_event: bool, len: usize) {
+        if self.modulation_frozen {
+            return;
+        }
+        let sources = self.modulation_sources.get_mut();
+        // Modulators borrow the event, so payloads that aren't Copy are never cloned here
+        let event = if with_event { self.events.last() } else { None };
+        #[cfg(not(feature = "profiling"))]
+        for modulator in sources.iter_mut() {
+            modulator.update_block(sample_rate, event, len);
+        }
+        #[cfg(feature = "profiling")]
+        for (modulator, time) in sources.iter_mut().zip(self.source_timings.iter_mut()) {
+            let started = std::time::Instant::now();
+            modulator.update_block(sample_rate, event, len);
+            *time += started.elapsed();
+        }
+    }
+
+    fn dispatch_block(&mut self, sample_rate: f32, event: Option<E>, input: &[f32], output: &mut [f32]) -> TickStatus {
+        let with_event = event.is_some();
+        self.events.extend(event);
+        self.update_sources(sample_rate, with_event, output.len());
+        self.sample_rate = sample_rate;
+        self.block_len = output.len();
+        self.samples_processed += output.len() as u64;
+        for stale in &self.params_stale {
+            stale.set(true);
+        }
+        unsafe {
+            let sources = &*self.modulation_sources.get();
+            let modulators_active = sources.iter().any(|modulator| modulator.is_active());
             
-            Box::new(move |runtime, input, output, sample_rate| {
-                if components.is_empty() {
-                    output.copy_from_slice(input);
-                    return;
+            // The input always runs through the bypass delay, so bypass can switch in at any block
+            let mut delayed = [0.0; BUFFER_SIZE];
+            let delayed = &mut delayed[..output.len()];
+            self.delay_for_bypass(input, delayed);
+            let bypass_target = if self.bypassed { 1.0 } else { 0.0 };
+
+            if self.bypass_mix == 1.0 && bypass_target == 1.0 {
+                output.copy_from_slice(delayed);
+            } else {
+                let check_output = cfg!(debug_assertions) && self.check_output;
+                if check_output {
+                    output.fill(f32::NAN);
                 }
-                
-                if buffer_a.len() != output.len() {
-                    buffer_a.resize(output.len(), 0.0);
-                    buffer_b.resize(output.len(), 0.0);
+
+                let component = &mut *self.component.get();
+                component(self, input, output, sample_rate);
+
+                if check_output {
+                    let unwritten = output.iter().filter(|sample| sample.is_nan()).count();
+                    assert!(unwritten == 0, "{} of {} output samples were not written (or are NaN)", unwritten, output.len());
                 }
-                
-                buffer_a.copy_from_slice(input);
-                
-                for (i, comp) in components.iter_mut().enumerate() {
-                    let (inp, out) = if i % 2 == 0 {
-                        (&buffer_a[..], &mut buffer_b[..])
-                    } else {
-                        (&buffer_b[..], &mut buffer_a[..])
-                    };
-                    out.fill(0.0);
-                    comp(runtime, inp, out, sample_rate);
+
+                // Crossfades over the block in which bypass was switched
+                if self.bypass_mix != 0.0 || bypass_target != 0.0 {
+                    let step = (bypass_target - self.bypass_mix) / output.len().max(1) as f32;
+                    for (i, (out, &dry)) in output.iter_mut().zip(delayed.iter()).enumerate() {
+                        let mix = self.bypass_mix + step * (i + 1) as f32;
+                        *out += (dry - *out) * mix;
+                    }
                 }
-                
+                // A zero-length block has no audio to fade over, so the fade waits
+                if !output.is_empty() {
+                    self.bypass_mix = bypass_target;
+                }
+            }
+
+            if !output.is_empty() {
+                // Moved out for the call so hooks can borrow the runtime; this doesn't allocate
+                let mut hooks = std::mem::take(&mut self.tick_hooks);
+                for hook in &mut hooks {
+                    hook(self, output);
+                }
+                self.tick_hooks = hooks;
+            }
+            self.events.clear();
+
+            TickStatus {
+                audible: output.iter().any(|sample| sample.abs() > SILENCE_THRESHOLD),
+                modulators_active,
+            }
+        }
+    }
+    
+    // Writes `input` into the bypass delay line and what it delays out into `delayed`.
+    // Input shorter than `delayed` is padded with silence.
+    fn delay_for_bypass(&mut self, input: &[f32], delayed: &mut [f32]) {
+        let samples = input.iter().copied().chain(std::iter::repeat(0.0));
+        if self.bypass_line.is_empty() {
+            for (out, sample) in delayed.iter_mut().zip(samples) {
+                *out = sample;
+            }
+            return;
+        }
+        for (out, sample) in delayed.iter_mut().zip(samples) {
+            *out = std::mem::replace(&mut self.bypass_line[self.bypass_head], sample);
+            self.bypass_head = (self.bypass_head + 1) % self.bypass_line.len();
+        }
+    }
+
+    /// Processes one block of at most `BUFFER_SIZE` samples: the smallest unit of
+    /// work, for fuzzing and property tests. Every event but the last is handed to
+    /// the modulators ahead of the block, as in `render_timeline`, and the last one
+    /// is dispatched with the audio; components see them all through `events`.
+    ///
+    /// Processing is deterministic: a runtime built the same way and given the same
+    /// sequence of calls produces bit-identical output. Nothing reads clocks, devices
+    /// or global state, and components with noise seed it themselves.
+    pub fn process_block(&mut self, sample_rate: f32, events: &[E], input: &[f32], output: &mut [f32]) -> TickStatus {
+        assert!(output.len() <= BUFFER_SIZE, "process_block takes at most BUFFER_SIZE samples");
+        let (last, earlier) = match events.split_last() {
+            Some((last, earlier)) => (Some(last.clone()), earlier),
+            None => (None, events),
+        };
+        self.dispatch_emitted(sample_rate);
+        for event in earlier {
+            self.dispatch_event(sample_rate, event.clone());
+        }
+        self.tick_block(sample_rate, last, input, output)
+    }
+
+    /// Renders `frames` samples offline with silent input and no events.
+    pub fn render(&mut self, sample_rate: f32, frames: usize) -> Vec<f32> {
+        self.render_timeline(sample_rate, &[], frames)
+    }
+
+    /// Renders `frames` samples offline, dispatching each `(frame, event)` exactly at
+    /// its frame. Blocks are split at event frames; of events sharing a frame, all
+    /// but the last are handed to the modulators on their own just before the audio
+    /// at that frame. `timeline` must be sorted by frame, and events at or past
+    /// `frames` are never dispatched.
+    pub fn render_timeline(&mut self, sample_rate: f32, timeline: &[(usize, E)], frames: usize) -> Vec<f32> {
+        self.render_automated(sample_rate, timeline, &[], frames)
+    }
+
+    /// `render_timeline`, also setting parameters from `automation` as each point is
+    /// reached. A point with `AutomationCurve::Linear` ramps from the previous point
+    /// for the same parameter rather than stepping; ramps are rendered in one-frame
+    /// ticks, which cost more per sample than steady stretches but smooth the same
+    /// way, since parameters advance by each tick's length. `automation` may be in
+    /// any order (points for one parameter at the same frame apply in the order
+    /// given), and points naming a parameter the target doesn't have are skipped.
+    pub fn render_automated(&mut self, sample_rate: f32, timeline: &[(usize, E)], automation: &[Automation], frames: usize) -> Vec<f32> {
+        debug_assert!(timeline.windows(2).all(|pair| pair[0].0 <= pair[1].0), "timeline must be sorted by frame");
+        let mut output = vec![0.0; frames];
+        let mut events = timeline.iter().peekable();
+
+        // Each parameter's points in frame order, with a cursor at the next one to reach
+        let mut lanes: Vec<(Vec<&Automation>, usize)> = Vec::new();
+        for point in automation {
+            match lanes.iter_mut().find(|(points, _)| (points[0].target, points[0].param) == (point.target, point.param)) {
+                Some((points, _)) => points.push(point),
+                None => lanes.push((vec![point], 0)),
+            }
+        }
+        for (points, _) in &mut lanes {
+            points.sort_by_key(|point| point.frame);
+        }
+        let mut position = 0;
+
+        while position < frames {
+            let mut ramping = false;
+            let mut next_point = frames;
+            for (points, cursor) in &mut lanes {
+                while let Some(point) = points.get(*cursor).filter(|point| point.frame <= position) {
+                    self.apply_automation(point, point.value);
+                    *cursor += 1;
+                }
+                let Some(next) = points.get(*cursor) else { continue };
+                next_point = next_point.min(next.frame);
+                // Ramps run from the last point reached toward this lane's next one
+                if let (AutomationCurve::Linear, Some(from)) = (next.curve, cursor.checked_sub(1).map(|reached| points[reached])) {
+                    let t = (position - from.frame) as f32 / (next.frame - from.frame) as f32;
+                    self.apply_automation(next, from.value + (next.value - from.value) * t);
+                    ramping = true;
+                }
+            }
+
+            // Every event but the last one due at this frame goes ahead on its own
+            let mut event = None;
+            while let Some((_, due)) = events.next_if(|(frame, _)| *frame <= position) {
+                if let Some(earlier) = event.replace(due.clone()) {
+                    self.dispatch_emitted(sample_rate);
+                    self.dispatch_event(sample_rate, earlier);
+                }
+            }
+
+            let next_event = events.peek().map_or(frames, |(frame, _)| (*frame).min(frames));
+            let block_end = if ramping { position + 1 } else { position + BUFFER_SIZE };
+            let end = next_event.min(next_point).min(block_end);
+            self.tick_block(sample_rate, event, &SILENCE[..end - position], &mut output[position..end]);
+            position = end;
+        }
+        output
+    }
+
+    fn apply_automation(&mut self, point: &Automation, value: f32) {
+        let _ = self.apply(ControlCommand(Command::SetBase { target: point.target, param: point.param, value }));
+    }
+
+    /// Ticks `frames` samples of silence with no events and discards the output, letting
+    /// delay lines and feedback networks settle before the first captured block.
+    pub fn prime(&mut self, sample_rate: f32, frames: usize) {
+        let mut output = [0.0; BUFFER_SIZE];
+        let mut remaining = frames;
+        while remaining > 0 {
+            let len = remaining.min(BUFFER_SIZE);
+            self.tick(sample_rate, None, &SILENCE[..len], &mut output[..len]);
+            remaining -= len;
+        }
+    }
+
+    /// The block's values for `handle`. Each tick only marks parameter runtimes
+    /// stale; a runtime is updated on its first read in a block and cached for
+    /// later reads, so parameters nothing reads cost nothing. Smoothing on an
+    /// unread parameter pauses and picks up where it left off.
+    pub fn get_parameters<T: Parameters>(&self, handle: &ParameterHandle<T>) -> T::Accessor<'_, E> {
+        unsafe {
+            let sources = &*self.modulation_sources.get();
+
+            let target_boxed = &mut *self.modulation_targets[handle.slot].get();
+            let concrete_runtime = &mut *(target_boxed.as_mut() as *mut dyn ParameterRuntime<E> as *mut T::Runtime<E>);
+
+            // Once per block, however many components read these parameters
+            if self.params_stale[handle.slot].replace(false) {
+                concrete_runtime.update(self.sample_rate, sources, self.block_len);
+            }
+            T::create_accessor(concrete_runtime)
+        }
+    }
+}
+
+// === Chaining Runtimes ===
+/// Runs several runtimes in series, each one's output feeding the next one's input,
+/// e.g. separately built modules in a rack. Every runtime's `UnsafeCell`s are only
+/// reached through its own `&mut self`, so ticking one runtime into a buffer and a
+/// second from that buffer is sound; `Chain` just does that with reused buffers.
+pub struct Chain<E: 'static> {
+    runtimes: Vec<Runtime<E>>,
+    buffers: [Vec<f32>; 2],
+}
+
+impl<E: 'static + Send + Clone> Chain<E> {
+    pub fn new(runtimes: Vec<Runtime<E>>) -> Self {
+        Self { runtimes, buffers: [Vec::new(), Vec::new()] }
+    }
+
+    pub fn runtimes_mut(&mut self) -> &mut [Runtime<E>] {
+        &mut self.runtimes
+    }
+
+    /// Ticks every runtime in order; each one receives `event`.
+    pub fn tick(&mut self, sample_rate: f32, event: Option<E>, input: &[f32], output: &mut [f32]) -> TickStatus {
+        let count = self.runtimes.len();
+        if count == 0 {
+            let len = output.len().min(input.len());
+            output[..len].copy_from_slice(&input[..len]);
+            output[len..].fill(0.0);
+            return TickStatus { audible: false, modulators_active: false };
+        }
+
+        for buffer in &mut self.buffers {
+            if buffer.len() != output.len() {
+                buffer.resize(output.len(), 0.0);
+            }
+        }
+
+        let mut status = TickStatus { audible: false, modulators_active: false };
+        let [a, b] = &mut self.buffers;
+        for (i, runtime) in self.runtimes.iter_mut().enumerate() {
+            // Stages alternate between the two buffers; the first reads `input`, the last writes `output`
+            let (from, to) = if i % 2 == 0 { (&*b, &mut *a) } else { (&*a, &mut *b) };
+            let stage_input = if i == 0 { input } else { from.as_slice() };
+            let stage_output = if i + 1 == count { &mut *output } else { to.as_mut_slice() };
+            let stage_status = runtime.tick(sample_rate, event.clone(), stage_input, stage_output);
+            status.modulators_active |= stage_status.modulators_active;
+            status.audible = stage_status.audible;
+        }
+        status
+    }
+}
+
+// === Automation ===
+/// How an automation point is reached from the previous point for the same parameter.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum AutomationCurve {
+    /// Jump to the value at the point's frame
+    #[default]
+    Step,
+    /// Ramp linearly from the previous point's value, arriving at the point's frame
+    Linear,
+}
+
+/// A parameter's base value at a frame of an offline render, see `Runtime::render_automated`.
+#[derive(Clone, Copy, Debug)]
+pub struct Automation {
+    frame: usize,
+    target: usize,
+    param: &'static str,
+    value: f32,
+    curve: AutomationCurve,
+}
+
+impl Automation {
+    /// A point that steps to `value` at `frame`.
+    pub fn new<T: Parameters>(frame: usize, target: ParameterHandle<T>, param: &'static str, value: f32) -> Self {
+        Self { frame, target: target.slot, param, value, curve: AutomationCurve::Step }
+    }
+
+    pub fn with_curve(mut self, curve: AutomationCurve) -> Self {
+        self.curve = curve;
+        self
+    }
+
+    pub fn frame(&self) -> usize {
+        self.frame
+    }
+}
+
+// === Control Commands ===
+/// A parameter change built on a UI or control thread and applied by the audio
+/// thread between blocks, see `Engine::control`.
+#[derive(Clone)]
+pub struct ControlCommand(Command);
+
+#[derive(Clone)]
+enum Command {
+    SetBase { target: usize, param: &'static str, value: f32 },
+    Route { source: usize, target: usize, param: &'static str, amount: f32, mode: RoutingMode },
+    Unroute { source: usize, target: usize, param: &'static str },
+}
+
+impl ControlCommand {
+    pub fn set_base<T: Parameters>(target: ParameterHandle<T>, param: &'static str, value: f32) -> Self {
+        Self(Command::SetBase { target: target.slot, param, value })
+    }
+
+    pub fn route<S, T: Parameters>(source: ModulatorHandle<S>, target: ParameterHandle<T>, param: &'static str, amount: f32) -> Self {
+        Self::route_with_mode(source, target, param, amount, RoutingMode::Add)
+    }
+
+    pub fn route_with_mode<S, T: Parameters>(
+        source: ModulatorHandle<S>,
+        target: ParameterHandle<T>,
+        param: &'static str,
+        amount: f32,
+        mode: RoutingMode,
+    ) -> Self {
+        Self(Command::Route { source: source.slot, target: target.slot, param, amount, mode })
+    }
+
+    pub fn unroute<S, T: Parameters>(source: ModulatorHandle<S>, target: ParameterHandle<T>, param: &'static str) -> Self {
+        Self(Command::Unroute { source: source.slot, target: target.slot, param })
+    }
+}
+
+// === Routing Config ===
+/// A preset's base values and routings, naming parameters and modulators by the
+/// names given with `Builder::name_parameters` and `Builder::name_modulator` so it
+/// can be stored in a file. Apply it with `Runtime::apply_config`.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(default))]
+pub struct RoutingConfig {
+    pub bases: Vec<BaseEntry>,
+    pub routes: Vec<RouteEntry>,
+}
+
+/// Sets `param` of the parameters named `target` to `value`, normalized.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BaseEntry {
+    pub target: String,
+    pub param: String,
+    pub value: f32,
+}
+
+/// Routes the modulator named `source` to `param` of the parameters named `target`.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RouteEntry {
+    pub source: String,
+    pub target: String,
+    pub param: String,
+    pub amount: f32,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub mode: RoutingMode,
+}
+
+// === Reorderable chains ===
+/// Processing order of a `serial_reorderable` chain, as indices into the
+/// components it was built from. Change it between ticks with `Runtime::get_mut`.
+pub struct SerialOrder {
+    order: Vec<usize>,
+}
+
+impl SerialOrder {
+    pub fn order(&self) -> &[usize] {
+        &self.order
+    }
+
+    /// Returns `false`, leaving the order unchanged, unless `order` is a
+    /// permutation of the chain's component indices.
+    pub fn set(&mut self, order: &[usize]) -> bool {
+        let mut sorted = order.to_vec();
+        sorted.sort_unstable();
+        if !sorted.iter().copied().eq(0..self.order.len()) {
+            return false;
+        }
+        self.order.copy_from_slice(order);
+        true
+    }
+}
+
+/// Like `serial!`, but the order the components run in is read from a
+/// `SerialOrder` each block, so it can be changed at runtime (filter before or
+/// after distortion, say). Starts in the order given.
+pub fn serial_reorderable<E>(components: Vec<ComponentFn<E>>) -> impl FnOnce(&mut Builder<E>) -> (ComponentFn<E>, StateHandle<SerialOrder>)
+where
+    E: Clone + Send + 'static,
+{
+    move |builder: &mut Builder<E>| {
+        let len = components.len();
+        let order = builder.new_state(move || SerialOrder { order: (0..len).collect() });
+        let mut components = components;
+        let mut buffer_a = Vec::new();
+        let mut buffer_b = Vec::new();
+
+        let component: ComponentFn<E> = Box::new(move |runtime, input, output, sample_rate| {
+            buffer_a.clear();
+            buffer_a.extend_from_slice(input);
+            buffer_a.resize(output.len(), 0.0);
+            buffer_b.resize(output.len(), 0.0);
+
+            for step in 0..components.len() {
+                let index = runtime.get(&order).order[step];
+                buffer_b.fill(0.0);
+                components[index](runtime, &buffer_a, &mut buffer_b, sample_rate);
+                std::mem::swap(&mut buffer_a, &mut buffer_b);
+            }
+            output.copy_from_slice(&buffer_a);
+        });
+        (component, order)
+    }
+}
+
+// === Buses ===
+/// One block of signal that a component writes (see `components::send`) and
+/// others read later in the same tick, e.g. as `parallel_from!` branch inputs.
+pub struct Bus {
+    samples: [f32; BUFFER_SIZE],
+    len: usize,
+}
+
+impl Default for Bus {
+    fn default() -> Self {
+        Self { samples: [0.0; BUFFER_SIZE], len: 0 }
+    }
+}
+
+impl Bus {
+    /// Replaces the bus contents; anything past `BUFFER_SIZE` is dropped.
+    pub fn write(&mut self, samples: &[f32]) {
+        self.len = samples.len().min(BUFFER_SIZE);
+        self.samples[..self.len].copy_from_slice(&samples[..self.len]);
+    }
+
+    /// What was last written.
+    pub fn read(&self) -> &[f32] {
+        &self.samples[..self.len]
+    }
+}
+
+// === Mixing ===
+/// Adds `input * weight` into `output`, the accumulate step of `parallel!`. With
+/// the `simd` feature the bulk runs eight lanes at a time, with a scalar remainder.
+pub fn mix_weighted(output: &mut [f32], input: &[f32], weight: f32) {
+    #[cfg(feature = "simd")]
+    {
+        use wide::f32x8;
+        let len = output.len().min(input.len());
+        let (out_lanes, out_rest) = output[..len].as_chunks_mut::<8>();
+        let (in_lanes, in_rest) = input[..len].as_chunks::<8>();
+        let weights = f32x8::splat(weight);
+        for (out, &samples) in out_lanes.iter_mut().zip(in_lanes) {
+            *out = (f32x8::new(*out) + f32x8::new(samples) * weights).to_array();
+        }
+        mix_weighted_scalar(out_rest, in_rest, weight);
+    }
+    #[cfg(not(feature = "simd"))]
+    mix_weighted_scalar(output, input, weight);
+}
+
+fn mix_weighted_scalar(output: &mut [f32], input: &[f32], weight: f32) {
+    for (out, &sample) in output.iter_mut().zip(input.iter()) {
+        *out += sample * weight;
+    }
+}
+
+// === Main API ===
+pub fn new<E: Clone + Send + 'static>() -> (EventBus<E>, Builder<E>) {
+    (EventBus::new(), Builder::new())
+}
+
+// === Macros ===
+/// Sums weighted branches that all read the same input:
+/// `parallel!((0.5, dry), (0.5, chorus))`. The branches become a `Graph`, compiled
+/// into a `Schedule`; a branch that is itself a `serial!(..)` or `parallel!(..)`
+/// invocation is spliced in rather than compiled on its own, so nesting flattens
+/// into one schedule over one buffer pool.
+#[macro_export]
+macro_rules! parallel {
+    ($($branches:tt)+) => {
+        |builder: &mut $crate::Builder<_>| -> $crate::ComponentFn<_> {
+            $crate::Schedule::compile($crate::__parallel_graph!(builder; []; $($branches)+)).into_component()
+        }
+    };
+}
+
+/// `parallel!`, with the weighted sum soft-clipped by `tanh` so loud branch
+/// combinations saturate instead of exceeding ±1. Quiet sums pass nearly unchanged.
+#[macro_export]
+macro_rules! parallel_sat {
+    ($(($weight:expr, $comp:expr)),+) => {
+        |builder: &mut $crate::Builder<_>| -> $crate::ComponentFn<_> {
+            let mut sum: $crate::ComponentFn<_> = $crate::parallel!($(($weight, $comp)),+)(builder);
+
+            Box::new(move |runtime, input, output, sample_rate| {
+                sum(runtime, input, output, sample_rate);
+                for sample in output.iter_mut() {
+                    *sample = sample.tanh();
+                }
+            })
+        }
+    };
+}
+
+/// `parallel!` where each branch reads its own bus instead of the shared input:
+/// `parallel_from!((weight, bus, component), ...)`. Fill the buses earlier in
+/// the graph, e.g. with `components::send`. A bus holding fewer samples than the
+/// block is padded with silence.
+#[macro_export]
+macro_rules! parallel_from {
+    ($(($weight:expr, $bus:expr, $comp:expr)),+) => {
+        |builder: &mut $crate::Builder<_>| -> $crate::ComponentFn<_> {
+            let mut branches: Vec<(f32, $crate::StateHandle<$crate::Bus>, $crate::ComponentFn<_>)> =
+                vec![$(($weight as f32, $bus, $comp(builder))),+];
+            let mut branch_input: Vec<f32> = Vec::new();
+            let mut branch_output: Vec<f32> = Vec::new();
+
+            Box::new(move |runtime, _input, output, sample_rate| {
+                output.fill(0.0);
+                for (weight, bus, comp) in branches.iter_mut() {
+                    branch_input.clear();
+                    branch_input.extend_from_slice(runtime.get(bus).read());
+                    branch_input.resize(output.len(), 0.0);
+                    branch_output.clear();
+                    branch_output.resize(output.len(), 0.0);
+
+                    comp(runtime, &branch_input, &mut branch_output, sample_rate);
+                    $crate::mix_weighted(output, &branch_output, *weight);
+                }
+            })
+        }
+    };
+}
+
+/// Runs components one after another, each reading the previous one's output.
+/// Like `parallel!`, the chain is compiled into a `Schedule`, and nested
+/// `serial!(..)` / `parallel!(..)` stages are spliced into it.
+#[macro_export]
+macro_rules! serial {
+    ($($stages:tt)+) => {
+        |builder: &mut $crate::Builder<_>| -> $crate::ComponentFn<_> {
+            $crate::Schedule::compile($crate::__serial_graph!(builder; []; $($stages)+)).into_component()
+        }
+    };
+}
+
+// Builds the `Graph` behind `serial!` one argument at a time. Macro invocations
+// are matched by name before falling back to an expression, since a `serial!(..)`
+// argument would otherwise be expanded into a separately compiled component.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __serial_graph {
+    ($builder:ident; [$($nodes:expr),*]; $(,)?) => {
+        $crate::Graph::Serial(vec![$($nodes),*])
+    };
+    ($builder:ident; [$($nodes:expr),*]; serial!($($inner:tt)+) $(, $($rest:tt)*)?) => {
+        $crate::__serial_graph!($builder; [$($nodes,)* $crate::__serial_graph!($builder; []; $($inner)+)]; $($($rest)*)?)
+    };
+    ($builder:ident; [$($nodes:expr),*]; parallel!($($inner:tt)+) $(, $($rest:tt)*)?) => {
+        $crate::__serial_graph!($builder; [$($nodes,)* $crate::__parallel_graph!($builder; []; $($inner)+)]; $($($rest)*)?)
+    };
+    ($builder:ident; [$($nodes:expr),*]; $comp:expr $(, $($rest:tt)*)?) => {
+        $crate::__serial_graph!($builder; [$($nodes,)* $crate::Graph::from($comp($builder))]; $($($rest)*)?)
+    };
+}
+
+// `__serial_graph!` for `parallel!`'s `(weight, component)` pairs
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __parallel_graph {
+    ($builder:ident; [$($nodes:expr),*]; $(,)?) => {
+        $crate::Graph::Parallel(vec![$($nodes),*])
+    };
+    ($builder:ident; [$($nodes:expr),*]; ($weight:expr, serial!($($inner:tt)+)) $(, $($rest:tt)*)?) => {
+        $crate::__parallel_graph!($builder; [$($nodes,)* ($weight as f32, $crate::__serial_graph!($builder; []; $($inner)+))]; $($($rest)*)?)
+    };
+    ($builder:ident; [$($nodes:expr),*]; ($weight:expr, parallel!($($inner:tt)+)) $(, $($rest:tt)*)?) => {
+        $crate::__parallel_graph!($builder; [$($nodes,)* ($weight as f32, $crate::__parallel_graph!($builder; []; $($inner)+))]; $($($rest)*)?)
+    };
+    ($builder:ident; [$($nodes:expr),*]; ($weight:expr, $comp:expr) $(, $($rest:tt)*)?) => {
+        $crate::__parallel_graph!($builder; [$($nodes,)* ($weight as f32, $crate::Graph::from($comp($builder)))]; $($($rest)*)?)
+    };
+}
+
+/// `serial!` where each stage's output is blended with that stage's input before
+/// moving on: `serial_blend!((0.3, drive), (1.0, filter))`. A mix of 0 skips the
+/// stage's effect and 1 passes it fully, as in `serial!`.
+#[macro_export]
+macro_rules! serial_blend {
+    ($(($mix:expr, $comp:expr)),+) => {
+        |builder: &mut $crate::Builder<_>| -> $crate::ComponentFn<_> {
+            let mut components: Vec<(f32, $crate::ComponentFn<_>)> = vec![$(($mix as f32, $comp(builder))),+];
+            let mut buffer_a = Vec::new();
+            let mut buffer_b = Vec::new();
+
+            Box::new(move |runtime, input, output, sample_rate| {
+                if buffer_a.len() != output.len() {
+                    buffer_a.resize(output.len(), 0.0);
+                    buffer_b.resize(output.len(), 0.0);
+                }
+
+                buffer_a.copy_from_slice(input);
+
+                for (i, (mix, comp)) in components.iter_mut().enumerate() {
+                    let (inp, out) = if i % 2 == 0 {
+                        (&buffer_a[..], &mut buffer_b[..])
+                    } else {
+                        (&buffer_b[..], &mut buffer_a[..])
+                    };
+                    out.fill(0.0);
+                    comp(runtime, inp, out, sample_rate);
+                    for (wet, &dry) in out.iter_mut().zip(inp.iter()) {
+                        *wet = dry + (*wet - dry) * *mix;
+                    }
+                }
+
                 let final_buf = if components.len() % 2 == 1 { &buffer_b } else { &buffer_a };
                 output.copy_from_slice(final_buf);
             })
@@ -339,7 +1685,1738 @@ macro_rules! serial {
     };
 }
 
-struct ModulationRouting {
-    source_index: usize,
-    amount: f32,
-}
\ No newline at end of file
+/// Blends a sub-graph's output with its unprocessed input. `$mix` is a `#[parameters]`
+/// struct with a `mix` field (0 = dry, 1 = wet), read per sample so it can be modulated.
+/// The dry signal is delayed by whatever latency the sub-graph reports, so the two
+/// stay aligned.
+#[macro_export]
+macro_rules! wet_dry {
+    ($mix:ty, $comp:expr) => {
+        |builder: &mut $crate::Builder<_>| -> $crate::ComponentFn<_> {
+            let mix_handle = builder.use_parameters::<$mix>();
+            let (mut component, latency) = builder.measure_latency($comp);
+            let mut dry_line = vec![0.0f32; latency];
+            let mut dry_head = 0;
+            
+            Box::new(move |runtime, input, output, sample_rate| {
+                output.fill(0.0);
+                component(runtime, input, output, sample_rate);
+                
+                let mix = runtime.get_parameters(&mix_handle);
+                for (i, (out, &dry)) in output.iter_mut().zip(input.iter()).enumerate() {
+                    let dry = if dry_line.is_empty() {
+                        dry
+                    } else {
+                        let delayed = std::mem::replace(&mut dry_line[dry_head], dry);
+                        dry_head = (dry_head + 1) % dry_line.len();
+                        delayed
+                    };
+                    let amount = mix[i].mix;
+                    *out = dry * (1.0 - amount) + *out * amount;
+                }
+            })
+        }
+    };
+}
+
+// === Shared Modulators ===
+// How many times a reader retries a block the owner is publishing before it
+// keeps the block it already has
+const SHARED_READ_ATTEMPTS: usize = 4;
+
+// One published block, guarded like a seqlock: `generation` is odd while the owner
+// writes and even once the block is complete, so a reader that sees the same even
+// generation before and after copying knows it copied a single block.
+struct SharedBlock {
+    generation: AtomicU64,
+    values: [AtomicU32; BUFFER_SIZE],
+    active: AtomicBool,
+}
+
+impl SharedBlock {
+    // Only the owner writes, so the generation can't change under it
+    fn publish(&self, value: impl Fn(usize) -> f32, active: bool) {
+        let generation = self.generation.load(Ordering::Relaxed);
+        self.generation.store(generation + 1, Ordering::Relaxed);
+        fence(Ordering::Release);
+        for (i, slot) in self.values.iter().enumerate() {
+            slot.store(value(i).to_bits(), Ordering::Relaxed);
+        }
+        self.active.store(active, Ordering::Relaxed);
+        self.generation.store(generation + 2, Ordering::Release);
+    }
+
+    // Copies the latest complete block into `values`, returning its activity, or
+    // `None` (with `values` untouched) if every attempt overlapped a publish
+    fn read(&self, values: &mut [f32; BUFFER_SIZE]) -> Option<bool> {
+        let mut copy = [0.0; BUFFER_SIZE];
+        for _ in 0..SHARED_READ_ATTEMPTS {
+            let before = self.generation.load(Ordering::Acquire);
+            if before % 2 == 1 {
+                std::hint::spin_loop();
+                continue;
+            }
+            for (value, slot) in copy.iter_mut().zip(self.values.iter()) {
+                *value = f32::from_bits(slot.load(Ordering::Relaxed));
+            }
+            let active = self.active.load(Ordering::Relaxed);
+            fence(Ordering::Acquire);
+            if self.generation.load(Ordering::Relaxed) == before {
+                *values = copy;
+                return Some(active);
+            }
+        }
+        None
+    }
+}
+
+pub struct SharedModulator<T> {
+    modulator: Mutex<Option<T>>,
+    block: Arc<SharedBlock>,
+}
+
+impl<T> SharedModulator<T> {
+    pub fn new(modulator: T) -> Arc<Self> {
+        Arc::new(Self {
+            modulator: Mutex::new(Some(modulator)),
+            block: Arc::new(SharedBlock {
+                generation: AtomicU64::new(0),
+                values: std::array::from_fn(|_| AtomicU32::new(0.0f32.to_bits())),
+                active: AtomicBool::new(true),
+            }),
+        })
+    }
+}
+
+/// A runtime's view of a `SharedModulator`: the owner holds and advances the
+/// modulator, readers see the values it published.
+///
+/// The owner publishes each block as a unit and a reader copies one at the start
+/// of its own block, so every value a reader returns during a block comes from the
+/// same published block, never a mix of two. If the owner is publishing while a
+/// reader copies, the reader retries a few times and otherwise keeps the block it
+/// had, so it never waits on the owner.
+pub struct SharedSource<T> {
+    owned: Option<T>,
+    block: Arc<SharedBlock>,
+    // A reader's copy of the last block it read
+    values: [f32; BUFFER_SIZE],
+    active: bool,
+}
+
+impl<T> SharedSource<T> {
+    pub fn is_owner(&self) -> bool {
+        self.owned.is_some()
+    }
+
+    /// The shared modulator itself, only reachable through the owning runtime.
+    pub fn modulator_mut(&mut self) -> Option<&mut T> {
+        self.owned.as_mut()
+    }
+}
+
+impl<E, T: Modulator<E>> Modulator<E> for SharedSource<T> {
+    fn update(&mut self, sample_rate: f32, event: Option<&E>) {
+        self.update_block(sample_rate, event, BUFFER_SIZE);
+    }
+
+    fn update_block(&mut self, sample_rate: f32, event: Option<&E>, len: usize) {
+        match &mut self.owned {
+            Some(modulator) => {
+                modulator.update_block(sample_rate, event, len);
+                // Readers see the held values, so they can read every sample
+                let rate = modulator.rate();
+                self.block.publish(|i| modulator.get_value(rate.held_index(i)), modulator.is_active());
+            }
+            None => {
+                if let Some(active) = self.block.read(&mut self.values) {
+                    self.active = active;
+                }
+            }
+        }
+    }
+
+    fn get_value(&self, index: usize) -> f32 {
+        match &self.owned {
+            Some(modulator) => modulator.get_value(index),
+            None => self.values[index % BUFFER_SIZE],
+        }
+    }
+
+    fn is_active(&self) -> bool {
+        match &self.owned {
+            Some(modulator) => modulator.is_active(),
+            None => self.active,
+        }
+    }
+
+    fn rate(&self) -> ModRate {
+        match &self.owned {
+            Some(modulator) => modulator.rate(),
+            None => ModRate::AudioRate,
+        }
+    }
+
+    fn range(&self) -> (f32, f32) {
+        match &self.owned {
+            Some(modulator) => modulator.range(),
+            None => (0.0, 1.0),
+        }
+    }
+
+    fn debug_state(&self) -> String {
+        match &self.owned {
+            Some(modulator) => modulator.debug_state(),
+            None => "shared (reader)".to_string(),
+        }
+    }
+}
+
+// === Modulation Routing ===
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum RoutingMode {
+    /// Adds `source * amount` on top of the base value and other additive routings.
+    #[default]
+    Add,
+    /// Pins the parameter to `source * amount`, ignoring the base and additive routings.
+    /// When several overrides target one parameter the highest priority wins, and
+    /// between equal priorities the most recently routed one does.
+    Override { priority: i32 },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RouteError {
+    /// The target parameters have no field with this name
+    UnknownParam(String),
+    /// No parameters were registered under this name with `Builder::name_parameters`
+    UnknownTarget(String),
+    /// No modulator was registered under this name with `Builder::name_modulator`
+    UnknownSource(String),
+}
+
+impl std::fmt::Display for RouteError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RouteError::UnknownParam(name) => write!(f, "unknown parameter '{}'", name),
+            RouteError::UnknownTarget(name) => write!(f, "no parameters named '{}'", name),
+            RouteError::UnknownSource(name) => write!(f, "no modulator named '{}'", name),
+        }
+    }
+}
+
+impl std::error::Error for RouteError {}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ModulationRouting {
+    pub source_index: usize,
+    pub amount: f32,
+    pub mode: RoutingMode,
+}
+
+/// Custom mapping applied to a parameter's value after modulation.
+pub type ParamMap = Box<dyn Fn(f32) -> f32 + Send>;
+
+/// Modulation state for a single parameter: every routing that targets it, most
+/// recently routed last, each with its source's depth scale, and an optional
+/// output map. Routings never grow past the capacity set by `reserve`, so they
+/// can change on the audio thread; `Builder::build` reserves one per source.
+#[derive(Default)]
+pub struct ParamModulation {
+    routings: Vec<(ModulationRouting, f32)>,
+    map: Option<ParamMap>,
+}
+
+impl ParamModulation {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Makes room for routings from `sources` sources in all.
+    pub fn reserve(&mut self, sources: usize) {
+        self.routings.reserve_exact(sources.saturating_sub(self.routings.len()));
+    }
+
+    /// Adds a routing, replacing any earlier routing from the same source in place.
+    /// Re-routing a source keeps its depth. A new source is dropped when the
+    /// reserved capacity is full rather than reallocating.
+    pub fn route(&mut self, routing: ModulationRouting) {
+        match self.routings.iter().position(|(existing, _)| existing.source_index == routing.source_index) {
+            Some(index) => {
+                // Moved to the end, as the most recently routed
+                self.routings[index..].rotate_left(1);
+                self.routings.last_mut().unwrap().0 = routing;
+            }
+            None if self.routings.len() < self.routings.capacity() => self.routings.push((routing, 1.0)),
+            None => {}
+        }
+    }
+
+    pub fn unroute(&mut self, source_index: usize) {
+        self.routings.retain(|(existing, _)| existing.source_index != source_index);
+    }
+
+    pub fn set_source_depth(&mut self, source_index: usize, depth: f32) {
+        for (routing, routing_depth) in &mut self.routings {
+            if routing.source_index == source_index {
+                *routing_depth = depth;
+            }
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.routings.is_empty()
+    }
+
+    /// Indices of the sources routed here.
+    pub fn sources(&self) -> impl Iterator<Item = usize> + '_ {
+        self.routings.iter().map(|(routing, _)| routing.source_index)
+    }
+
+    pub fn set_map(&mut self, map: ParamMap) {
+        self.map = Some(map);
+    }
+
+    pub fn apply_map(&self, value: f32) -> f32 {
+        match &self.map {
+            Some(map) => map(value),
+            None => value,
+        }
+    }
+
+    /// Returns the summed additive modulation at sample `index`, and the value of
+    /// the winning override routing if there is one.
+    pub fn evaluate<E: 'static>(&self, sources: &[Box<dyn Modulator<E>>], index: usize) -> (f32, Option<f32>) {
+        let mut sum = 0.0;
+        let mut winner: Option<(i32, f32)> = None;
+        for (routing, depth) in &self.routings {
+            // A source at zero depth (or disabled) doesn't take part, even as an override
+            if *depth == 0.0 {
+                continue;
+            }
+            let source = &sources[routing.source_index];
+            let value = source.get_value(source.rate().held_index(index)) * routing.amount * depth;
+            match routing.mode {
+                RoutingMode::Add => sum += value,
+                RoutingMode::Override { priority } => {
+                    match winner {
+                        Some((best, _)) if priority < best => {}
+                        _ => winner = Some((priority, value)),
+                    }
+                }
+            }
+        }
+        (sum, winner.map(|(_, value)| value))
+    }
+}
+
+/// One-pole smoothing for a parameter's value, so stepped changes (a knob, a
+/// `set_base`) glide instead of clicking. The coefficient is recomputed whenever
+/// the sample rate changes, so the time stays the same in milliseconds.
+pub struct ParamSmoother {
+    time_ms: f32,
+    sample_rate: f32,
+    coefficient: f32,
+    // None until the first value, which is taken as is
+    value: Option<f32>,
+}
+
+impl ParamSmoother {
+    pub fn new(time_ms: f32) -> Self {
+        Self { time_ms: time_ms.max(0.0), sample_rate: 0.0, coefficient: 1.0, value: None }
+    }
+
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        if sample_rate == self.sample_rate {
+            return;
+        }
+        self.sample_rate = sample_rate;
+        let samples = self.time_ms * 0.001 * sample_rate;
+        // No known rate or no time: follow the target directly
+        self.coefficient = if samples > 1.0 { 1.0 - (-1.0 / samples).exp() } else { 1.0 };
+    }
+
+    /// Fraction of the remaining distance covered per sample.
+    pub fn coefficient(&self) -> f32 {
+        self.coefficient
+    }
+
+    /// Moves one sample toward `target` and returns the new value.
+    pub fn next(&mut self, target: f32) -> f32 {
+        let value = match self.value {
+            Some(value) => value + (target - value) * self.coefficient,
+            None => target,
+        };
+        self.value = Some(value);
+        value
+    }
+
+    /// Whether the value has (all but) reached `target`. If so it is snapped
+    /// there, so a settled parameter can skip per-sample work.
+    pub fn settle(&mut self, target: f32) -> bool {
+        match self.value {
+            Some(value) if (value - target).abs() > 1e-6 => false,
+            _ => {
+                self.value = Some(target);
+                true
+            }
+        }
+    }
+}
+
+// === Pitch ===
+// Pitch parameters and note modulators share one normalized scale: MIDI note / 127,
+// so a note source routed with `RoutingMode::Override` at amount 1.0 plays in tune.
+
+pub fn note_to_pitch(note: f32) -> f32 {
+    note / 127.0
+}
+
+pub fn pitch_to_note(pitch: f32) -> f32 {
+    pitch * 127.0
+}
+
+pub fn pitch_to_hz(pitch: f32) -> f32 {
+    440.0 * 2f32.powf((pitch_to_note(pitch) - 69.0) / 12.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parameters;
+    use std::sync::atomic::AtomicUsize;
+
+    // Builds a graph with `f`, handing back what it returns alongside the component
+    fn build<E: Clone + Send + 'static, H>(f: impl FnOnce(&mut Builder<E>) -> (ComponentFn<E>, H)) -> (Runtime<E>, H) {
+        let (_bus, builder) = new::<E>();
+        let mut handles = None;
+        let runtime = builder.build(|builder| {
+            let (component, built) = f(builder);
+            handles = Some(built);
+            component
+        });
+        (runtime, handles.unwrap())
+    }
+
+    fn silent<E>() -> ComponentFn<E> {
+        Box::new(|_runtime, _input, _output, _sample_rate| {})
+    }
+
+    fn tick<E: Clone + Send + 'static>(runtime: &mut Runtime<E>) {
+        runtime.tick(48_000.0, None, &SILENCE, &mut [0.0; BUFFER_SIZE]);
+    }
+
+    // A source that holds one value, so routed amounts are easy to predict
+    struct Constant(f32);
+
+    impl Default for Constant {
+        fn default() -> Self {
+            Constant(1.0)
+        }
+    }
+
+    impl<E> Modulator<E> for Constant {
+        fn update(&mut self, _sample_rate: f32, _event: Option<&E>) {}
+
+        fn get_value(&self, _index: usize) -> f32 {
+            self.0
+        }
+    }
+
+    #[parameters]
+    struct LimitedParams {
+        #[param(default = 0.5, mod_limit = 0.1)]
+        level: f32,
+    }
+
+    #[test]
+    fn modulation_limit_caps_the_contribution_whatever_the_amount() {
+        for amount in [0.5, 1.0, 10.0, -10.0] {
+            let (mut runtime, (source, params)) = build::<(), _>(|builder| {
+                let handles = (builder.use_modulator::<Constant>(), builder.use_parameters::<LimitedParams>());
+                (silent(), handles)
+            });
+            runtime.route(source, params, "level", amount).unwrap();
+            tick(&mut runtime);
+            let level = runtime.get_parameters(&params)[0].level;
+            assert!((level - (0.5 + 0.1 * amount.signum())).abs() < 1e-6, "amount {amount}: {level}");
+        }
+    }
+
+    // Writes the first registered source's block to the output
+    fn play_first_source<E: Clone + Send + 'static>() -> ComponentFn<E> {
+        Box::new(|runtime, _input, output, _sample_rate| {
+            let source = runtime.sources().next().unwrap();
+            for (i, out) in output.iter_mut().enumerate() {
+                *out = source.get_value(i);
+            }
+        })
+    }
+
+    #[test]
+    fn tick_reports_idle_once_envelopes_finish_and_output_is_silent() {
+        let (mut runtime, ()) = build::<[u8; 3], _>(|builder| {
+            builder.use_modulator::<crate::modulators::AD>();
+            (play_first_source(), ())
+        });
+        let mut output = [0.0; BUFFER_SIZE];
+        assert!(!runtime.tick(48_000.0, Some([0x90, 60, 100]), &SILENCE, &mut output).is_idle());
+
+        // The default 200 ms decay is 37.5 blocks at 48 kHz
+        let busy_blocks = (0..100)
+            .take_while(|_| !runtime.tick(48_000.0, None, &SILENCE, &mut output).is_idle())
+            .count();
+        assert!((35..40).contains(&busy_blocks), "{busy_blocks}");
+        assert!(output.iter().all(|&sample| sample == 0.0));
+    }
+
+    // A source of 0.5 into a feedback loop with gain 0.99, settling at 50
+    fn feedback_loop<E>() -> ComponentFn<E> {
+        let mut state = 0.0f32;
+        Box::new(move |_runtime, _input, output, _sample_rate| {
+            for out in output.iter_mut() {
+                state = 0.5 + 0.99 * state;
+                *out = state;
+            }
+        })
+    }
+
+    #[test]
+    fn priming_settles_feedback_before_the_first_block() {
+        let render_first_block = |prime: usize| {
+            let (mut runtime, ()) = build::<(), _>(|_builder| (feedback_loop(), ()));
+            runtime.prime(48_000.0, prime);
+            runtime.render(48_000.0, BUFFER_SIZE)
+        };
+        let cold = render_first_block(0);
+        assert_eq!(cold[0], 0.5);
+
+        let primed = render_first_block(10_000);
+        assert!(primed.iter().all(|&sample| (sample - 50.0).abs() < 1e-3));
+    }
+
+    #[parameters]
+    struct LevelParams {
+        #[param(default = 0.5)]
+        level: f32,
+    }
+
+    #[test]
+    fn override_pins_the_value_over_a_concurrent_lfo() {
+        let (mut runtime, (lfo, pin, params)) = build::<(), _>(|builder| {
+            let lfo = builder.use_modulator::<crate::modulators::LfoBank<1>>();
+            let pin = builder.use_modulator::<Constant>();
+            (silent(), (lfo, pin, builder.use_parameters::<LevelParams>()))
+        });
+        runtime.get_source_mut(&lfo).set_frequency(50.0);
+        runtime.get_source_mut(&pin).0 = 0.8;
+        runtime.route(lfo, params, "level", 0.5).unwrap();
+        runtime.route_with_mode(pin, params, "level", 1.0, RoutingMode::Override { priority: 0 }).unwrap();
+
+        for _ in 0..8 {
+            tick(&mut runtime);
+            assert!(runtime.get_parameters(&params).iter().all(|values| values.level == 0.8));
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn base_values_round_trip_through_json() {
+        #[parameters(serde)]
+        struct PresetParams {
+            #[param(default = 0.3)]
+            cutoff: f32,
+            resonance: f32,
+        }
+
+        let preset = PresetParams { cutoff: 0.8, resonance: 0.25 };
+        let json = serde_json::to_string(&preset).unwrap();
+        let loaded: PresetParams = serde_json::from_str(&json).unwrap();
+        assert_eq!((loaded.cutoff, loaded.resonance), (0.8, 0.25));
+
+        // Fields missing from an older preset take their defaults
+        let older: PresetParams = serde_json::from_str(r#"{"resonance": 0.5}"#).unwrap();
+        assert_eq!((older.cutoff, older.resonance), (0.3, 0.5));
+    }
+
+    #[test]
+    fn routing_to_a_misspelled_param_is_an_error() {
+        let (mut runtime, (source, params)) = build::<(), _>(|builder| {
+            let handles = (builder.use_modulator::<Constant>(), builder.use_parameters::<LevelParams>());
+            (silent(), handles)
+        });
+        let error = runtime.route(source, params, "levle", 0.5).unwrap_err();
+        assert!(matches!(&error, RouteError::UnknownParam(name) if name == "levle"));
+        assert_eq!(runtime.is_modulated(params, "level"), Ok(false));
+        assert!(runtime.route(source, params, "level", 0.5).is_ok());
+    }
+
+    #[test]
+    fn runtimes_read_the_same_shared_lfo_block() {
+        let shared = SharedModulator::new(crate::modulators::LfoBank::<1>::default());
+        let build_with_shared = || build::<(), _>(|builder| {
+            let lfo = builder.use_shared_modulator(&shared);
+            (play_first_source(), lfo)
+        });
+        let (mut owner, lfo) = build_with_shared();
+        let (mut reader, _) = build_with_shared();
+        owner.get_source_mut(&lfo).modulator_mut().unwrap().set_frequency(100.0);
+
+        for _ in 0..4 {
+            // The owner ticks first each cycle
+            let owned = owner.render(48_000.0, BUFFER_SIZE);
+            let read = reader.render(48_000.0, BUFFER_SIZE);
+            assert_eq!(owned, read);
+            assert!(owned.iter().any(|&value| value != owned[0]));
+        }
+    }
+
+    #[test]
+    fn shared_blocks_are_never_read_torn() {
+        let block = Arc::new(SharedBlock {
+            generation: AtomicU64::new(0),
+            values: std::array::from_fn(|_| AtomicU32::new(0.0f32.to_bits())),
+            active: AtomicBool::new(true),
+        });
+        let writer_block = block.clone();
+        let writer = std::thread::spawn(move || {
+            for generation in 1..20_000 {
+                writer_block.publish(|_| generation as f32, true);
+            }
+        });
+
+        let mut values = [0.0; BUFFER_SIZE];
+        while !writer.is_finished() {
+            block.read(&mut values);
+            assert!(values.iter().all(|&value| value == values[0]), "torn block");
+        }
+        writer.join().unwrap();
+    }
+
+    // 0 for the first 100 samples of each block, then 1
+    #[derive(Default)]
+    struct StepAt100;
+
+    impl<E> Modulator<E> for StepAt100 {
+        fn update(&mut self, _sample_rate: f32, _event: Option<&E>) {}
+
+        fn get_value(&self, index: usize) -> f32 {
+            if index < 100 { 0.0 } else { 1.0 }
+        }
+    }
+
+    #[test]
+    fn params_read_at_a_mid_block_offset_see_the_step() {
+        let (mut runtime, (step, params)) = build::<(), _>(|builder| {
+            let handles = (builder.use_modulator::<StepAt100>(), builder.use_parameters::<LevelParams>());
+            (silent(), handles)
+        });
+        runtime.route(step, params, "level", 0.25).unwrap();
+        tick(&mut runtime);
+        let values = runtime.get_parameters(&params);
+        assert_eq!(values.at(99).level, 0.5);
+        assert_eq!(values.at(100).level, 0.75);
+    }
+
+    #[test]
+    fn param_map_is_applied_after_modulation() {
+        let (mut runtime, (source, params)) = build::<(), _>(|builder| {
+            let handles = (builder.use_modulator::<Constant>(), builder.use_parameters::<LevelParams>());
+            (silent(), handles)
+        });
+        runtime.set_param_map(params, "level", Box::new(|value| value * value)).unwrap();
+        runtime.route(source, params, "level", 0.25).unwrap();
+        tick(&mut runtime);
+        assert!(runtime.get_parameters(&params).iter().all(|values| values.level == 0.5625));
+        assert!(runtime.set_param_map(params, "levle", Box::new(|value| value)).is_err());
+    }
+
+    #[parameters]
+    struct AllDry {
+        #[param(default = 0.0)]
+        mix: f32,
+    }
+
+    #[parameters]
+    struct AllWet {
+        #[param(default = 1.0)]
+        mix: f32,
+    }
+
+    // Doubles its input, standing in for an effect
+    fn double<E>(_builder: &mut Builder<E>) -> ComponentFn<E> {
+        Box::new(|_runtime, input, output, _sample_rate| {
+            for (out, &sample) in output.iter_mut().zip(input) {
+                *out = sample * 2.0;
+            }
+        })
+    }
+
+    #[test]
+    fn wet_dry_mix_ends_give_pure_input_and_pure_effect() {
+        let input: Vec<f32> = (0..BUFFER_SIZE).map(|i| i as f32 / BUFFER_SIZE as f32).collect();
+        let run = |(mut runtime, ()): (Runtime<()>, ())| {
+            let mut output = [0.0; BUFFER_SIZE];
+            runtime.tick(48_000.0, None, &input, &mut output);
+            output
+        };
+        let dry = run(build(|builder| (crate::wet_dry!(AllDry, double)(builder), ())));
+        let wet = run(build(|builder| (crate::wet_dry!(AllWet, double)(builder), ())));
+        assert_eq!(dry[..], input[..]);
+        assert!(wet.iter().zip(&input).all(|(&wet, &dry)| wet == dry * 2.0));
+    }
+
+    #[parameters]
+    struct PairParams {
+        #[param(default = 0.5)]
+        routed: f32,
+        #[param(default = 0.5)]
+        fixed: f32,
+    }
+
+    #[test]
+    fn unrouted_params_are_computed_once_per_block() {
+        let (mut runtime, (source, params)) = build::<(), _>(|builder| {
+            let handles = (builder.use_modulator::<Constant>(), builder.use_parameters::<PairParams>());
+            (silent(), handles)
+        });
+        // The map runs wherever the value is computed, so it counts evaluations
+        let counter = |calls: &Arc<AtomicUsize>| {
+            let calls = calls.clone();
+            Box::new(move |value: f32| {
+                calls.fetch_add(1, Ordering::Relaxed);
+                value
+            })
+        };
+        let (routed_calls, fixed_calls) = (Arc::new(AtomicUsize::new(0)), Arc::new(AtomicUsize::new(0)));
+        runtime.set_param_map(params, "routed", counter(&routed_calls)).unwrap();
+        runtime.set_param_map(params, "fixed", counter(&fixed_calls)).unwrap();
+
+        tick(&mut runtime);
+        runtime.get_parameters(&params);
+        assert_eq!((routed_calls.load(Ordering::Relaxed), fixed_calls.load(Ordering::Relaxed)), (1, 1));
+
+        runtime.route(source, params, "routed", 0.25).unwrap();
+        tick(&mut runtime);
+        assert!(runtime.get_parameters(&params).iter().all(|values| values.routed == 0.75 && values.fixed == 0.5));
+        assert_eq!(routed_calls.load(Ordering::Relaxed), 1 + BUFFER_SIZE);
+        assert_eq!(fixed_calls.load(Ordering::Relaxed), 2);
+    }
+
+    #[test]
+    fn accessor_base_ignores_routed_modulation() {
+        let (mut runtime, (source, params)) = build::<(), _>(|builder| {
+            let handles = (builder.use_modulator::<Constant>(), builder.use_parameters::<LevelParams>());
+            (silent(), handles)
+        });
+        runtime.route(source, params, "level", 0.25).unwrap();
+        runtime.set_base(params, "level", 0.4).unwrap();
+        tick(&mut runtime);
+        let values = runtime.get_parameters(&params);
+        assert_eq!(values.base().level, 0.4);
+        assert!((values[0].level - 0.65).abs() < 1e-6);
+    }
+
+    // A ramp across the block, read every 32 samples
+    #[derive(Default)]
+    struct SlowRamp;
+
+    impl<E> Modulator<E> for SlowRamp {
+        fn update(&mut self, _sample_rate: f32, _event: Option<&E>) {}
+
+        fn get_value(&self, index: usize) -> f32 {
+            index as f32 / BUFFER_SIZE as f32
+        }
+
+        fn rate(&self) -> ModRate {
+            ModRate::ControlRate(32)
+        }
+    }
+
+    #[test]
+    fn control_rate_sources_step_at_their_interval() {
+        let (mut runtime, (ramp, params)) = build::<(), _>(|builder| {
+            let handles = (builder.use_modulator::<SlowRamp>(), builder.use_parameters::<LevelParams>());
+            (silent(), handles)
+        });
+        runtime.set_base(params, "level", 0.0).unwrap();
+        runtime.route(ramp, params, "level", 1.0).unwrap();
+        tick(&mut runtime);
+        let values = runtime.get_parameters(&params);
+        for i in 0..BUFFER_SIZE {
+            assert_eq!(values[i].level, (i / 32 * 32) as f32 / BUFFER_SIZE as f32, "sample {i}");
+        }
+    }
+
+    #[test]
+    fn headless_runtimes_compute_params_without_audio() {
+        let (_bus, mut builder) = new::<()>();
+        let source = builder.use_modulator::<Constant>();
+        let params = builder.use_parameters::<LevelParams>();
+        let mut runtime = builder.build_headless();
+        runtime.route(source, params, "level", 0.25).unwrap();
+
+        runtime.tick(48_000.0, None, &[], &mut []);
+        assert!(runtime.get_parameters(&params).iter().all(|values| values.level == 0.75));
+    }
+
+    #[test]
+    fn is_modulated_follows_routing_and_unrouting() {
+        let (mut runtime, (source, params)) = build::<(), _>(|builder| {
+            let handles = (builder.use_modulator::<Constant>(), builder.use_parameters::<LevelParams>());
+            (silent(), handles)
+        });
+        assert_eq!(runtime.is_modulated(params, "level"), Ok(false));
+        runtime.route(source, params, "level", 0.25).unwrap();
+        assert_eq!(runtime.is_modulated(params, "level"), Ok(true));
+        runtime.unroute(source, params, "level").unwrap();
+        assert_eq!(runtime.is_modulated(params, "level"), Ok(false));
+    }
+
+    #[test]
+    fn routing_never_grows_past_the_reserved_capacity() {
+        let routing = |source_index, amount| ModulationRouting { source_index, amount, mode: RoutingMode::Add };
+        let mut modulation = ParamModulation::new();
+        modulation.reserve(2);
+        let capacity = modulation.routings.capacity();
+
+        modulation.route(routing(0, 0.1));
+        modulation.route(routing(1, 0.2));
+        // Re-routing replaces in place and moves to the end
+        modulation.route(routing(0, 0.3));
+        assert_eq!(modulation.sources().collect::<Vec<_>>(), [1, 0]);
+        assert_eq!(modulation.routings[1].0.amount, 0.3);
+        // A source beyond the reservation is dropped
+        modulation.route(routing(2, 0.4));
+        assert_eq!(modulation.sources().collect::<Vec<_>>(), [1, 0]);
+        assert_eq!(modulation.routings.capacity(), capacity);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "256 of 256 output samples were not written")]
+    fn output_check_catches_a_component_that_writes_nothing() {
+        let (mut runtime, ()) = build::<(), _>(|builder| {
+            builder.debug_check_output();
+            (silent(), ())
+        });
+        tick(&mut runtime);
+    }
+
+    #[test]
+    fn output_check_passes_a_component_that_writes() {
+        let (mut runtime, ()) = build::<(), _>(|builder| {
+            builder.debug_check_output();
+            (feedback_loop(), ())
+        });
+        tick(&mut runtime);
+    }
+
+    #[parameters]
+    struct RangedParams {
+        #[param(default = 0.5, min = 0.2, max = 0.8)]
+        width: f32,
+        level: f32,
+    }
+
+    #[test]
+    fn param_range_reports_the_declared_bounds() {
+        assert_eq!(RangedParams::param_range("width"), Some((0.2, 0.8)));
+        assert_eq!(RangedParams::param_range("level"), Some((0.0, 1.0)));
+        assert_eq!(RangedParams::param_range("depth"), None);
+
+        // Modulation is clamped to the same bounds
+        let (mut runtime, (source, params)) = build::<(), _>(|builder| {
+            let handles = (builder.use_modulator::<Constant>(), builder.use_parameters::<RangedParams>());
+            (silent(), handles)
+        });
+        runtime.route(source, params, "width", 1.0).unwrap();
+        tick(&mut runtime);
+        assert_eq!(runtime.get_parameters(&params)[0].width, 0.8);
+    }
+
+    #[test]
+    fn parameter_instances_of_one_type_route_independently() {
+        let (mut runtime, (first_source, second_source, first, second)) = build::<(), _>(|builder| {
+            let first_source = builder.use_modulator::<Constant>();
+            let second_source = builder.use_modulator::<StepAt100>();
+            let first = builder.use_parameters_instance::<LevelParams>();
+            let second = builder.use_parameters_instance::<LevelParams>();
+            (silent(), (first_source, second_source, first, second))
+        });
+        assert_ne!(first.slot, second.slot);
+        runtime.route(first_source, first, "level", 0.25).unwrap();
+        runtime.route(second_source, second, "level", -0.25).unwrap();
+        runtime.set_base(second, "level", 0.6).unwrap();
+        tick(&mut runtime);
+
+        assert!(runtime.get_parameters(&first).iter().all(|values| values.level == 0.75));
+        let second = runtime.get_parameters(&second);
+        assert_eq!(second.at(0).level, 0.6);
+        assert!((second.at(BUFFER_SIZE - 1).level - 0.35).abs() < 1e-6);
+    }
+
+    // Holds the value of the last event it saw
+    #[derive(Default)]
+    struct Latch(f32);
+
+    impl Modulator<u8> for Latch {
+        fn update(&mut self, _sample_rate: f32, event: Option<&u8>) {
+            if let Some(&value) = event {
+                self.0 = value as f32;
+            }
+        }
+
+        fn get_value(&self, _index: usize) -> f32 {
+            self.0
+        }
+    }
+
+    #[test]
+    fn timeline_events_land_on_their_exact_frames() {
+        let (mut runtime, ()) = build::<u8, _>(|builder| {
+            builder.use_modulator::<Latch>();
+            (play_first_source(), ())
+        });
+        let output = runtime.render_timeline(48_000.0, &[(100, 1), (700, 2)], 1_000);
+        let expected: Vec<f32> = (0..1_000)
+            .map(|frame| match frame {
+                0..100 => 0.0,
+                100..700 => 1.0,
+                _ => 2.0,
+            })
+            .collect();
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn modulation_buffer_is_computed_minus_base() {
+        let (mut runtime, (step, params)) = build::<(), _>(|builder| {
+            let handles = (builder.use_modulator::<StepAt100>(), builder.use_parameters::<LevelParams>());
+            (silent(), handles)
+        });
+        runtime.route(step, params, "level", 0.25).unwrap();
+        tick(&mut runtime);
+        let values = runtime.get_parameters(&params);
+        let modulation = values.modulation("level").unwrap();
+        for (i, &delta) in modulation.iter().enumerate() {
+            assert_eq!(delta, values[i].level - values.base().level, "sample {i}");
+        }
+        assert_eq!((modulation[99], modulation[100]), (0.0, 0.25));
+        assert!(values.modulation("levle").is_none());
+    }
+
+    fn gain<E>(gain: f32) -> ComponentFn<E> {
+        Box::new(move |_runtime, input, output, _sample_rate| {
+            for (out, &sample) in output.iter_mut().zip(input) {
+                *out = sample * gain;
+            }
+        })
+    }
+
+    #[test]
+    fn chained_runtimes_apply_each_stage_in_turn() {
+        let (first, ()) = build::<(), _>(|_builder| (gain(2.0), ()));
+        let (second, ()) = build::<(), _>(|_builder| (gain(3.0), ()));
+        let mut chain = Chain::new(vec![first, second]);
+
+        let input: Vec<f32> = (0..300).map(|i| i as f32 * 0.001).collect();
+        let mut output = vec![0.0; input.len()];
+        chain.tick(48_000.0, None, &input, &mut output);
+        assert!(output.iter().zip(&input).all(|(&out, &sample)| (out - sample * 6.0).abs() < 1e-6));
+
+        // Stages stay reachable for routing between blocks
+        assert_eq!(chain.runtimes_mut().len(), 2);
+    }
+
+    #[test]
+    fn sources_enumerate_every_modulator_in_registration_order() {
+        let (mut runtime, lfo) = build::<(), _>(|builder| {
+            let lfo = builder.use_modulator::<crate::modulators::LfoBank<1>>();
+            builder.use_modulator::<crate::modulators::ToBipolar<Constant>>();
+            (silent(), lfo)
+        });
+        runtime.get_source_mut(&lfo).set_frequency(2.0);
+        let ranges: Vec<_> = runtime.sources().map(|source| source.range()).collect();
+        assert_eq!(ranges, [(0.0, 1.0), (-1.0, 1.0)]);
+    }
+
+    #[test]
+    fn saturating_parallel_sum_is_bounded_and_monotonic() {
+        let (mut runtime, ()) = build::<(), _>(|builder| (crate::parallel_sat!((1.0, double), (1.0, double))(builder), ()));
+        // Up to 4x full scale once summed
+        let input: Vec<f32> = (0..BUFFER_SIZE).map(|i| i as f32 / (BUFFER_SIZE - 1) as f32 * 2.0 - 1.0).collect();
+        let mut output = [0.0; BUFFER_SIZE];
+        runtime.tick(48_000.0, None, &input, &mut output);
+
+        assert!(output.iter().all(|sample| sample.abs() < 1.0));
+        assert!(output.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(output[BUFFER_SIZE - 1] > 0.99);
+    }
+
+    #[test]
+    fn dump_lists_each_param_with_its_value() {
+        let (mut runtime, (source, params)) = build::<(), _>(|builder| {
+            let handles = (builder.use_modulator::<Constant>(), builder.use_parameters::<PairParams>());
+            (silent(), handles)
+        });
+        runtime.route(source, params, "routed", 0.25).unwrap();
+        tick(&mut runtime);
+        assert_eq!(runtime.dump_params(&params), "routed = 0.7500\nfixed = 0.5000");
+    }
+
+    #[test]
+    fn source_depth_scales_every_routing_of_the_source() {
+        let (mut runtime, (source, pair, level)) = build::<(), _>(|builder| {
+            let source = builder.use_modulator::<Constant>();
+            let handles = (source, builder.use_parameters::<PairParams>(), builder.use_parameters::<LevelParams>());
+            (silent(), handles)
+        });
+        runtime.route(source, pair, "routed", 0.2).unwrap();
+        runtime.route(source, level, "level", -0.4).unwrap();
+        runtime.set_source_depth(source, 0.5);
+        tick(&mut runtime);
+        assert!((runtime.get_parameters(&pair)[0].routed - 0.6).abs() < 1e-6);
+        assert!((runtime.get_parameters(&level)[0].level - 0.3).abs() < 1e-6);
+    }
+
+    #[test]
+    fn components_start_a_tone_on_a_note_on_event() {
+        use crate::modulators::NoteEvent;
+
+        let (mut runtime, ()) = build::<[u8; 3], _>(|_builder| {
+            let mut phase: Option<f32> = None;
+            let component: ComponentFn<[u8; 3]> = Box::new(move |runtime, _input, output, sample_rate| {
+                if runtime.events().iter().any(|event| event.note_on().is_some()) {
+                    phase = Some(0.0);
+                }
+                for out in output.iter_mut() {
+                    *out = phase.map_or(0.0, |phase| (std::f32::consts::TAU * phase).sin());
+                    if let Some(phase) = &mut phase {
+                        *phase = (*phase + 440.0 / sample_rate).fract();
+                    }
+                }
+            });
+            (component, ())
+        });
+        let output = runtime.render_timeline(48_000.0, &[(300, [0x90, 69, 100])], 1_000);
+        assert!(output[..301].iter().all(|&sample| sample == 0.0));
+        assert!(output[301..].iter().any(|&sample| sample.abs() > 0.9));
+    }
+
+    #[test]
+    fn smoother_covers_63_percent_in_its_time() {
+        let mut smoother = ParamSmoother::new(10.0);
+        smoother.set_sample_rate(48_000.0);
+        smoother.next(0.0);
+        let value = (0..480).map(|_| smoother.next(1.0)).last().unwrap();
+        assert!((value - 0.632).abs() < 0.01, "{}", value);
+    }
+
+    #[test]
+    fn smoother_keeps_its_time_in_ms_across_sample_rates() {
+        let mut smoother = ParamSmoother::new(10.0);
+        smoother.set_sample_rate(48_000.0);
+        let at_48k = smoother.coefficient();
+        smoother.set_sample_rate(96_000.0);
+        assert!(smoother.coefficient() < at_48k);
+
+        smoother.next(0.0);
+        let value = (0..960).map(|_| smoother.next(1.0)).last().unwrap();
+        assert!((value - 0.632).abs() < 0.01, "{}", value);
+    }
+
+    #[test]
+    fn weighted_mix_matches_the_scalar_loop() {
+        // 37 samples: whole eight-lane chunks plus a remainder
+        let input: Vec<f32> = (0..37).map(|i| (i as f32 * 0.37).sin()).collect();
+        let mut mixed: Vec<f32> = (0..37).map(|i| i as f32 * 0.01).collect();
+        let mut expected = mixed.clone();
+        mix_weighted(&mut mixed, &input, 0.7);
+        mix_weighted_scalar(&mut expected, &input, 0.7);
+        assert_eq!(mixed, expected);
+    }
+
+    #[test]
+    fn typed_setters_set_the_base_within_range() {
+        let (mut runtime, ranged) = build::<(), _>(|builder| (silent(), builder.use_parameters::<RangedParams>()));
+        runtime.parameters_mut(&ranged).set_width(0.3);
+        tick(&mut runtime);
+        assert!((runtime.get_parameters(&ranged)[0].width - 0.3).abs() < 1e-6);
+
+        runtime.parameters_mut(&ranged).set_width(1.0);
+        tick(&mut runtime);
+        assert!((runtime.get_parameters(&ranged)[0].width - 0.8).abs() < 1e-6);
+    }
+
+    #[test]
+    fn frozen_modulators_hold_their_values_and_miss_events() {
+        let (mut runtime, latch) = build::<u8, _>(|builder| {
+            let latch = builder.use_modulator::<Latch>();
+            (play_first_source(), latch)
+        });
+        let mut output = [0.0; BUFFER_SIZE];
+        runtime.tick(48_000.0, Some(3), &SILENCE, &mut output);
+        runtime.set_modulation_frozen(true);
+        runtime.tick(48_000.0, Some(7), &SILENCE, &mut output);
+        assert_eq!(runtime.get_source_mut(&latch).0, 3.0);
+        assert!(output.iter().all(|&sample| sample == 3.0));
+
+        runtime.set_modulation_frozen(false);
+        runtime.tick(48_000.0, Some(7), &SILENCE, &mut output);
+        assert_eq!(runtime.get_source_mut(&latch).0, 7.0);
+    }
+
+    #[test]
+    fn sample_counter_includes_the_block_in_progress() {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let log = seen.clone();
+        let (mut runtime, ()) = build::<(), _>(|_builder| {
+            let component: ComponentFn<()> = Box::new(move |runtime, _input, output, _sample_rate| {
+                log.lock().unwrap().push(runtime.samples_processed());
+                output.fill(0.0);
+            });
+            (component, ())
+        });
+        tick(&mut runtime);
+        runtime.tick(48_000.0, None, &SILENCE[..100], &mut [0.0; 100]);
+        assert_eq!(*seen.lock().unwrap(), [BUFFER_SIZE as u64, BUFFER_SIZE as u64 + 100]);
+        assert_eq!(runtime.samples_processed(), BUFFER_SIZE as u64 + 100);
+
+        runtime.reset_samples_processed();
+        assert_eq!(runtime.samples_processed(), 0);
+    }
+
+    #[test]
+    fn parallel_from_feeds_each_branch_its_own_bus() {
+        let (mut runtime, ()) = build::<(), _>(|builder| {
+            let (full, short) = (builder.use_bus(), builder.use_bus());
+            let mut mix = crate::parallel_from!((1.0, full, double), (0.5, short, double))(builder);
+            let component: ComponentFn<()> = Box::new(move |runtime, input, output, sample_rate| {
+                runtime.get_mut(&full).write(&[1.0; BUFFER_SIZE]);
+                runtime.get_mut(&short).write(&[2.0; 100]);
+                mix(runtime, input, output, sample_rate);
+            });
+            (component, ())
+        });
+        let mut output = [0.0; BUFFER_SIZE];
+        runtime.tick(48_000.0, None, &SILENCE, &mut output);
+        // The shorter bus is padded with silence
+        assert!(output[..100].iter().all(|&sample| sample == 4.0));
+        assert!(output[100..].iter().all(|&sample| sample == 2.0));
+    }
+
+    #[test]
+    fn reordering_a_chain_swaps_its_stages() {
+        let offset: ComponentFn<()> = Box::new(|_runtime, input, output, _sample_rate| {
+            for (out, &sample) in output.iter_mut().zip(input) {
+                *out = sample + 1.0;
+            }
+        });
+        let (mut runtime, order) = build::<(), _>(serial_reorderable(vec![offset, gain(2.0)]));
+        let input = [1.0; BUFFER_SIZE];
+        let mut output = [0.0; BUFFER_SIZE];
+        runtime.tick(48_000.0, None, &input, &mut output);
+        assert_eq!(output[0], 4.0);
+
+        assert!(!runtime.get_mut(&order).set(&[1, 1]));
+        assert!(runtime.get_mut(&order).set(&[1, 0]));
+        runtime.tick(48_000.0, None, &input, &mut output);
+        assert_eq!(output[0], 3.0);
+    }
+
+    // Takes a millisecond over each block
+    #[cfg(feature = "profiling")]
+    #[derive(Default)]
+    struct Sluggish;
+
+    #[cfg(feature = "profiling")]
+    impl<E> Modulator<E> for Sluggish {
+        fn update(&mut self, _sample_rate: f32, _event: Option<&E>) {}
+
+        fn update_block(&mut self, _sample_rate: f32, _event: Option<&E>, _len: usize) {
+            std::thread::sleep(std::time::Duration::from_millis(1));
+        }
+
+        fn get_value(&self, _index: usize) -> f32 {
+            0.0
+        }
+    }
+
+    #[cfg(feature = "profiling")]
+    #[test]
+    fn source_timings_single_out_the_slow_source() {
+        let (mut runtime, ()) = build::<(), _>(|builder| {
+            builder.use_modulator::<Constant>();
+            builder.use_modulator::<Sluggish>();
+            (silent(), ())
+        });
+        for _ in 0..4 {
+            tick(&mut runtime);
+        }
+        let timings = runtime.source_timings();
+        assert!(timings[1] >= std::time::Duration::from_millis(4));
+        assert!(timings[0] < timings[1]);
+
+        runtime.reset_source_timings();
+        assert!(runtime.source_timings().iter().all(|time| time.is_zero()));
+    }
+
+    #[test]
+    fn disabled_sources_drop_out_and_come_back_at_their_depth() {
+        let (mut runtime, (source, params)) = build::<(), _>(|builder| {
+            let handles = (builder.use_modulator::<Constant>(), builder.use_parameters::<LevelParams>());
+            (silent(), handles)
+        });
+        runtime.route(source, params, "level", 0.25).unwrap();
+        runtime.set_source_depth(source, 0.5);
+        runtime.set_source_enabled(source, false);
+        tick(&mut runtime);
+        assert_eq!(runtime.get_parameters(&params)[0].level, 0.5);
+
+        runtime.set_source_enabled(source, true);
+        tick(&mut runtime);
+        assert_eq!(runtime.get_parameters(&params)[0].level, 0.625);
+    }
+
+    #[test]
+    fn tick_hooks_run_once_per_block_in_order() {
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let (first, second) = (calls.clone(), calls.clone());
+        let (mut runtime, ()) = build::<(), _>(|builder| {
+            builder.on_tick(Box::new(move |_runtime, output| first.lock().unwrap().push(("first", output.len()))));
+            builder.on_tick(Box::new(move |_runtime, output| second.lock().unwrap().push(("second", output.len()))));
+            (silent(), ())
+        });
+        tick(&mut runtime);
+        tick(&mut runtime);
+        let block = BUFFER_SIZE;
+        assert_eq!(*calls.lock().unwrap(), [("first", block), ("second", block), ("first", block), ("second", block)]);
+
+        // Several events ahead of one block still make one block
+        calls.lock().unwrap().clear();
+        runtime.process_block(48_000.0, &[(), (), ()], &SILENCE[..64], &mut [0.0; 64]);
+        runtime.process_block(48_000.0, &[(), ()], &[], &mut []);
+        assert_eq!(*calls.lock().unwrap(), [("first", 64), ("second", 64)]);
+    }
+
+    #[parameters]
+    struct EvenMix {
+        #[param(default = 0.5)]
+        mix: f32,
+    }
+
+    // Delays its input by ten samples and says so
+    fn late<E: Send + 'static>(builder: &mut Builder<E>) -> ComponentFn<E> {
+        builder.report_latency(10);
+        let mut line = [0.0; 10];
+        let mut head = 0;
+        Box::new(move |_runtime, input, output, _sample_rate| {
+            for (out, &sample) in output.iter_mut().zip(input) {
+                *out = std::mem::replace(&mut line[head], sample);
+                head = (head + 1) % line.len();
+            }
+        })
+    }
+
+    #[test]
+    fn wet_dry_delays_the_dry_path_to_match_the_wet_latency() {
+        let (mut runtime, ()) = build::<(), _>(|builder| (crate::wet_dry!(EvenMix, late)(builder), ()));
+        assert_eq!(runtime.latency(), 10);
+        let mut input = [0.0; BUFFER_SIZE];
+        input[0] = 1.0;
+        let mut output = [0.0; BUFFER_SIZE];
+        runtime.tick(48_000.0, None, &input, &mut output);
+        // Both halves of the impulse arrive together
+        assert_eq!(output[10], 1.0);
+        assert!(output.iter().enumerate().all(|(i, &sample)| i == 10 || sample == 0.0));
+    }
+
+    // Emits a note-on when the input jumps past 0.5, like an onset detector
+    fn onsets(_builder: &mut Builder<[u8; 3]>) -> ComponentFn<[u8; 3]> {
+        let mut previous = 0.0f32;
+        Box::new(move |runtime, input, output, _sample_rate| {
+            for (out, &sample) in output.iter_mut().zip(input) {
+                if sample - previous > 0.5 {
+                    runtime.emit([0x90, 60, 100]);
+                }
+                previous = sample;
+                *out = sample;
+            }
+        })
+    }
+
+    #[test]
+    fn emitted_events_trigger_an_envelope_on_the_next_block() {
+        let (mut runtime, envelope) = build::<[u8; 3], _>(|builder| {
+            let envelope = builder.use_modulator::<crate::modulators::AD>();
+            (onsets(builder), envelope)
+        });
+        let mut output = [0.0; BUFFER_SIZE];
+        runtime.tick(48_000.0, None, &SILENCE, &mut output);
+        let mut hit = [0.0; BUFFER_SIZE];
+        hit[100] = 1.0;
+        runtime.tick(48_000.0, None, &hit, &mut output);
+        assert!(!Modulator::<[u8; 3]>::is_active(runtime.get_source_mut(&envelope)));
+
+        runtime.tick(48_000.0, None, &SILENCE, &mut output);
+        assert!(Modulator::<[u8; 3]>::is_active(runtime.get_source_mut(&envelope)));
+    }
+
+    #[test]
+    fn emits_beyond_the_capacity_are_dropped_and_counted() {
+        let (mut runtime, ()) = build::<u8, _>(|_builder| {
+            let component: ComponentFn<u8> = Box::new(|runtime, _input, output, _sample_rate| {
+                if !output.is_empty() {
+                    for _ in 0..EMIT_CAPACITY + 3 {
+                        runtime.emit(1);
+                    }
+                }
+            });
+            (component, ())
+        });
+        tick(&mut runtime);
+        assert_eq!(runtime.emit_overflows(), 3);
+        // Every queued event goes out ahead of the next block, leaving room again
+        tick(&mut runtime);
+        assert_eq!(runtime.emit_overflows(), 6);
+    }
+
+    #[derive(Clone, Default)]
+    struct Patch(Vec<f32>);
+
+    #[derive(Default)]
+    struct Scratch(f32);
+
+    #[test]
+    fn restoring_a_snapshot_brings_back_cloneable_state_only() {
+        let (mut runtime, (patch, scratch)) = build::<(), _>(|builder| {
+            (silent(), (builder.use_cloneable_state::<Patch>(), builder.use_state::<Scratch>()))
+        });
+        runtime.get_mut(&patch).0.push(0.25);
+        let snapshot = runtime.snapshot_state();
+
+        runtime.get_mut(&patch).0.push(0.5);
+        runtime.get_mut(&scratch).0 = 1.0;
+        runtime.restore_state(&snapshot);
+        assert_eq!(runtime.get(&patch).0, [0.25]);
+        assert_eq!(runtime.get(&scratch).0, 1.0);
+
+        // The snapshot survives being restored
+        runtime.get_mut(&patch).0.clear();
+        runtime.restore_state(&snapshot);
+        assert_eq!(runtime.get(&patch).0, [0.25]);
+    }
+
+    // Plays the level parameter as the signal
+    fn play_level(builder: &mut Builder<()>) -> (ComponentFn<()>, ParameterHandle<LevelParams>) {
+        let params = builder.use_parameters::<LevelParams>();
+        let component: ComponentFn<()> = Box::new(move |runtime, _input, output, _sample_rate| {
+            let values = runtime.get_parameters(&params);
+            for (i, out) in output.iter_mut().enumerate() {
+                *out = values[i].level;
+            }
+        });
+        (component, params)
+    }
+
+    #[test]
+    fn linear_automation_ramps_a_frame_at_a_time() {
+        let (mut runtime, params) = build(play_level);
+        // Given out of order; the render sorts each parameter's points
+        let automation = [
+            Automation::new(10, params, "level", 1.0).with_curve(AutomationCurve::Linear),
+            Automation::new(0, params, "level", 0.0),
+        ];
+        let output = runtime.render_automated(48_000.0, &[], &automation, 20);
+        for (frame, &value) in output[..=10].iter().enumerate() {
+            assert!((value - frame as f32 * 0.1).abs() < 1e-6, "frame {}: {}", frame, value);
+        }
+        assert!(output[10..].iter().all(|&value| value == 1.0));
+    }
+
+    #[test]
+    fn activity_jumps_with_modulation_and_eases_back_after() {
+        let (mut runtime, (source, params)) = build::<(), _>(|builder| {
+            let handles = (builder.use_modulator::<Constant>(), builder.use_parameters::<LevelParams>());
+            (silent(), handles)
+        });
+        runtime.route(source, params, "level", 0.25).unwrap();
+        tick(&mut runtime);
+        assert!((runtime.get_parameters(&params).activity("level").unwrap() - 0.25).abs() < 1e-6);
+        assert_eq!(runtime.get_parameters(&params).activity("nope"), None);
+
+        runtime.unroute(source, params, "level").unwrap();
+        tick(&mut runtime);
+        let easing = runtime.get_parameters(&params).activity("level").unwrap();
+        assert!(easing > 0.1 && easing < 0.25, "{}", easing);
+        // 100 blocks are about 530 ms at 48 kHz; it only eases on blocks that read it
+        for _ in 0..100 {
+            tick(&mut runtime);
+            runtime.get_parameters(&params);
+        }
+        assert!(runtime.get_parameters(&params).activity("level").unwrap() < 0.01);
+    }
+
+    #[test]
+    fn state_with_an_initializer_is_sized_at_build_and_shared_per_type() {
+        let len = 480;
+        let (runtime, (first, second)) = build::<(), _>(|builder| {
+            let first = builder.use_state_with(move || vec![0.0f32; len]);
+            let second = builder.use_state_with(|| vec![1.0f32; 4]);
+            (silent(), (first, second))
+        });
+        assert_eq!(first.slot, second.slot);
+        assert_eq!(*runtime.get(&first), vec![0.0; 480]);
+    }
+
+    #[test]
+    fn bypass_delays_the_input_by_the_reported_latency() {
+        // `late`, doubled so the bypassed signal can be told apart
+        let (mut runtime, ()) = build::<(), _>(|builder| {
+            let mut delayed = late(builder);
+            let component: ComponentFn<()> = Box::new(move |runtime, input, output, sample_rate| {
+                delayed(runtime, input, output, sample_rate);
+                output.iter_mut().for_each(|sample| *sample *= 2.0);
+            });
+            (component, ())
+        });
+        let mut input = [0.0; BUFFER_SIZE];
+        input[0] = 1.0;
+        let mut output = [0.0; BUFFER_SIZE];
+        runtime.tick(48_000.0, None, &input, &mut output);
+        assert_eq!(output[10], 2.0);
+
+        // Crossfades over one block, then passes the input at the same delay
+        runtime.set_bypass(true);
+        runtime.tick(48_000.0, None, &input, &mut output);
+        assert!(output[10] > 1.0 && output[10] < 2.0);
+        runtime.tick(48_000.0, None, &input, &mut output);
+        assert!(output.iter().enumerate().all(|(i, &sample)| sample == if i == 10 { 1.0 } else { 0.0 }));
+    }
+
+    #[test]
+    fn accessor_iter_yields_each_sample_in_order() {
+        let (mut runtime, (step, params)) = build::<(), _>(|builder| {
+            let handles = (builder.use_modulator::<StepAt100>(), builder.use_parameters::<LevelParams>());
+            (silent(), handles)
+        });
+        runtime.route(step, params, "level", 0.25).unwrap();
+        tick(&mut runtime);
+        let values = runtime.get_parameters(&params);
+        let levels: Vec<f32> = values.iter().map(|values| values.level).collect();
+        assert_eq!(levels.len(), BUFFER_SIZE);
+        assert!(levels.iter().enumerate().all(|(i, &level)| level == values[i].level));
+        assert_eq!((levels[99], levels[100]), (0.5, 0.75));
+    }
+
+    #[test]
+    fn serial_blend_mixes_each_stage_with_its_input() {
+        let input: Vec<f32> = (0..BUFFER_SIZE).map(|i| i as f32 / BUFFER_SIZE as f32).collect();
+        let run = |(mut runtime, ()): (Runtime<()>, ())| {
+            let mut output = [0.0; BUFFER_SIZE];
+            runtime.tick(48_000.0, None, &input, &mut output);
+            output
+        };
+        // Half of a doubling is 1.5x; then a full doubling
+        let half = run(build(|builder| (crate::serial_blend!((0.5, double))(builder), ())));
+        let chained = run(build(|builder| (crate::serial_blend!((0.5, double), (1.0, double))(builder), ())));
+        assert!(half.iter().zip(&input).all(|(&out, &dry)| out == dry * 1.5));
+        assert!(chained.iter().zip(&input).all(|(&out, &dry)| out == dry * 3.0));
+    }
+
+    #[test]
+    fn a_declared_feed_back_to_the_source_is_a_cycle() {
+        let (mut runtime, (lfo, step, lfo_rate, step_rate)) = build::<(), _>(|builder| {
+            let lfo = builder.use_modulator::<Constant>();
+            let step = builder.use_modulator::<StepAt100>();
+            let lfo_rate = builder.use_parameters_instance::<LevelParams>();
+            let step_rate = builder.use_parameters_instance::<LevelParams>();
+            builder.declare_feed(lfo_rate, lfo);
+            builder.declare_feed(step_rate, step);
+            (silent(), (lfo, step, lfo_rate, step_rate))
+        });
+        assert!(!runtime.has_cycle());
+
+        // A chain: the LFO drives the step's rate
+        runtime.route(lfo, step_rate, "level", 0.5).unwrap();
+        assert!(!runtime.has_cycle());
+
+        // Closing the loop: the step drives the LFO's rate
+        runtime.route(step, lfo_rate, "level", 0.5).unwrap();
+        assert!(runtime.has_cycle());
+    }
+
+    #[test]
+    fn a_source_feeding_its_own_parameters_is_a_cycle() {
+        let (mut runtime, (lfo, rate)) = build::<(), _>(|builder| {
+            let handles = (builder.use_modulator::<Constant>(), builder.use_parameters::<LevelParams>());
+            builder.declare_feed(handles.1, handles.0);
+            (silent(), handles)
+        });
+        runtime.route(lfo, rate, "level", 0.5).unwrap();
+        assert!(runtime.has_cycle());
+        assert_eq!(runtime.routing_graph(), vec![(lfo.slot, rate.slot, "level".to_string())]);
+    }
+
+    #[parameters]
+    struct CutoffParams {
+        #[param(default = 0.3)]
+        cutoff: f32,
+    }
+
+    fn named_filter() -> (Runtime<()>, ParameterHandle<CutoffParams>) {
+        build::<(), _>(|builder| {
+            let lfo = builder.use_modulator::<Constant>();
+            let params = builder.use_parameters::<CutoffParams>();
+            builder.name_modulator(lfo, "lfo");
+            builder.name_parameters(params, "filter");
+            (silent(), params)
+        })
+    }
+
+    fn cutoff_base(value: f32) -> BaseEntry {
+        BaseEntry { target: "filter".to_string(), param: "cutoff".to_string(), value }
+    }
+
+    #[test]
+    fn config_bases_override_the_struct_defaults() {
+        let (mut runtime, params) = named_filter();
+        runtime.apply_config(&RoutingConfig { bases: vec![cutoff_base(0.6)], routes: Vec::new() }).unwrap();
+        tick(&mut runtime);
+        assert!(runtime.get_parameters(&params).iter().all(|values| values.cutoff == 0.6));
+
+        // Routings are applied on top of the new base
+        let lfo = RouteEntry { source: "lfo".to_string(), target: "filter".to_string(), param: "cutoff".to_string(), amount: 0.25, mode: RoutingMode::Add };
+        runtime.apply_config(&RoutingConfig { bases: Vec::new(), routes: vec![lfo] }).unwrap();
+        tick(&mut runtime);
+        assert!(runtime.get_parameters(&params).iter().all(|values| (values.cutoff - 0.85).abs() < 1e-6));
+    }
+
+    #[test]
+    fn a_config_with_an_unknown_name_applies_nothing() {
+        let (mut runtime, params) = named_filter();
+        let route = |source: &str, target: &str| RouteEntry {
+            source: source.to_string(), target: target.to_string(), param: "cutoff".to_string(), amount: 0.25, mode: RoutingMode::Add,
+        };
+
+        let config = RoutingConfig { bases: vec![cutoff_base(0.6)], routes: vec![route("envelope", "filter")] };
+        assert_eq!(runtime.apply_config(&config), Err(RouteError::UnknownSource("envelope".to_string())));
+        let config = RoutingConfig { bases: vec![cutoff_base(0.6)], routes: vec![route("lfo", "amp")] };
+        assert_eq!(runtime.apply_config(&config), Err(RouteError::UnknownTarget("amp".to_string())));
+        let misspelled = BaseEntry { param: "cutof".to_string(), ..cutoff_base(0.6) };
+        let config = RoutingConfig { bases: vec![cutoff_base(0.6), misspelled], routes: Vec::new() };
+        assert_eq!(runtime.apply_config(&config), Err(RouteError::UnknownParam("cutof".to_string())));
+
+        tick(&mut runtime);
+        assert!(runtime.get_parameters(&params).iter().all(|values| values.cutoff == 0.3));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn a_json_preset_sets_bases_and_routes() {
+        let (mut runtime, params) = named_filter();
+        let config: RoutingConfig = serde_json::from_str(r#"{
+            "bases": [{ "target": "filter", "param": "cutoff", "value": 0.6 }],
+            "routes": [{ "source": "lfo", "target": "filter", "param": "cutoff", "amount": 0.1 }]
+        }"#).unwrap();
+        runtime.apply_config(&config).unwrap();
+        tick(&mut runtime);
+        assert!(runtime.get_parameters(&params).iter().all(|values| (values.cutoff - 0.7).abs() < 1e-6));
+    }
+
+    // Parameters with no fields that count their runtimes' updates, to see when
+    // the runtime computes them
+    static COUNTED_UPDATES: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+
+    #[derive(Default)]
+    struct Counted;
+
+    struct CountedRuntime;
+
+    impl<E> ParameterRuntime<E> for CountedRuntime {
+        fn update(&mut self, _sample_rate: f32, _sources: &[Box<dyn Modulator<E>>], _len: usize) {
+            COUNTED_UPDATES.fetch_add(1, Ordering::Relaxed);
+        }
+        fn route_parameter(&mut self, _param_name: &str, _routing: ModulationRouting) -> bool { false }
+        fn set_param_map(&mut self, _param_name: &str, _map: ParamMap) -> bool { false }
+        fn set_base(&mut self, _param_name: &str, _value: f32) -> bool { false }
+        fn unroute_parameter(&mut self, _param_name: &str, _source_index: usize) -> bool { false }
+        fn is_modulated(&self, _param_name: &str) -> Option<bool> { None }
+        fn value(&self, _param_name: &str) -> Option<f32> { None }
+        fn set_source_depth(&mut self, _source_index: usize, _depth: f32) {}
+        fn routings(&self) -> Vec<(String, usize)> { Vec::new() }
+        fn reserve_routings(&mut self, _sources: usize) {}
+    }
+
+    impl Parameters for Counted {
+        type Runtime<E: Send + 'static> = CountedRuntime;
+        type Accessor<'a, E> = &'a CountedRuntime where E: 'a;
+        type Values = ();
+
+        fn create_runtime<E: Send + 'static>() -> CountedRuntime {
+            CountedRuntime
+        }
+
+        fn create_accessor<E: Send + 'static>(runtime: &CountedRuntime) -> &CountedRuntime {
+            runtime
+        }
+
+        fn param_names() -> Vec<String> {
+            Vec::new()
+        }
+
+        fn param_range(_param_name: &str) -> Option<(f32, f32)> {
+            None
+        }
+    }
+
+    #[test]
+    fn parameters_update_once_per_block_and_only_when_read() {
+        let (mut runtime, (read, unread)) = build::<(), _>(|builder| {
+            let read = builder.use_parameters_instance::<Counted>();
+            let unread = builder.use_parameters_instance::<Counted>();
+            // Read twice a block; the second read is served from the first
+            let component: ComponentFn<()> = Box::new(move |runtime, _input, _output, _sample_rate| {
+                runtime.get_parameters(&read);
+                runtime.get_parameters(&read);
+            });
+            (component, (read, unread))
+        });
+        for _ in 0..5 {
+            tick(&mut runtime);
+        }
+        assert_eq!(COUNTED_UPDATES.load(Ordering::Relaxed), 5);
+
+        // A change between reads makes the next read recompute
+        runtime.get_parameters(&read);
+        runtime.parameters_mut(&read);
+        runtime.get_parameters(&read);
+        runtime.parameters_mut(&unread);
+        assert_eq!(COUNTED_UPDATES.load(Ordering::Relaxed), 6);
+    }
+
+    #[test]
+    fn parameter_buffers_count_one_block_per_registered_runtime() {
+        let (runtime, _) = build::<(), _>(|builder| {
+            builder.use_parameters::<LevelParams>();
+            // The same type again shares the first registration's runtime
+            builder.use_parameters::<LevelParams>();
+            builder.use_parameters::<PairParams>();
+            builder.use_parameters_instance::<PairParams>();
+            (silent(), ())
+        });
+        let report = runtime.memory_footprint();
+        let level = std::mem::size_of::<LevelParams>();
+        let pair = std::mem::size_of::<PairParams>();
+        assert_eq!((level, pair), (4, 8));
+        assert_eq!(report.parameter_buffers, BUFFER_SIZE * (level + 2 * pair));
+        assert!(report.parameter_runtimes > 0);
+    }
+
+    #[parameters]
+    struct SmoothedParams {
+        #[param(default = 0.0, smooth_ms = 10.0)]
+        level: f32,
+    }
+
+    fn play_smoothed(builder: &mut Builder<()>) -> (ComponentFn<()>, ParameterHandle<SmoothedParams>) {
+        let params = builder.use_parameters::<SmoothedParams>();
+        let component: ComponentFn<()> = Box::new(move |runtime, _input, output, _sample_rate| {
+            for (out, values) in output.iter_mut().zip(runtime.get_parameters(&params).iter()) {
+                *out = values.level;
+            }
+        });
+        (component, params)
+    }
+
+    // The smoothed level after a 0 to 1 step, rendered in ticks of the given lengths
+    fn smoothed_step(ticks: &[usize]) -> Vec<f32> {
+        let (mut runtime, params) = build::<(), _>(play_smoothed);
+        tick(&mut runtime);
+        runtime.set_base(params, "level", 1.0).unwrap();
+        let mut output = vec![0.0; ticks.iter().sum()];
+        let mut start = 0;
+        for &len in ticks {
+            runtime.tick(48_000.0, None, &SILENCE[..len.min(BUFFER_SIZE)], &mut output[start..start + len]);
+            start += len;
+        }
+        output
+    }
+
+    #[test]
+    fn smoothing_is_the_same_however_the_span_is_split() {
+        let whole = smoothed_step(&[300]);
+        assert_eq!(smoothed_step(&[256, 44]), whole);
+        assert_eq!(smoothed_step(&[100, 100, 100]), whole);
+        assert_eq!(smoothed_step(&[1; 300]), whole);
+    }
+
+    #[test]
+    fn smooth_ms_holds_through_short_blocks() {
+        // 10 ms at 48 kHz is 480 samples, covering 63% of a step
+        for len in [16, 44, 100] {
+            let output = smoothed_step(&vec![len; 480 / len + 1]);
+            assert!((output[479] - 0.632).abs() < 0.01, "{}-sample blocks: {}", len, output[479]);
+        }
+    }
+
+    #[test]
+    fn smoothing_follows_a_linear_automation_ramp_sample_by_sample() {
+        let (mut runtime, params) = build(play_smoothed);
+        let automation = [
+            Automation::new(0, params, "level", 0.0),
+            Automation::new(480, params, "level", 1.0).with_curve(AutomationCurve::Linear),
+        ];
+        let output = runtime.render_automated(48_000.0, &[], &automation, 960);
+
+        // One smoothing step per frame toward that frame's point on the ramp
+        let coefficient = 1.0 - (-1.0f32 / 480.0).exp();
+        let mut expected = 0.0;
+        for (frame, &value) in output.iter().enumerate() {
+            let target = (frame as f32 / 480.0).min(1.0);
+            expected += (target - expected) * coefficient;
+            assert!((value - expected).abs() < 1e-4, "frame {}: {} vs {}", frame, value, expected);
+        }
+        // Lagging the ramp by about the smoothing time
+        assert!(output[480] < 0.5 && output[959] > 0.6);
+    }
+
+    // Moves a whole block on per update, like a source that only implements `update`
+    #[derive(Default)]
+    struct BlockClock(usize);
+
+    impl Modulator<u8> for BlockClock {
+        fn update(&mut self, _sample_rate: f32, _event: Option<&u8>) {
+            self.0 += BUFFER_SIZE;
+        }
+
+        fn get_value(&self, _index: usize) -> f32 {
+            0.0
+        }
+    }
+
+    #[test]
+    fn emitted_events_reach_components_without_moving_block_sources_on() {
+        let (mut runtime, clock) = build::<u8, _>(|builder| {
+            let clock = builder.use_modulator::<BlockClock>();
+            let mut emitted = false;
+            // Emits once, and outputs how many events each block carries
+            let component: ComponentFn<u8> = Box::new(move |runtime, _input, output, _sample_rate| {
+                output.fill(runtime.events().len() as f32);
+                if !std::mem::replace(&mut emitted, true) {
+                    runtime.emit(7);
+                }
+            });
+            (component, clock)
+        });
+        let mut output = [0.0; BUFFER_SIZE];
+        let mut counts = Vec::new();
+        for _ in 0..3 {
+            runtime.tick(48_000.0, None, &SILENCE, &mut output);
+            counts.push(output[0]);
+        }
+        assert_eq!(counts, [0.0, 1.0, 0.0]);
+        assert_eq!(runtime.get_source_mut(&clock).0, 3 * BUFFER_SIZE);
+    }
+}
@@ -1,56 +1,735 @@
 // build_synth.rs is the entrypoint for the audio engine
-// the AudioEngine object owns and manages the audio engine thread, and contains all the cpal logic.
+// the AudioEngine object owns and manages the audio engine thread. Device access
+// goes through an `AudioBackend`, cpal's by default.
+use crate::backend::{AudioBackend, AudioStream, CpalBackend};
 use crate::core::*;
-use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
-use crossbeam::channel::Sender;
+use crossbeam::channel::{unbounded, Sender};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicUsize, Ordering};
 
-pub struct Engine<E: Clone + Copy + Send + 'static> {
+pub struct Engine<E: Clone + Send + 'static> {
     pub tx: Sender<E>,
-    stream: cpal::platform::Stream,
+    /// Parameter changes applied at the start of the next callback, before any
+    /// events or processing
+    pub control: Sender<ControlCommand>,
+    stream: Box<dyn AudioStream>,
+    running: AtomicBool,
+    level: Arc<LevelMeter>,
+    // One latch per device channel, set when a sample sent to it reaches full scale
+    clips: Arc<[AtomicBool]>,
+    // Fade-in length in frames, read by the callback when the stream starts
+    fade_in: Arc<AtomicUsize>,
+    // Linear output ceiling as f32 bits; infinity when off
+    ceiling: Arc<AtomicU32>,
+    // Whether the runtime only ever sees full `BUFFER_SIZE` blocks
+    fixed_blocks: Arc<AtomicBool>,
+    // Whether every channel of each device frame gets the sum of all of them
+    mono_sum: Arc<AtomicBool>,
+    // Stream errors reported since the last reset
+    errors: Arc<AtomicUsize>,
+    sample_rate: f32,
+    channels: usize,
+}
+
+/// Level of the most recent device buffer, as linear amplitude.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct OutputLevel {
+    pub peak: f32,
+    pub rms: f32,
+}
+
+// Written by the audio callback, read by the host; f32 bits in atomics so neither side locks
+#[derive(Default)]
+struct LevelMeter {
+    peak: AtomicU32,
+    rms: AtomicU32,
+}
+
+impl LevelMeter {
+    fn measure(&self, buffer: &[f32]) {
+        let (peak, sum_squares) = buffer.iter().fold((0.0f32, 0.0f32), |(peak, sum), &sample| {
+            (peak.max(sample.abs()), sum + sample * sample)
+        });
+        let rms = (sum_squares / buffer.len().max(1) as f32).sqrt();
+        self.peak.store(peak.to_bits(), Ordering::Relaxed);
+        self.rms.store(rms.to_bits(), Ordering::Relaxed);
+    }
 }
 
 impl<E> Engine<E> 
 where 
-    E: Clone + Copy + Send + 'static,
+    E: Clone + Send + 'static,
 {
     pub fn new<F>(f: F) -> Self 
     where
         F: for<'a> FnOnce(Builder<E>) -> Runtime<E>,
+    {
+        Self::with_backend(CpalBackend::default_output(), f)
+    }
+
+    pub fn with_backend<B, F>(backend: B, f: F) -> Self
+    where
+        B: AudioBackend,
+        F: FnOnce(Builder<E>) -> Runtime<E>,
     {
         let (event_bus, builder) = new::<E>();
         let EventBus{tx, rx} = event_bus;
-        
-        // cpal setup
-        let host = cpal::default_host();
-        let device = host.default_output_device()
-            .ok_or("no output device available").unwrap();
-        let config = device.default_output_config().unwrap();
-        let sample_rate = config.sample_rate().0 as f32;
+        let (control, control_rx) = unbounded::<ControlCommand>();
+
+        let sample_rate = backend.sample_rate();
+        let channels = backend.channels().max(1);
         let mut runtime = f(builder);
+        runtime.set_channels(channels);
+        let level = Arc::new(LevelMeter::default());
+        let meter = level.clone();
+        let clips: Arc<[AtomicBool]> = (0..channels).map(|_| AtomicBool::new(false)).collect();
+        let clip_latches = clips.clone();
+        let fade_in = Arc::new(AtomicUsize::new(0));
+        let fade_len = fade_in.clone();
+        let mut faded = 0usize;
+        let ceiling = Arc::new(AtomicU32::new(f32::INFINITY.to_bits()));
+        let output_ceiling = ceiling.clone();
+        let errors = Arc::new(AtomicUsize::new(0));
+        let error_count = errors.clone();
+        let fixed_blocks = Arc::new(AtomicBool::new(false));
+        let use_fixed_blocks = fixed_blocks.clone();
+        let mono_sum = Arc::new(AtomicBool::new(false));
+        let use_mono_sum = mono_sum.clone();
+        // Rendered ahead in fixed mode: the samples of `block` from `block_read` on
+        // haven't been handed to the device yet
+        let mut block = [0.0; BUFFER_SIZE];
+        let mut block_read = BUFFER_SIZE;
 
         Engine {
             tx,
-            stream: device.build_output_stream(
-                &config.into(),
-                move |data: &mut [f32], _: &cpal::OutputCallbackInfo| {
+            control,
+            level,
+            clips,
+            fade_in,
+            ceiling,
+            fixed_blocks,
+            mono_sum,
+            errors,
+            sample_rate,
+            channels,
+            running: AtomicBool::new(false),
+            stream: backend.build_stream(Box::new(move |data: &mut [f32]| {
+                // A command naming a parameter the target doesn't have is dropped
+                while let Ok(command) = control_rx.try_recv() {
+                    let _ = runtime.apply(command);
+                }
 
-                    let input = vec![0.0; data.len()];
-
-                    for (input_chunk, output_chunk) in input.chunks(256).zip(data.chunks_mut(256)) {
-                        if let Ok(event) = rx.try_recv() {
-                        runtime.tick(sample_rate, Some(event), &input_chunk, output_chunk);
-                        } else {
-                            runtime.tick(sample_rate, None, &input_chunk, output_chunk)
+                // The device gives no input, so every chunk reads the shared silent block.
+                // The engine holds a sender, so the channel never disconnects and an
+                // empty queue just means no event
+                if use_fixed_blocks.load(Ordering::Relaxed) {
+                    let mut written = 0;
+                    while written < data.len() {
+                        if block_read == BUFFER_SIZE {
+                            runtime.tick(sample_rate, rx.try_recv().ok(), &SILENCE, &mut block);
+                            block_read = 0;
                         }
+                        let len = (BUFFER_SIZE - block_read).min(data.len() - written);
+                        data[written..written + len].copy_from_slice(&block[block_read..block_read + len]);
+                        block_read += len;
+                        written += len;
+                    }
+                } else {
+                    for output_chunk in data.chunks_mut(BUFFER_SIZE) {
+                        let input_chunk = &SILENCE[..output_chunk.len()];
+                        runtime.tick(sample_rate, rx.try_recv().ok(), input_chunk, output_chunk);
+                    }
+                }
+
+                if channels > 1 && use_mono_sum.load(Ordering::Relaxed) {
+                    for frame in data.chunks_mut(channels) {
+                        let sum: f32 = frame.iter().sum();
+                        frame.fill(sum);
+                    }
+                }
+
+                // One gain per frame, so every channel fades together
+                let fade_len = fade_len.load(Ordering::Relaxed);
+                for frame in data.chunks_mut(channels).take(fade_len.saturating_sub(faded)) {
+                    let gain = faded as f32 / fade_len as f32;
+                    frame.iter_mut().for_each(|sample| *sample *= gain);
+                    faded += 1;
+                }
+                let ceiling = f32::from_bits(output_ceiling.load(Ordering::Relaxed));
+                if ceiling.is_finite() {
+                    for sample in data.iter_mut() {
+                        *sample = sample.clamp(-ceiling, ceiling);
                     }
-                },
-                |err| eprintln!("Audio stream error: {}", err),
-                None,
-            ).unwrap(),
+                }
+                meter.measure(data);
+                for (i, sample) in data.iter().enumerate() {
+                    if sample.abs() >= 1.0 {
+                        clip_latches[i % channels].store(true, Ordering::Relaxed);
+                    }
+                }
+            }), Box::new(move |err: &str| {
+                error_count.fetch_add(1, Ordering::Relaxed);
+                eprintln!("Audio stream error: {}", err);
+            })),
         }
     }
 
+    /// Another handle for sending events, e.g. one for a MIDI-in thread and one for
+    /// the GUI. Senders are independent: each one's events arrive in the order it
+    /// sent them, interleaved with other senders' in no particular order. The
+    /// engine holds the receiving end, so it keeps ticking, and draining events
+    /// already queued, after every outside sender is dropped.
+    ///
+    /// ```
+    /// use ceres::{Engine, MockBackend};
+    /// use std::thread;
+    ///
+    /// let device = MockBackend::new(48_000.0);
+    /// let engine: Engine<u8> = Engine::with_backend(device.clone(), |builder| {
+    ///     builder.build(|_| Box::new(|runtime, _, output, _| {
+    ///         output.fill(runtime.events().len() as f32);
+    ///     }))
+    /// });
+    ///
+    /// let midi_in = engine.sender();
+    /// let gui = engine.sender();
+    /// let producers = [
+    ///     thread::spawn(move || (0..50).for_each(|note| midi_in.send(note).unwrap())),
+    ///     thread::spawn(move || (50..100).for_each(|note| gui.send(note).unwrap())),
+    /// ];
+    /// for producer in producers {
+    ///     producer.join().unwrap();
+    /// }
+    ///
+    /// // A one-sample device buffer takes one event per callback
+    /// engine.run();
+    /// let received: f32 = (0..150).map(|_| device.render(1)[0]).sum();
+    /// assert_eq!(received, 100.0);
+    /// ```
+    pub fn sender(&self) -> Sender<E> {
+        self.tx.clone()
+    }
+
+    /// Ramps the output up from silence over `ms` once the stream starts, hiding
+    /// the click a cold first block can make. Call before `run`.
+    pub fn with_fade_in(self, ms: f32) -> Self {
+        let frames = (ms.max(0.0) * 0.001 * self.sample_rate) as usize;
+        self.fade_in.store(frames, Ordering::Relaxed);
+        self
+    }
+
+    /// Hard-limits every sample sent to the device to `db` dBFS, a last line of
+    /// defence against a runaway patch. It clips, so keep it above the level the
+    /// patch normally reaches.
+    pub fn with_output_ceiling(self, db: f32) -> Self {
+        let linear = 10f32.powf(db / 20.0);
+        self.ceiling.store(linear.to_bits(), Ordering::Relaxed);
+        self
+    }
+
+    /// Always ticks the runtime in full `BUFFER_SIZE` blocks, whatever size the
+    /// device asks for, keeping what a block renders beyond the current callback
+    /// for the next one. For algorithms that need a fixed block, at the cost of
+    /// events landing on block boundaries up to a block early. Call before `run`.
+    pub fn with_fixed_blocks(self) -> Self {
+        self.fixed_blocks.store(true, Ordering::Relaxed);
+        self
+    }
+
+    /// Sums every channel of each device frame and sends the sum to all of them,
+    /// for checking a mix's mono compatibility or feeding mono hardware. It's a
+    /// sum, not an average, so in-phase content comes out louder; the output
+    /// ceiling applies after it. Has no effect on a one-channel device.
+    pub fn with_mono_sum(self, on: bool) -> Self {
+        self.mono_sum.store(on, Ordering::Relaxed);
+        self
+    }
+
+    /// Starts (or resumes) the stream. The engine is the handle to it: the stream
+    /// plays until `pause`, or until the engine is stopped or dropped.
     pub fn run(&self) {
-        self.stream.play().unwrap();
+        self.stream.play();
+        self.running.store(true, Ordering::Relaxed);
+    }
+
+    /// Halts the callback, keeping the stream and the runtime's state for `run` to resume.
+    pub fn pause(&self) {
+        self.stream.pause();
+        self.running.store(false, Ordering::Relaxed);
+    }
+
+    /// Whether the stream is playing, i.e. `run` has been called since the last `pause`.
+    pub fn is_running(&self) -> bool {
+        self.running.load(Ordering::Relaxed)
+    }
+
+    /// Closes the stream and drops the runtime. Dropping the engine does the same;
+    /// this spells it out at the call site.
+    pub fn stop(self) {
+        self.stream.pause();
+    }
+
+    /// Stream errors (underruns and the like) reported since the engine started or
+    /// `reset_errors` was last called.
+    pub fn error_count(&self) -> usize {
+        self.errors.load(Ordering::Relaxed)
+    }
+
+    pub fn reset_errors(&self) {
+        self.errors.store(0, Ordering::Relaxed);
+    }
+
+    /// Peak and RMS of the last buffer handed to the device; safe to poll from a UI thread.
+    pub fn output_level(&self) -> OutputLevel {
+        OutputLevel {
+            peak: f32::from_bits(self.level.peak.load(Ordering::Relaxed)),
+            rms: f32::from_bits(self.level.rms.load(Ordering::Relaxed)),
+        }
+    }
+
+    /// Whether any sample sent to device channel `channel` has reached full scale
+    /// (|x| >= 1.0) since the engine started or `reset_clip` was last called. False
+    /// for channels the device doesn't have.
+    pub fn clipped(&self, channel: usize) -> bool {
+        channel < self.channels && self.clips[channel].load(Ordering::Relaxed)
+    }
+
+    /// Clears every channel's clip latch.
+    pub fn reset_clip(&self) {
+        for clip in self.clips.iter() {
+            clip.store(false, Ordering::Relaxed);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::MockBackend;
+    use crate::parameters;
+    use std::alloc::{GlobalAlloc, Layout, System};
+    use std::cell::Cell;
+
+    // Counts each thread's allocations, so a test can check its own thread's
+    // without seeing other tests running alongside
+    struct CountingAllocator;
+
+    thread_local! {
+        static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+    }
+
+    unsafe impl GlobalAlloc for CountingAllocator {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            // Not counted while the thread is being torn down
+            let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+            unsafe { System.alloc(layout) }
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            unsafe { System.dealloc(ptr, layout) }
+        }
+    }
+
+    #[global_allocator]
+    static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+    fn allocations() -> usize {
+        ALLOCATIONS.with(Cell::get)
+    }
+
+    // An event that can be cloned but not copied
+    #[derive(Clone)]
+    struct Named(String);
+
+    #[derive(Default)]
+    struct NameLength(f32);
+
+    impl Modulator<Named> for NameLength {
+        fn update(&mut self, _sample_rate: f32, event: Option<&Named>) {
+            if let Some(Named(name)) = event {
+                self.0 = name.len() as f32;
+            }
+        }
+
+        fn get_value(&self, _index: usize) -> f32 {
+            self.0
+        }
+    }
+
+    #[test]
+    fn clone_only_events_reach_modulators() {
+        let device = MockBackend::new(48_000.0);
+        let engine = Engine::with_backend(device.clone(), |builder| {
+            builder.build(|builder| {
+                builder.use_modulator::<NameLength>();
+                Box::new(|runtime, _input, output, _sample_rate| {
+                    let length = runtime.sources().next().map_or(0.0, |source| source.get_value(0));
+                    output.fill(length);
+                })
+            })
+        });
+        engine.run();
+        engine.tx.send(Named("cutoff".to_string())).unwrap();
+        assert_eq!(device.render(64), vec![6.0; 64]);
+    }
+
+    #[test]
+    fn mock_device_pulls_the_graph_output_once_playing() {
+        let device = MockBackend::new(48_000.0);
+        let engine = Engine::with_backend(device.clone(), |builder: Builder<()>| {
+            builder.build(|_builder| {
+                let mut count = 0.0;
+                Box::new(move |_runtime, _input, output, _sample_rate| {
+                    for out in output.iter_mut() {
+                        count += 1.0;
+                        *out = count;
+                    }
+                })
+            })
+        });
+        assert_eq!(device.render(8), vec![0.0; 8]);
+
+        engine.run();
+        assert!(device.is_playing());
+        assert_eq!(device.render(4), [1.0, 2.0, 3.0, 4.0]);
+        assert_eq!(device.render(4), [5.0, 6.0, 7.0, 8.0]);
+    }
+
+    // A running engine on `device` whose graph is just `component`
+    fn play<E: Clone + Send + 'static>(device: &MockBackend, component: ComponentFn<E>) -> Engine<E> {
+        let engine = Engine::with_backend(device.clone(), |builder| builder.build(|_builder| component));
+        engine.run();
+        engine
+    }
+
+    #[test]
+    fn output_level_reports_the_last_buffer() {
+        let device = MockBackend::new(48_000.0);
+        let mut phase = 0usize;
+        let engine = play::<()>(&device, Box::new(move |_runtime, _input, output, _sample_rate| {
+            for out in output.iter_mut() {
+                *out = 0.8 * (std::f32::consts::TAU * phase as f32 / 48.0).sin();
+                phase += 1;
+            }
+        }));
+        assert_eq!(engine.output_level(), OutputLevel::default());
+
+        // Ten whole cycles
+        device.render(480);
+        let level = engine.output_level();
+        assert!((level.peak - 0.8).abs() < 1e-3, "{level:?}");
+        assert!((level.rms - 0.8 * std::f32::consts::FRAC_1_SQRT_2).abs() < 1e-3, "{level:?}");
+    }
+
+    #[parameters]
+    struct LevelParams {
+        #[param(default = 0.5)]
+        level: f32,
+    }
+
+    // A source that holds 1.0
+    #[derive(Default)]
+    struct Full;
+
+    impl<E> Modulator<E> for Full {
+        fn update(&mut self, _sample_rate: f32, _event: Option<&E>) {}
+
+        fn get_value(&self, _index: usize) -> f32 {
+            1.0
+        }
+    }
+
+    #[test]
+    fn control_commands_apply_before_the_next_block() {
+        let device = MockBackend::new(48_000.0);
+        let mut handles = None;
+        let engine = Engine::with_backend(device.clone(), |builder: Builder<()>| {
+            builder.build(|builder| {
+                let source = builder.use_modulator::<Full>();
+                let params = builder.use_parameters::<LevelParams>();
+                handles = Some((source, params));
+                Box::new(move |runtime, _input, output, _sample_rate| {
+                    let values = runtime.get_parameters(&params);
+                    for (i, out) in output.iter_mut().enumerate() {
+                        *out = values[i].level;
+                    }
+                })
+            })
+        });
+        let (source, params) = handles.unwrap();
+        engine.run();
+        assert_eq!(device.render(64), vec![0.5; 64]);
+
+        engine.control.send(ControlCommand::route(source, params, "level", 0.25)).unwrap();
+        assert_eq!(device.render(64), vec![0.75; 64]);
+        engine.control.send(ControlCommand::unroute(source, params, "level")).unwrap();
+        assert_eq!(device.render(64), vec![0.5; 64]);
     }
-}
\ No newline at end of file
+
+    #[parameters]
+    struct SmoothedLevel {
+        #[param(default = 0.0, smooth_ms = 10.0)]
+        level: f32,
+    }
+
+    // The smoothed level one block after it is set to 1.0 on a device at `sample_rate`
+    fn level_after_step(sample_rate: f32) -> f32 {
+        let device = MockBackend::new(sample_rate);
+        let mut handle = None;
+        let engine = Engine::<()>::with_backend(device.clone(), |builder| {
+            builder.build(|builder| {
+                let params = builder.use_parameters::<SmoothedLevel>();
+                handle = Some(params);
+                Box::new(move |runtime, _input, output, _sample_rate| {
+                    let values = runtime.get_parameters(&params);
+                    for (i, out) in output.iter_mut().enumerate() {
+                        *out = values[i].level;
+                    }
+                })
+            })
+        });
+        engine.run();
+        device.render(256);
+        engine.control.send(ControlCommand::set_base(handle.unwrap(), "level", 1.0)).unwrap();
+        *device.render(256).last().unwrap()
+    }
+
+    #[test]
+    fn parameter_runtimes_are_updated_at_the_device_rate() {
+        // 256 samples are 5.3 ms at 48 kHz and 2.7 ms at 96 kHz, against a 10 ms time
+        let at_48k = level_after_step(48_000.0);
+        let at_96k = level_after_step(96_000.0);
+        assert!((at_48k - (1.0 - (-256.0f32 / 480.0).exp())).abs() < 0.01, "{}", at_48k);
+        assert!((at_96k - (1.0 - (-256.0f32 / 960.0).exp())).abs() < 0.01, "{}", at_96k);
+    }
+
+    #[test]
+    fn stream_errors_are_counted_until_reset() {
+        let device = MockBackend::new(48_000.0);
+        let engine = play::<()>(&device, Box::new(|_runtime, _input, output, _sample_rate| output.fill(0.0)));
+        assert_eq!(engine.error_count(), 0);
+        device.inject_error("underrun");
+        device.inject_error("underrun");
+        assert_eq!(engine.error_count(), 2);
+        engine.reset_errors();
+        assert_eq!(engine.error_count(), 0);
+    }
+
+    #[test]
+    fn output_ceiling_clamps_what_the_device_gets() {
+        let device = MockBackend::new(48_000.0);
+        let engine = Engine::<()>::with_backend(device.clone(), |builder| {
+            builder.build(|_builder| {
+                Box::new(|_runtime, _input, output, _sample_rate| {
+                    for (i, out) in output.iter_mut().enumerate() {
+                        *out = if i % 2 == 0 { 1.5 } else { -0.25 };
+                    }
+                })
+            })
+        }).with_output_ceiling(-6.0);
+        engine.run();
+        let ceiling = 10f32.powf(-6.0 / 20.0);
+        let output = device.render(64);
+        assert!(output.iter().step_by(2).all(|&sample| sample == ceiling));
+        assert!(output.iter().skip(1).step_by(2).all(|&sample| sample == -0.25));
+    }
+
+    #[test]
+    fn components_see_the_device_channel_count() {
+        let device = MockBackend::new(48_000.0).with_channels(2);
+        let _engine = play::<()>(&device, Box::new(|runtime, _input, output, _sample_rate| {
+            output.fill(runtime.channels() as f32);
+        }));
+        assert_eq!(device.render(64), vec![2.0; 128]);
+    }
+
+    #[test]
+    fn mono_sum_sends_each_frames_sum_to_every_channel() {
+        let device = MockBackend::new(48_000.0).with_channels(2);
+        let engine = play::<()>(&device, Box::new(|_runtime, _input, output, _sample_rate| {
+            for frame in output.chunks_exact_mut(2) {
+                frame.copy_from_slice(&[0.25, -0.5]);
+            }
+        })).with_mono_sum(true);
+        assert_eq!(device.render(64), vec![-0.25; 128]);
+
+        let _engine = engine.with_mono_sum(false);
+        assert_eq!(device.render(2), vec![0.25, -0.5, 0.25, -0.5]);
+    }
+
+    #[test]
+    fn clip_latches_hold_per_channel_until_reset() {
+        let device = MockBackend::new(48_000.0).with_channels(2);
+        let mut blocks = 0;
+        // Only the first block sends the right channel over full scale
+        let engine = play::<()>(&device, Box::new(move |_runtime, _input, output, _sample_rate| {
+            let right = if blocks == 0 { 1.2 } else { 0.5 };
+            for frame in output.chunks_exact_mut(2) {
+                frame.copy_from_slice(&[0.5, right]);
+            }
+            blocks += 1;
+        }));
+        device.render(64);
+        assert!(!engine.clipped(0));
+        assert!(engine.clipped(1));
+        assert!(!engine.clipped(2));
+
+        device.render(64);
+        assert!(engine.clipped(1));
+
+        engine.reset_clip();
+        device.render(64);
+        assert!(!engine.clipped(0) && !engine.clipped(1));
+    }
+
+    #[test]
+    fn fixed_blocks_tick_full_blocks_for_any_device_size() {
+        let device = MockBackend::new(48_000.0);
+        let blocks = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let seen = blocks.clone();
+        let mut count = 0.0;
+        let engine = Engine::<()>::with_backend(device.clone(), |builder| {
+            builder.build(|_builder| {
+                Box::new(move |_runtime, _input, output, _sample_rate| {
+                    seen.lock().unwrap().push(output.len());
+                    for out in output.iter_mut() {
+                        *out = count;
+                        count += 1.0;
+                    }
+                })
+            })
+        }).with_fixed_blocks();
+        engine.run();
+
+        let sizes = [100, 300, 37, 512];
+        let output: Vec<f32> = sizes.iter().flat_map(|&frames| device.render(frames)).collect();
+        let expected: Vec<f32> = (0..sizes.iter().sum::<usize>()).map(|i| i as f32).collect();
+        assert_eq!(output, expected);
+        // 949 samples need four blocks
+        assert_eq!(*blocks.lock().unwrap(), [BUFFER_SIZE; 4]);
+    }
+
+    #[test]
+    fn pausing_holds_the_stream_and_dropping_the_engine_stops_it() {
+        let device = MockBackend::new(48_000.0);
+        let engine = play::<()>(&device, Box::new(|_runtime, _input, output, _sample_rate| output.fill(0.5)));
+        assert!(engine.is_running());
+        assert_eq!(device.render(4), [0.5; 4]);
+
+        engine.pause();
+        assert!(!engine.is_running());
+        assert_eq!(device.render(4), [0.0; 4]);
+        engine.run();
+        assert_eq!(device.render(4), [0.5; 4]);
+
+        drop(engine);
+        assert!(!device.is_playing());
+        assert_eq!(device.render(4), [0.0; 4]);
+    }
+
+    #[test]
+    fn each_sender_keeps_its_own_order() {
+        let device = MockBackend::new(48_000.0);
+        let received = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let log = received.clone();
+        let engine = play::<u8>(&device, Box::new(move |runtime, _input, output, _sample_rate| {
+            log.lock().unwrap().extend(runtime.events().iter().copied());
+            output.fill(0.0);
+        }));
+        let (midi, gui) = (engine.sender(), engine.sender());
+        for (midi_event, gui_event) in [(1, 10), (2, 20), (3, 30)] {
+            midi.send(midi_event).unwrap();
+            gui.send(gui_event).unwrap();
+        }
+        drop((midi, gui));
+        device.render(BUFFER_SIZE * 6);
+
+        let received = received.lock().unwrap();
+        assert_eq!(received.len(), 6);
+        let from = |sender: fn(&u8) -> bool| received.iter().copied().filter(sender).collect::<Vec<_>>();
+        assert_eq!(from(|&event| event < 10), [1, 2, 3]);
+        assert_eq!(from(|&event| event >= 10), [10, 20, 30]);
+    }
+
+    // Counts samples: each block reads on from where the last one ended
+    #[derive(Default)]
+    struct Clock {
+        start: f32,
+        next: f32,
+    }
+
+    impl<E> Modulator<E> for Clock {
+        fn update(&mut self, sample_rate: f32, event: Option<&E>) {
+            self.update_block(sample_rate, event, BUFFER_SIZE);
+        }
+
+        fn update_block(&mut self, _sample_rate: f32, _event: Option<&E>, len: usize) {
+            self.start = self.next;
+            self.next += len as f32;
+        }
+
+        fn get_value(&self, index: usize) -> f32 {
+            self.start + index as f32
+        }
+    }
+
+    #[test]
+    fn device_buffers_split_into_full_blocks_and_a_short_last_one() {
+        let device = MockBackend::new(48_000.0);
+        let engine = Engine::with_backend(device.clone(), |builder: Builder<()>| {
+            builder.build(|builder| {
+                builder.use_modulator::<Clock>();
+                Box::new(|runtime, _input, output, _sample_rate| {
+                    let clock = runtime.sources().next().unwrap();
+                    for (i, out) in output.iter_mut().enumerate() {
+                        *out = clock.get_value(i);
+                    }
+                })
+            })
+        });
+        engine.run();
+
+        // 256 + 44, with the clock carrying on from 300 only if the short block
+        // advanced it by 44
+        let first = device.render(300);
+        let second = device.render(300);
+        let expected: Vec<f32> = (0..600).map(|i| i as f32).collect();
+        assert_eq!([first, second].concat(), expected);
+    }
+
+    #[test]
+    fn fade_in_spans_its_time_on_a_stereo_device() {
+        let device = MockBackend::new(48_000.0).with_channels(2);
+        let engine = Engine::with_backend(device.clone(), |builder: Builder<()>| {
+            builder.build(|_builder| Box::new(|_runtime, _input, output, _sample_rate| output.fill(1.0)))
+        })
+        .with_fade_in(1.0);
+        engine.run();
+
+        // 1 ms is 48 frames, 96 interleaved samples
+        let output = device.render(128);
+        assert_eq!(&output[..2], [0.0, 0.0]);
+        assert!(output[95] < 1.0);
+        assert!(output[96..].iter().all(|&sample| sample == 1.0));
+        // Left and right share each frame's gain
+        assert!(output.chunks_exact(2).all(|frame| frame[0] == frame[1]));
+    }
+
+    #[test]
+    fn generator_callback_does_not_allocate() {
+        let device = MockBackend::new(48_000.0);
+        let mut phase = 0.0f32;
+        let _engine = play::<()>(&device, Box::new(move |_runtime, _input, output, _sample_rate| {
+            for out in output.iter_mut() {
+                *out = phase.sin();
+                phase += 0.05;
+            }
+        }));
+        device.render(1_000);
+
+        // The mock's own output buffer is the one allocation
+        let before = allocations();
+        device.render(1_000);
+        assert_eq!(allocations() - before, 1);
+    }
+}
@@ -1,15 +1,103 @@
 //! Ceres DSP Framework - Modular audio processing with parameter modulation
 
+// Lets `#[parameters]` (which expands to `::ceres::...` paths) be used inside this crate
+extern crate self as ceres;
+
+pub mod backend;
+pub mod components;
 pub mod core;
 pub mod engine;
+pub mod modulators;
+pub mod schedule;
 
 // Re-export everything for clean imports
 pub use core::*;
 pub use ceres_macros::parameters;
 
+// Used by `#[parameters(serde)]`
+#[cfg(feature = "serde")]
+#[doc(hidden)]
+pub use serde;
+
 // Convenience re-exports
 pub use crate::core::{Builder, Runtime, ComponentFn};
 pub use crate::core::{StateHandle, ModulatorHandle, ParameterHandle};
 pub use crate::core::{Modulator, Parameters, ParameterRuntime};
-pub use crate::engine::Engine;
+pub use crate::backend::{AudioBackend, CpalBackend, MockBackend};
+pub use crate::engine::{Engine, OutputLevel};
+pub use crate::schedule::{Graph, Schedule};
+
 
+/// Compile checks for the field types `#[parameters]` accepts, run as doctests.
+///
+/// An alias of `f32` is accepted when the field is marked `raw`:
+///
+/// ```
+/// type Amp = f32;
+///
+/// #[ceres::parameters]
+/// struct GainParams {
+///     #[param(raw)]
+///     amount: Amp,
+/// }
+/// ```
+///
+/// Without `raw` it's rejected, since the check is syntactic:
+///
+/// ```compile_fail
+/// type Amp = f32;
+///
+/// #[ceres::parameters]
+/// struct GainParams {
+///     amount: Amp,
+/// }
+/// ```
+///
+/// `raw` still doesn't compile if the alias isn't `f32`:
+///
+/// ```compile_fail
+/// type Amp = f64;
+///
+/// #[ceres::parameters]
+/// struct GainParams {
+///     #[param(raw)]
+///     amount: Amp,
+/// }
+/// ```
+///
+/// and neither does a type that's merely named `f32`:
+///
+/// ```compile_fail
+/// mod units {
+///     #[derive(Clone, Copy, Default)]
+///     #[allow(non_camel_case_types)]
+///     pub struct f32(pub std::primitive::f32);
+/// }
+///
+/// #[ceres::parameters]
+/// struct GainParams {
+///     amount: units::f32,
+/// }
+/// ```
+///
+/// A field is evaluated once per block or every sample, not both:
+///
+/// ```compile_fail
+/// #[ceres::parameters]
+/// struct FmParams {
+///     #[param(block_rate, audio_rate)]
+///     index: f32,
+/// }
+/// ```
+///
+/// and a `block_rate` field can't be smoothed either:
+///
+/// ```compile_fail
+/// #[ceres::parameters]
+/// struct FilterParams {
+///     #[param(block_rate, smooth_ms = 10.0)]
+///     cutoff: f32,
+/// }
+/// ```
+#[cfg(doctest)]
+pub struct ParametersFieldTypes;
@@ -0,0 +1,167 @@
+// One-shot attack-decay envelope: note-ons trigger it, note-offs are ignored.
+use crate::core::*;
+use super::{ChannelFilter, NoteEvent};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Stage {
+    Attack,
+    Decay,
+    Idle,
+}
+
+/// Rises to 1.0 over the attack time and falls back to 0.0 over the decay time
+/// on every note-on, whether or not the key is still held. For percussive
+/// contours where gate length shouldn't matter.
+///
+/// A retrigger restarts the attack from the current level rather than from zero,
+/// so overlapping hits don't click.
+pub struct AD {
+    channel: ChannelFilter,
+    attack: f32,
+    decay: f32,
+    stage: Stage,
+    level: f32,
+    values: [f32; BUFFER_SIZE],
+}
+
+impl Default for AD {
+    fn default() -> Self {
+        Self {
+            channel: ChannelFilter::Omni,
+            attack: 0.001,
+            decay: 0.2,
+            stage: Stage::Idle,
+            level: 0.0,
+            values: [0.0; BUFFER_SIZE],
+        }
+    }
+}
+
+impl AD {
+    pub fn set_channel(&mut self, channel: ChannelFilter) {
+        self.channel = channel;
+    }
+
+    /// Seconds from 0.0 to 1.0. Zero jumps straight to the peak.
+    pub fn set_attack(&mut self, seconds: f32) {
+        self.attack = seconds.max(0.0);
+    }
+
+    /// Seconds from 1.0 back to 0.0.
+    pub fn set_decay(&mut self, seconds: f32) {
+        self.decay = seconds.max(0.0);
+    }
+
+    pub fn trigger(&mut self) {
+        self.stage = Stage::Attack;
+    }
+
+    pub fn level(&self) -> f32 {
+        self.level
+    }
+
+    fn advance(&mut self, sample_rate: f32) {
+        match self.stage {
+            Stage::Attack => {
+                self.level += 1.0 / (self.attack * sample_rate).max(1.0);
+                if self.attack == 0.0 || self.level >= 1.0 {
+                    self.level = 1.0;
+                    self.stage = Stage::Decay;
+                }
+            }
+            Stage::Decay => {
+                self.level -= 1.0 / (self.decay * sample_rate).max(1.0);
+                if self.level <= 0.0 {
+                    self.level = 0.0;
+                    self.stage = Stage::Idle;
+                }
+            }
+            Stage::Idle => {}
+        }
+    }
+}
+
+impl<E: NoteEvent> Modulator<E> for AD {
+    fn update(&mut self, sample_rate: f32, event: Option<&E>) {
+        self.update_block(sample_rate, event, BUFFER_SIZE);
+    }
+
+    fn update_block(&mut self, sample_rate: f32, event: Option<&E>, len: usize) {
+        let event = event.filter(|event| self.channel.accepts(*event));
+        if event.is_some_and(|event| event.note_on().is_some()) {
+            self.trigger();
+        }
+
+        for i in 0..len.min(BUFFER_SIZE) {
+            self.values[i] = self.level;
+            self.advance(sample_rate);
+        }
+    }
+
+    fn get_value(&self, index: usize) -> f32 {
+        self.values[index % BUFFER_SIZE]
+    }
+
+    fn is_active(&self) -> bool {
+        self.stage != Stage::Idle
+    }
+
+    fn debug_state(&self) -> String {
+        format!("{:?}, level {:.3}", self.stage, self.level)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tick(envelope: &mut AD, event: Option<[u8; 3]>) {
+        Modulator::update(envelope, 48_000.0, event.as_ref());
+    }
+
+    #[test]
+    fn envelopes_on_different_channels_trigger_on_their_own_notes() {
+        let mut envelopes = [AD::default(), AD::default()];
+        envelopes[0].set_channel(ChannelFilter::Channel(0));
+        envelopes[1].set_channel(ChannelFilter::Channel(1));
+        let active = |envelopes: &[AD; 2]| envelopes.each_ref().map(Modulator::<[u8; 3]>::is_active);
+
+        // A note-on on channel index 1, status 0x91
+        envelopes.iter_mut().for_each(|envelope| tick(envelope, Some([0x91, 60, 100])));
+        assert_eq!(active(&envelopes), [false, true]);
+
+        envelopes.iter_mut().for_each(|envelope| tick(envelope, Some([0x90, 60, 100])));
+        assert_eq!(active(&envelopes), [true, true]);
+    }
+
+    fn block(envelope: &AD) -> Vec<f32> {
+        (0..BUFFER_SIZE).map(|i| Modulator::<[u8; 3]>::get_value(envelope, i)).collect()
+    }
+
+    #[test]
+    fn each_trigger_restarts_the_contour_and_releases_are_ignored() {
+        let contour = |release: bool| {
+            let mut envelope = AD::default();
+            envelope.set_decay(0.05);
+            let mut values = Vec::new();
+            for block_index in 0..12 {
+                let event = match block_index {
+                    // A second hit part way down the decay
+                    0 | 4 => Some([0x90, 60, 100]),
+                    1 | 5 if release => Some([0x80, 60, 0]),
+                    _ => None,
+                };
+                tick(&mut envelope, event);
+                values.extend(block(&envelope));
+            }
+            values
+        };
+        let unreleased = contour(false);
+        let peaks = unreleased.windows(3).filter(|w| w[1] == 1.0 && w[0] < 1.0).count();
+        assert_eq!(peaks, 2, "both hits reach the peak");
+        // Falling before the second hit, then back at the top after it
+        assert!(unreleased[4 * BUFFER_SIZE - 1] < 0.8);
+        assert!(unreleased[4 * BUFFER_SIZE + 100] > 0.95);
+        assert_eq!(contour(true), unreleased);
+    }
+}
@@ -0,0 +1,110 @@
+// Gate opened by signal level instead of notes. The `audio_gate` component feeds
+// it audio; on its own it stays closed.
+use crate::core::*;
+
+/// 1.0 while the followed signal is loud enough, 0.0 otherwise. Opening and
+/// closing use separate thresholds, so a level hovering between them doesn't make
+/// the gate chatter.
+pub struct AudioGate {
+    open_threshold: f32,
+    close_threshold: f32,
+    release: f32,
+    envelope: f32,
+    open: bool,
+    values: [f32; BUFFER_SIZE],
+}
+
+impl Default for AudioGate {
+    fn default() -> Self {
+        Self {
+            open_threshold: 0.1,
+            close_threshold: 0.05,
+            release: 0.02,
+            envelope: 0.0,
+            open: false,
+            values: [0.0; BUFFER_SIZE],
+        }
+    }
+}
+
+impl AudioGate {
+    /// Linear amplitudes the level must reach to open the gate and fall below to
+    /// close it. `close` is capped at `open`.
+    pub fn set_thresholds(&mut self, open: f32, close: f32) {
+        self.open_threshold = open;
+        self.close_threshold = close.min(open);
+    }
+
+    /// Seconds the level follower takes to fall after the signal drops. Longer
+    /// values also ride over the gaps between cycles of low notes.
+    pub fn set_release(&mut self, seconds: f32) {
+        self.release = seconds.max(0.0);
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    /// Follows `input`'s level and updates the gate for each of its samples.
+    pub fn process(&mut self, input: &[f32], sample_rate: f32) {
+        let release = (-1.0 / (self.release * sample_rate).max(1.0)).exp();
+        for (value, &sample) in self.values.iter_mut().zip(input) {
+            self.envelope = sample.abs().max(self.envelope * release);
+            if self.envelope >= self.open_threshold {
+                self.open = true;
+            } else if self.envelope < self.close_threshold {
+                self.open = false;
+            }
+            *value = if self.open { 1.0 } else { 0.0 };
+        }
+    }
+}
+
+impl<E> Modulator<E> for AudioGate {
+    // The level is followed in `process`, called with the audio
+    fn update(&mut self, _sample_rate: f32, _event: Option<&E>) {}
+
+    fn get_value(&self, index: usize) -> f32 {
+        self.values[index % BUFFER_SIZE]
+    }
+
+    fn is_active(&self) -> bool {
+        self.open
+    }
+
+    fn debug_state(&self) -> String {
+        format!("gate {}, level {:.3}", if self.open { "open" } else { "closed" }, self.envelope)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Open/close changes over a second of a level wobbling 0.055..0.105 at 5 Hz
+    fn transitions(open: f32, close: f32) -> usize {
+        let mut gate = AudioGate::default();
+        gate.set_thresholds(open, close);
+        let signal: Vec<f32> = (0..48_000)
+            .map(|i| 0.08 + 0.025 * (std::f32::consts::TAU * 5.0 * i as f32 / 48_000.0).sin())
+            .collect();
+        let mut changes = 0;
+        let mut was_open = false;
+        for block in signal.chunks(BUFFER_SIZE) {
+            gate.process(block, 48_000.0);
+            for i in 0..block.len() {
+                let open = Modulator::<()>::get_value(&gate, i) == 1.0;
+                changes += (open != was_open) as usize;
+                was_open = open;
+            }
+        }
+        changes
+    }
+
+    #[test]
+    fn level_hovering_inside_the_hysteresis_band_does_not_chatter() {
+        // One threshold in the middle of the wobble toggles on every cycle
+        assert!(transitions(0.08, 0.08) >= 9);
+        assert_eq!(transitions(0.1, 0.05), 1);
+    }
+}
@@ -0,0 +1,66 @@
+// 1.0 while any key is held, 0.0 otherwise.
+use crate::core::*;
+use super::{ChannelFilter, NoteEvent};
+
+#[derive(Default)]
+pub struct Gate {
+    channel: ChannelFilter,
+    // One bit per MIDI key
+    held: u128,
+}
+
+impl Gate {
+    pub fn set_channel(&mut self, channel: ChannelFilter) {
+        self.channel = channel;
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.held != 0
+    }
+}
+
+impl<E: NoteEvent> Modulator<E> for Gate {
+    fn update(&mut self, _sample_rate: f32, event: Option<&E>) {
+        let Some(event) = event.filter(|event| self.channel.accepts(*event)) else { return };
+        if let Some((note, _)) = event.note_on() {
+            self.held |= 1 << (note & 0x7F);
+        } else if let Some(note) = event.note_off() {
+            self.held &= !(1 << (note & 0x7F));
+        }
+    }
+
+    fn get_value(&self, _index: usize) -> f32 {
+        if self.is_open() { 1.0 } else { 0.0 }
+    }
+
+    fn is_active(&self) -> bool {
+        self.is_open()
+    }
+
+    fn debug_state(&self) -> String {
+        format!("gate {} ({} held)", if self.is_open() { "open" } else { "closed" }, self.held.count_ones())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn play(gate: &mut Gate, event: [u8; 3]) -> f32 {
+        Modulator::update(gate, 48_000.0, Some(&event));
+        Modulator::<[u8; 3]>::get_value(gate, 0)
+    }
+
+    #[test]
+    fn gate_stays_open_while_any_note_is_held() {
+        let mut gate = Gate::default();
+        assert_eq!(Modulator::<[u8; 3]>::get_value(&gate, 0), 0.0);
+        assert_eq!(play(&mut gate, [0x90, 60, 100]), 1.0);
+        assert_eq!(play(&mut gate, [0x90, 64, 100]), 1.0);
+        assert_eq!(play(&mut gate, [0x80, 60, 0]), 1.0);
+        assert_eq!(play(&mut gate, [0x80, 64, 0]), 0.0);
+        // A note-on with velocity 0 is a release
+        assert_eq!(play(&mut gate, [0x90, 67, 100]), 1.0);
+        assert_eq!(play(&mut gate, [0x90, 67, 0]), 0.0);
+    }
+}
@@ -0,0 +1,90 @@
+// Key tracking: a value that follows the played note, for cutoffs and the like.
+use crate::core::*;
+use super::{ChannelFilter, NoteEvent};
+
+/// Outputs the most recently pressed note's distance from the centre note, on
+/// the pitch scale (semitones / 127) times the tracking amount. Notes above the
+/// centre give positive values and notes below negative, so routed additively
+/// to a cutoff it opens the filter for higher keys. Holds after release.
+pub struct KeyTrack {
+    channel: ChannelFilter,
+    amount: f32,
+    center: u8,
+    note: Option<u8>,
+}
+
+impl Default for KeyTrack {
+    fn default() -> Self {
+        Self { channel: ChannelFilter::Omni, amount: 1.0, center: 60, note: None }
+    }
+}
+
+impl KeyTrack {
+    pub fn set_channel(&mut self, channel: ChannelFilter) {
+        self.channel = channel;
+    }
+
+    /// 1.0 moves the output one pitch step per semitone; 0.0 turns tracking off.
+    pub fn set_amount(&mut self, amount: f32) {
+        self.amount = amount;
+    }
+
+    /// The note that outputs 0.0. Middle C (60) by default.
+    pub fn set_center(&mut self, note: u8) {
+        self.center = note;
+    }
+
+    pub fn value(&self) -> f32 {
+        match self.note {
+            Some(note) => note_to_pitch(note as f32 - self.center as f32) * self.amount,
+            None => 0.0,
+        }
+    }
+}
+
+impl<E: NoteEvent> Modulator<E> for KeyTrack {
+    fn update(&mut self, _sample_rate: f32, event: Option<&E>) {
+        let event = event.filter(|event| self.channel.accepts(*event));
+        if let Some((note, _)) = event.and_then(NoteEvent::note_on) {
+            self.note = Some(note);
+        }
+    }
+
+    fn get_value(&self, _index: usize) -> f32 {
+        self.value()
+    }
+
+    fn range(&self) -> (f32, f32) {
+        (-self.amount.abs(), self.amount.abs())
+    }
+
+    fn debug_state(&self) -> String {
+        match self.note {
+            Some(note) => format!("note {}, value {:.3}", note, self.value()),
+            None => "no note".to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn play(track: &mut KeyTrack, event: [u8; 3]) -> f32 {
+        Modulator::update(track, 48_000.0, Some(&event));
+        Modulator::<[u8; 3]>::get_value(track, 0)
+    }
+
+    #[test]
+    fn keys_above_the_centre_track_up_and_below_track_down() {
+        let mut track = KeyTrack::default();
+        assert_eq!(play(&mut track, [0x90, 60, 100]), 0.0);
+        assert_eq!(play(&mut track, [0x90, 72, 100]), note_to_pitch(12.0));
+        assert_eq!(play(&mut track, [0x90, 48, 100]), -note_to_pitch(12.0));
+        // Holds through the release
+        assert_eq!(play(&mut track, [0x80, 48, 0]), -note_to_pitch(12.0));
+
+        track.set_amount(0.5);
+        assert_eq!(play(&mut track, [0x90, 72, 100]), note_to_pitch(12.0) * 0.5);
+    }
+}
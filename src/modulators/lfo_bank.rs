@@ -0,0 +1,128 @@
+// Several LFO outputs driven by one phase accumulator, so they can't drift apart.
+use std::f32::consts::TAU;
+use crate::core::*;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LfoShape {
+    #[default]
+    Sine,
+    Triangle,
+    /// Rising ramp
+    Saw,
+    Square,
+}
+
+impl LfoShape {
+    /// Unipolar (0..1) value at `phase` cycles.
+    pub fn value(self, phase: f32) -> f32 {
+        let phase = phase.rem_euclid(1.0);
+        match self {
+            LfoShape::Sine => 0.5 - 0.5 * (phase * TAU).cos(),
+            LfoShape::Triangle => 1.0 - (2.0 * phase - 1.0).abs(),
+            LfoShape::Saw => phase,
+            LfoShape::Square => if phase < 0.5 { 1.0 } else { 0.0 },
+        }
+    }
+}
+
+/// N LFO channels on one master phase, each at its own phase offset, e.g. a
+/// quadrature pair at offsets 0.0 and 0.25 or a stereo spread.
+///
+/// Every shape starts its cycle at 0.0. Routing the bank uses channel 0; read
+/// other channels with `channel_value`.
+///
+/// The bank free-runs; wrap it in `Retrigger` to restart its phase on each note.
+pub struct LfoBank<const N: usize> {
+    shape: LfoShape,
+    frequency: f32,
+    // Offsets in cycles
+    offsets: [f32; N],
+    phase: f32,
+    values: [[f32; BUFFER_SIZE]; N],
+}
+
+impl<const N: usize> Default for LfoBank<N> {
+    fn default() -> Self {
+        Self {
+            shape: LfoShape::Sine,
+            frequency: 1.0,
+            offsets: [0.0; N],
+            phase: 0.0,
+            values: [[0.0; BUFFER_SIZE]; N],
+        }
+    }
+}
+
+impl<const N: usize> LfoBank<N> {
+    pub fn set_shape(&mut self, shape: LfoShape) {
+        self.shape = shape;
+    }
+
+    pub fn set_frequency(&mut self, hz: f32) {
+        self.frequency = hz.max(0.0);
+    }
+
+    /// Phase offset of `channel` in cycles: 0.25 is 90 degrees.
+    pub fn set_offset(&mut self, channel: usize, cycles: f32) {
+        self.offsets[channel] = cycles;
+    }
+
+    /// Jumps the master phase to `cycles`; the next block starts from there.
+    pub fn set_phase(&mut self, cycles: f32) {
+        self.phase = cycles.rem_euclid(1.0);
+    }
+
+    /// Master phase in cycles, 0..1.
+    pub fn phase(&self) -> f32 {
+        self.phase
+    }
+
+    /// `channel`'s value at sample `index` of the block.
+    pub fn channel_value(&self, channel: usize, index: usize) -> f32 {
+        self.values[channel][index % BUFFER_SIZE]
+    }
+}
+
+impl<E, const N: usize> Modulator<E> for LfoBank<N> {
+    fn update(&mut self, sample_rate: f32, event: Option<&E>) {
+        self.update_block(sample_rate, event, BUFFER_SIZE);
+    }
+
+    fn update_block(&mut self, sample_rate: f32, _event: Option<&E>, len: usize) {
+        let increment = self.frequency / sample_rate;
+        for i in 0..len.min(BUFFER_SIZE) {
+            for (values, offset) in self.values.iter_mut().zip(self.offsets.iter()) {
+                values[i] = self.shape.value(self.phase + offset);
+            }
+            self.phase = (self.phase + increment).fract();
+        }
+    }
+
+    fn get_value(&self, index: usize) -> f32 {
+        self.channel_value(0, index)
+    }
+
+    fn debug_state(&self) -> String {
+        format!("{:?} {:.2} Hz, phase {:.3}", self.shape, self.frequency, self.phase)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quadrature_pair_is_a_quarter_cycle_apart() {
+        let mut bank = LfoBank::<2>::default();
+        // 192 samples a cycle, so a quarter is 48
+        bank.set_frequency(250.0);
+        bank.set_offset(1, 0.25);
+        Modulator::<()>::update(&mut bank, 48_000.0, None);
+        for i in 0..BUFFER_SIZE - 48 {
+            let (leading, lagging) = (bank.channel_value(1, i), bank.channel_value(0, i + 48));
+            assert!((leading - lagging).abs() < 1e-4, "sample {i}: {leading} vs {lagging}");
+        }
+        assert!(bank.channel_value(0, 0).abs() < 1e-6);
+        assert!((bank.channel_value(1, 0) - 0.5).abs() < 1e-6);
+    }
+}
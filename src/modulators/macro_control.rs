@@ -0,0 +1,131 @@
+// Macro control: one knob driving a set of routings, like a hardware macro.
+use crate::core::*;
+
+/// A constant source set from a GUI or controller, 0..1. Change it with
+/// `runtime.get_source_mut(&handle).set_value(..)`. It's meant to be routed
+/// through `MacroDestinations`, which keeps its routings together.
+#[derive(Default)]
+pub struct Macro {
+    value: f32,
+}
+
+impl Macro {
+    pub fn set_value(&mut self, value: f32) {
+        self.value = value.clamp(0.0, 1.0);
+    }
+
+    pub fn value(&self) -> f32 {
+        self.value
+    }
+}
+
+impl<E> Modulator<E> for Macro {
+    fn update(&mut self, _sample_rate: f32, _event: Option<&E>) {}
+
+    fn get_value(&self, _index: usize) -> f32 {
+        self.value
+    }
+
+    // It only moves when set, and then holds for the whole block
+    fn is_active(&self) -> bool {
+        false
+    }
+
+    fn rate(&self) -> ModRate {
+        ModRate::ControlRate(BUFFER_SIZE)
+    }
+
+    fn debug_state(&self) -> String {
+        format!("macro {:.3}", self.value)
+    }
+}
+
+/// A macro's destinations, each with its own amount; a negative amount inverts
+/// it, so turning the macro up closes one parameter while opening another. The
+/// set is routed and unrouted as a unit, and `Runtime::set_source_depth` on the
+/// macro's handle scales every destination at once.
+pub struct MacroDestinations {
+    source: ModulatorHandle<Macro>,
+    routes: Vec<ControlCommand>,
+    unroutes: Vec<ControlCommand>,
+}
+
+impl MacroDestinations {
+    pub fn new(source: ModulatorHandle<Macro>) -> Self {
+        Self { source, routes: Vec::new(), unroutes: Vec::new() }
+    }
+
+    /// Adds `param` of `target` at `amount`.
+    pub fn with<T: Parameters>(mut self, target: ParameterHandle<T>, param: &'static str, amount: f32) -> Self {
+        self.routes.push(ControlCommand::route(self.source, target, param, amount));
+        self.unroutes.push(ControlCommand::unroute(self.source, target, param));
+        self
+    }
+
+    pub fn source(&self) -> ModulatorHandle<Macro> {
+        self.source
+    }
+
+    /// Routes every destination. Stops at the first parameter the target
+    /// doesn't have, leaving earlier destinations routed.
+    pub fn route<E: Clone + Send + 'static>(&self, runtime: &mut Runtime<E>) -> Result<(), RouteError> {
+        self.routes.iter().try_for_each(|command| runtime.apply(command.clone()))
+    }
+
+    /// Removes every destination's routing.
+    pub fn unroute<E: Clone + Send + 'static>(&self, runtime: &mut Runtime<E>) -> Result<(), RouteError> {
+        self.unroutes.iter().try_for_each(|command| runtime.apply(command.clone()))
+    }
+
+    /// The routings as commands, for sending to a running engine through `Engine::control`.
+    pub fn route_commands(&self) -> impl Iterator<Item = ControlCommand> + '_ {
+        self.routes.iter().cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parameters;
+
+    #[parameters]
+    struct CutoffParams {
+        #[param(default = 0.5)]
+        cutoff: f32,
+    }
+
+    #[parameters]
+    struct DriveParams {
+        #[param(default = 0.5)]
+        drive: f32,
+    }
+
+    #[test]
+    fn destinations_move_in_proportion_to_their_amounts() {
+        let (_bus, mut builder) = new::<()>();
+        let source = builder.use_modulator::<Macro>();
+        let (cutoff, drive) = (builder.use_parameters::<CutoffParams>(), builder.use_parameters::<DriveParams>());
+        let mut runtime = builder.build_headless();
+        let destinations = MacroDestinations::new(source).with(cutoff, "cutoff", 0.4).with(drive, "drive", -0.2);
+        destinations.route(&mut runtime).unwrap();
+
+        // Each destination's offset from its base, at the first sample of a block
+        let offsets = |runtime: &mut Runtime<()>, value: f32| {
+            runtime.get_source_mut(&source).set_value(value);
+            runtime.tick(48_000.0, None, &[0.0; BUFFER_SIZE], &mut [0.0; BUFFER_SIZE]);
+            (runtime.get_parameters(&cutoff)[0].cutoff - 0.5, runtime.get_parameters(&drive)[0].drive - 0.5)
+        };
+        for value in [0.25, 0.5, 1.0] {
+            let (cutoff, drive) = offsets(&mut runtime, value);
+            assert!((cutoff - 0.4 * value).abs() < 1e-6 && (drive + 0.2 * value).abs() < 1e-6, "{}", value);
+        }
+
+        // The source's depth scales both together
+        runtime.set_source_depth(source, 0.5);
+        let (cutoff, drive) = offsets(&mut runtime, 1.0);
+        assert!((cutoff - 0.2).abs() < 1e-6 && (drive + 0.1).abs() < 1e-6);
+
+        destinations.unroute(&mut runtime).unwrap();
+        assert_eq!(offsets(&mut runtime, 1.0), (0.0, 0.0));
+    }
+}
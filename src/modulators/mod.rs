@@ -0,0 +1,83 @@
+//! Built-in modulators
+//!
+//! Modulators that react to notes read them through `NoteEvent`, so any event
+//! type can drive them by saying which of its variants are note-ons and -offs.
+
+mod ad;
+mod audio_gate;
+mod gate;
+mod key_track;
+mod lfo_bank;
+mod macro_control;
+mod mono_note;
+mod polarity;
+mod retrigger;
+mod slew;
+
+pub use ad::AD;
+pub use audio_gate::AudioGate;
+pub use gate::Gate;
+pub use key_track::KeyTrack;
+pub use lfo_bank::{LfoBank, LfoShape};
+pub use macro_control::{Macro, MacroDestinations};
+pub use mono_note::{MonoNote, NotePriority};
+pub use polarity::{ToBipolar, ToUnipolar};
+pub use retrigger::Retrigger;
+pub use slew::Slew;
+
+pub trait NoteEvent {
+    /// MIDI note number and velocity (0..1) if this event starts a note.
+    fn note_on(&self) -> Option<(u8, f32)>;
+    /// MIDI note number if this event ends a note.
+    fn note_off(&self) -> Option<u8>;
+    /// MIDI channel (0..16) the event arrived on, if it has one.
+    fn channel(&self) -> Option<u8> {
+        None
+    }
+}
+
+/// Which events a note modulator listens to, set with its `set_channel`. Several
+/// modulators can then share one event stream, each answering its own channel.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ChannelFilter {
+    #[default]
+    Omni,
+    /// Only events on this channel; events without a channel are ignored
+    Channel(u8),
+}
+
+impl ChannelFilter {
+    pub fn accepts(self, event: &impl NoteEvent) -> bool {
+        match self {
+            ChannelFilter::Omni => true,
+            ChannelFilter::Channel(channel) => event.channel() == Some(channel),
+        }
+    }
+}
+
+// Raw three-byte MIDI channel messages; a note-on with velocity 0 is a note-off
+impl NoteEvent for [u8; 3] {
+    fn note_on(&self) -> Option<(u8, f32)> {
+        match *self {
+            [status, note, velocity] if status & 0xF0 == 0x90 && velocity > 0 => {
+                Some((note & 0x7F, (velocity & 0x7F) as f32 / 127.0))
+            }
+            _ => None,
+        }
+    }
+
+    fn note_off(&self) -> Option<u8> {
+        match *self {
+            [status, note, _] if status & 0xF0 == 0x80 => Some(note & 0x7F),
+            [status, note, 0] if status & 0xF0 == 0x90 => Some(note & 0x7F),
+            _ => None,
+        }
+    }
+
+    fn channel(&self) -> Option<u8> {
+        match self[0] & 0xF0 {
+            0x80..=0xE0 => Some(self[0] & 0x0F),
+            _ => None,
+        }
+    }
+}
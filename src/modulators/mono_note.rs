@@ -0,0 +1,221 @@
+// Monophonic note tracking: keeps every held key and outputs the pitch of the
+// one that should sound, so releasing a key falls back to another held one.
+use crate::core::*;
+use super::{ChannelFilter, NoteEvent};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum NotePriority {
+    /// The most recently pressed key
+    #[default]
+    Last,
+    Lowest,
+    Highest,
+}
+
+/// Outputs the sounding note as a normalized pitch (see `note_to_pitch`). After
+/// every key is released the last pitch is held so release tails stay in tune.
+///
+/// With a glide time set, moving to a new key while another is still held slides
+/// the pitch there over that time; a key pressed from silence starts on its pitch.
+pub struct MonoNote {
+    channel: ChannelFilter,
+    priority: NotePriority,
+    legato: bool,
+    glide_time: f32,
+    // Held keys in the order they were pressed
+    held: Vec<u8>,
+    current: Option<u8>,
+    // Pitch the glide is heading for, and how far it moves per sample
+    target: f32,
+    glide_step: f32,
+    pitch: f32,
+    values: [f32; BUFFER_SIZE],
+    velocity: f32,
+    triggered: bool,
+}
+
+impl Default for MonoNote {
+    fn default() -> Self {
+        Self {
+            channel: ChannelFilter::Omni,
+            priority: NotePriority::Last,
+            legato: false,
+            glide_time: 0.0,
+            // Every MIDI key can be held at once, so this never reallocates
+            held: Vec::with_capacity(128),
+            current: None,
+            target: 0.0,
+            glide_step: 0.0,
+            pitch: 0.0,
+            values: [0.0; BUFFER_SIZE],
+            velocity: 0.0,
+            triggered: false,
+        }
+    }
+}
+
+impl MonoNote {
+    pub fn set_channel(&mut self, channel: ChannelFilter) {
+        self.channel = channel;
+    }
+
+    pub fn set_priority(&mut self, priority: NotePriority) {
+        self.priority = priority;
+    }
+
+    /// With legato on, moving between held keys changes pitch without retriggering.
+    pub fn set_legato(&mut self, legato: bool) {
+        self.legato = legato;
+    }
+
+    /// Seconds a glide takes, whatever the interval. Zero jumps straight to new notes.
+    pub fn set_glide_time(&mut self, seconds: f32) {
+        self.glide_time = seconds.max(0.0);
+    }
+
+    pub fn note(&self) -> Option<u8> {
+        self.current
+    }
+
+    pub fn velocity(&self) -> f32 {
+        self.velocity
+    }
+
+    pub fn gate(&self) -> bool {
+        self.current.is_some()
+    }
+
+    /// Whether envelopes should restart this tick: always when playing from
+    /// silence, and on every note change unless legato is on.
+    pub fn triggered(&self) -> bool {
+        self.triggered
+    }
+
+    fn select(&self) -> Option<u8> {
+        match self.priority {
+            NotePriority::Last => self.held.last().copied(),
+            NotePriority::Lowest => self.held.iter().min().copied(),
+            NotePriority::Highest => self.held.iter().max().copied(),
+        }
+    }
+
+    fn handle(&mut self, event: &impl NoteEvent, sample_rate: f32) {
+        let mut pressed = None;
+        if let Some((note, velocity)) = event.note_on() {
+            self.held.retain(|&held| held != note);
+            self.held.push(note);
+            // Velocity follows new keys even when priority keeps another note sounding
+            self.velocity = velocity;
+            pressed = Some(note);
+        } else if let Some(note) = event.note_off() {
+            self.held.retain(|&held| held != note);
+        } else {
+            return;
+        }
+
+        let previous = self.current;
+        self.current = self.select();
+        if let Some(note) = self.current {
+            self.target = note_to_pitch(note as f32);
+            if previous.is_some() && self.glide_time > 0.0 {
+                self.glide_step = (self.target - self.pitch).abs() / (self.glide_time * sample_rate);
+            } else {
+                self.pitch = self.target;
+            }
+            // Re-pressing the sounding key counts as a change too
+            let changed = previous != self.current || pressed == self.current;
+            self.triggered = previous.is_none() || (changed && !self.legato);
+        }
+    }
+}
+
+impl<E: NoteEvent> Modulator<E> for MonoNote {
+    fn update(&mut self, sample_rate: f32, event: Option<&E>) {
+        self.update_block(sample_rate, event, BUFFER_SIZE);
+    }
+
+    fn update_block(&mut self, sample_rate: f32, event: Option<&E>, len: usize) {
+        self.triggered = false;
+        if let Some(event) = event.filter(|event| self.channel.accepts(*event)) {
+            self.handle(event, sample_rate);
+        }
+
+        // Only the samples the block uses advance the glide
+        for value in &mut self.values[..len.min(BUFFER_SIZE)] {
+            self.pitch = if self.pitch < self.target {
+                (self.pitch + self.glide_step).min(self.target)
+            } else {
+                (self.pitch - self.glide_step).max(self.target)
+            };
+            *value = self.pitch;
+        }
+    }
+
+    fn get_value(&self, index: usize) -> f32 {
+        self.values[index % BUFFER_SIZE]
+    }
+
+    fn debug_state(&self) -> String {
+        match self.current {
+            Some(note) => format!("note {} ({} held), pitch {:.2}", note, self.held.len(), pitch_to_note(self.pitch)),
+            None => format!("released, pitch {:.2}", pitch_to_note(self.pitch)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn play(note: &mut MonoNote, event: [u8; 3]) -> f32 {
+        Modulator::update(note, 48_000.0, Some(&event));
+        Modulator::<[u8; 3]>::get_value(note, 0)
+    }
+
+    #[test]
+    fn releasing_a_held_note_follows_the_priority() {
+        // Sounding note after holding 60 then 64, and after releasing 64 or 60
+        for (priority, both_held, released_64, released_60) in [
+            (NotePriority::Last, 64.0, 60.0, 64.0),
+            (NotePriority::Lowest, 60.0, 60.0, 64.0),
+            (NotePriority::Highest, 64.0, 60.0, 64.0),
+        ] {
+            for (release, expected) in [(64, released_64), (60, released_60)] {
+                let mut note = MonoNote::default();
+                note.set_priority(priority);
+                play(&mut note, [0x90, 60, 100]);
+                assert_eq!(play(&mut note, [0x90, 64, 100]), note_to_pitch(both_held), "{priority:?}");
+                assert_eq!(play(&mut note, [0x80, release, 0]), note_to_pitch(expected), "{priority:?}");
+                assert!(note.gate());
+            }
+        }
+    }
+
+    #[test]
+    fn glide_takes_the_set_time_to_reach_the_new_note() {
+        let mut note = MonoNote::default();
+        note.set_glide_time(0.1);
+        note.set_legato(true);
+        play(&mut note, [0x90, 60, 100]);
+        play(&mut note, [0x90, 72, 100]);
+        assert!(!note.triggered());
+
+        // Pitches from the block the new note arrived in until it's reached
+        let target = note_to_pitch(72.0);
+        let block = |note: &MonoNote| (0..BUFFER_SIZE).map(|i| Modulator::<[u8; 3]>::get_value(note, i)).collect::<Vec<_>>();
+        let mut pitches = block(&note);
+        while pitches.last() != Some(&target) {
+            Modulator::<[u8; 3]>::update(&mut note, 48_000.0, None);
+            pitches.extend(block(&note));
+        }
+        let samples = pitches.iter().position(|&pitch| pitch == target).unwrap() + 1;
+        let seconds = samples as f32 / 48_000.0;
+        assert!((seconds - 0.1).abs() < 0.001, "{seconds} s");
+
+        // Played detached, the next note starts on its own pitch
+        play(&mut note, [0x80, 60, 0]);
+        play(&mut note, [0x80, 72, 0]);
+        assert_eq!(play(&mut note, [0x90, 65, 100]), note_to_pitch(65.0));
+        assert!(note.triggered());
+    }
+}
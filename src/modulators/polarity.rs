@@ -0,0 +1,117 @@
+// Range adapters between unipolar (0..1) and bipolar (-1..1) sources.
+use crate::core::*;
+
+/// Wraps the unipolar source `M` and remaps its output to bipolar, `x * 2 - 1`,
+/// e.g. so an envelope can swing a parameter both ways around its base. Like
+/// `Slew`, it's registered in place of `M` and updates the wrapped source itself.
+pub struct ToBipolar<M> {
+    source: M,
+}
+
+/// Wraps the bipolar source `M` and remaps its output to unipolar, `(x + 1) / 2`,
+/// e.g. so an LFO only ever opens a filter further. Registered in place of `M`.
+pub struct ToUnipolar<M> {
+    source: M,
+}
+
+macro_rules! range_adapter {
+    ($adapter:ident, $map:expr, $name:literal) => {
+        impl<M: Default> Default for $adapter<M> {
+            fn default() -> Self {
+                Self::new(M::default())
+            }
+        }
+
+        impl<M> $adapter<M> {
+            pub fn new(source: M) -> Self {
+                Self { source }
+            }
+
+            pub fn source(&self) -> &M {
+                &self.source
+            }
+
+            pub fn source_mut(&mut self) -> &mut M {
+                &mut self.source
+            }
+        }
+
+        impl<E, M: Modulator<E>> Modulator<E> for $adapter<M> {
+            fn update(&mut self, sample_rate: f32, event: Option<&E>) {
+                self.source.update(sample_rate, event);
+            }
+
+            fn update_block(&mut self, sample_rate: f32, event: Option<&E>, len: usize) {
+                self.source.update_block(sample_rate, event, len);
+            }
+
+            fn get_value(&self, index: usize) -> f32 {
+                $map(self.source.get_value(index))
+            }
+
+            fn is_active(&self) -> bool {
+                self.source.is_active()
+            }
+
+            fn rate(&self) -> ModRate {
+                self.source.rate()
+            }
+
+            fn range(&self) -> (f32, f32) {
+                let (low, high) = self.source.range();
+                ($map(low), $map(high))
+            }
+
+            fn debug_state(&self) -> String {
+                format!("{} of {}", $name, self.source.debug_state())
+            }
+        }
+    };
+}
+
+range_adapter!(ToBipolar, |x: f32| x * 2.0 - 1.0, "bipolar");
+range_adapter!(ToUnipolar, |x: f32| (x + 1.0) * 0.5, "unipolar");
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Holds whatever it's set to, over its declared range
+    struct Held {
+        value: f32,
+        range: (f32, f32),
+    }
+
+    impl Modulator<()> for Held {
+        fn update(&mut self, _sample_rate: f32, _event: Option<&()>) {}
+
+        fn get_value(&self, _index: usize) -> f32 {
+            self.value
+        }
+
+        fn range(&self) -> (f32, f32) {
+            self.range
+        }
+    }
+
+    #[test]
+    fn unipolar_values_map_onto_the_bipolar_range() {
+        let mut bipolar = ToBipolar::new(Held { value: 0.0, range: (0.0, 1.0) });
+        assert_eq!(Modulator::<()>::range(&bipolar), (-1.0, 1.0));
+        for (value, expected) in [(0.0, -1.0), (0.25, -0.5), (0.5, 0.0), (1.0, 1.0)] {
+            bipolar.source_mut().value = value;
+            bipolar.update(48_000.0, None);
+            assert_eq!(bipolar.get_value(0), expected);
+        }
+    }
+
+    #[test]
+    fn to_unipolar_undoes_to_bipolar() {
+        let mut round_trip = ToUnipolar::new(ToBipolar::new(Held { value: 0.0, range: (0.0, 1.0) }));
+        assert_eq!(Modulator::<()>::range(&round_trip), (0.0, 1.0));
+        for value in [0.0, 0.3, 0.75, 1.0] {
+            round_trip.source_mut().source_mut().value = value;
+            assert!((Modulator::<()>::get_value(&round_trip, 0) - value).abs() < 1e-6);
+        }
+    }
+}
@@ -0,0 +1,88 @@
+// Note-on phase reset for LFOs.
+use crate::core::*;
+use super::{ChannelFilter, LfoBank, NoteEvent};
+
+/// Wraps an `LfoBank` and restarts its master phase at the start phase on each
+/// accepted note-on, before the block is computed, so every note gets the same
+/// modulation. Like `Slew`, it's registered in place of the bank and updates
+/// the bank itself.
+pub struct Retrigger<const N: usize> {
+    source: LfoBank<N>,
+    channel: ChannelFilter,
+    start_phase: f32,
+}
+
+impl<const N: usize> Default for Retrigger<N> {
+    fn default() -> Self {
+        Self::new(LfoBank::default())
+    }
+}
+
+impl<const N: usize> Retrigger<N> {
+    pub fn new(source: LfoBank<N>) -> Self {
+        Self { source, channel: ChannelFilter::Omni, start_phase: 0.0 }
+    }
+
+    pub fn source(&self) -> &LfoBank<N> {
+        &self.source
+    }
+
+    pub fn source_mut(&mut self) -> &mut LfoBank<N> {
+        &mut self.source
+    }
+
+    /// Master phase in cycles that a note-on jumps to.
+    pub fn set_start_phase(&mut self, cycles: f32) {
+        self.start_phase = cycles.rem_euclid(1.0);
+    }
+
+    /// Which events retrigger the bank.
+    pub fn set_channel(&mut self, channel: ChannelFilter) {
+        self.channel = channel;
+    }
+}
+
+impl<E: NoteEvent, const N: usize> Modulator<E> for Retrigger<N> {
+    fn update(&mut self, sample_rate: f32, event: Option<&E>) {
+        self.update_block(sample_rate, event, BUFFER_SIZE);
+    }
+
+    fn update_block(&mut self, sample_rate: f32, event: Option<&E>, len: usize) {
+        let event = event.filter(|event| self.channel.accepts(*event));
+        if event.and_then(NoteEvent::note_on).is_some() {
+            self.source.set_phase(self.start_phase);
+        }
+        Modulator::<E>::update_block(&mut self.source, sample_rate, event, len);
+    }
+
+    fn get_value(&self, index: usize) -> f32 {
+        Modulator::<E>::get_value(&self.source, index)
+    }
+
+    fn debug_state(&self) -> String {
+        format!("retriggered {}", Modulator::<E>::debug_state(&self.source))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn note_on_mid_cycle_jumps_to_the_start_phase() {
+        let mut lfo = Retrigger::<1>::default();
+        lfo.source_mut().set_frequency(10.0);
+        lfo.set_start_phase(0.5);
+        Modulator::<[u8; 3]>::update(&mut lfo, 48_000.0, None);
+        assert!(lfo.source().phase() > 0.0 && lfo.source().phase() < 0.5);
+
+        // A note-off doesn't retrigger
+        Modulator::update(&mut lfo, 48_000.0, Some(&[0x80, 60, 0]));
+        let free_running = Modulator::<[u8; 3]>::get_value(&lfo, 0);
+        assert!((free_running - 1.0).abs() > 1e-3);
+
+        Modulator::update(&mut lfo, 48_000.0, Some(&[0x90, 60, 100]));
+        // The sine peaks halfway through its cycle
+        assert!((Modulator::<[u8; 3]>::get_value(&lfo, 0) - 1.0).abs() < 1e-6);
+    }
+}
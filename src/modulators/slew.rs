@@ -0,0 +1,129 @@
+// Slew limiter around another modulator, so stepped sources glide between values.
+use crate::core::*;
+
+/// Wraps the source `M` and limits how fast its output can move, separately going
+/// up and down. Registered in place of `M` (`use_modulator::<Slew<M>>()`), it
+/// updates the wrapped source itself; reach that through `source_mut`.
+///
+/// Rates are in units per second, so 10.0 takes 0.1 s to cross 0..1. Both start
+/// unlimited, passing the source through unchanged.
+pub struct Slew<M> {
+    source: M,
+    rise: f32,
+    fall: f32,
+    // None until the first value, which is taken as is
+    value: Option<f32>,
+    // Latest source value, where the output is heading
+    target: f32,
+    values: [f32; BUFFER_SIZE],
+}
+
+impl<M: Default> Default for Slew<M> {
+    fn default() -> Self {
+        Self::new(M::default())
+    }
+}
+
+impl<M> Slew<M> {
+    pub fn new(source: M) -> Self {
+        Self {
+            source,
+            rise: f32::INFINITY,
+            fall: f32::INFINITY,
+            value: None,
+            target: 0.0,
+            values: [0.0; BUFFER_SIZE],
+        }
+    }
+
+    /// Fastest rise and fall, in units per second.
+    pub fn set_rates(&mut self, rise: f32, fall: f32) {
+        self.rise = rise.max(0.0);
+        self.fall = fall.max(0.0);
+    }
+
+    pub fn source(&self) -> &M {
+        &self.source
+    }
+
+    pub fn source_mut(&mut self) -> &mut M {
+        &mut self.source
+    }
+}
+
+impl<E, M: Modulator<E>> Modulator<E> for Slew<M> {
+    fn update(&mut self, sample_rate: f32, event: Option<&E>) {
+        self.update_block(sample_rate, event, BUFFER_SIZE);
+    }
+
+    fn update_block(&mut self, sample_rate: f32, event: Option<&E>, len: usize) {
+        self.source.update_block(sample_rate, event, len);
+        let rate = self.source.rate();
+        let (max_rise, max_fall) = (self.rise / sample_rate, self.fall / sample_rate);
+        for (i, out) in self.values.iter_mut().enumerate().take(len.min(BUFFER_SIZE)) {
+            let target = self.source.get_value(rate.held_index(i));
+            self.target = target;
+            let value = match self.value {
+                Some(value) => value + (target - value).clamp(-max_fall, max_rise),
+                None => target,
+            };
+            self.value = Some(value);
+            *out = value;
+        }
+    }
+
+    fn get_value(&self, index: usize) -> f32 {
+        self.values[index % BUFFER_SIZE]
+    }
+
+    // Still gliding toward a source that has settled counts as movement
+    fn is_active(&self) -> bool {
+        self.source.is_active() || self.value.is_some_and(|value| value != self.target)
+    }
+
+    fn range(&self) -> (f32, f32) {
+        self.source.range()
+    }
+
+    fn debug_state(&self) -> String {
+        format!("slew {:.3} of {}", self.value.unwrap_or(0.0), self.source.debug_state())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Holds whatever it's set to
+    #[derive(Default)]
+    struct Held(f32);
+
+    impl Modulator<()> for Held {
+        fn update(&mut self, _sample_rate: f32, _event: Option<&()>) {}
+
+        fn get_value(&self, _index: usize) -> f32 {
+            self.0
+        }
+    }
+
+    #[test]
+    fn steps_glide_at_the_rise_and_fall_rates() {
+        let mut slew = Slew::new(Held(0.0));
+        slew.set_rates(48.0, 96.0);
+        slew.update(48_000.0, None);
+        assert_eq!(slew.get_value(BUFFER_SIZE - 1), 0.0);
+
+        // 48 units a second is 0.001 a sample at 48 kHz
+        slew.source_mut().0 = 1.0;
+        slew.update(48_000.0, None);
+        for i in [0, 99, BUFFER_SIZE - 1] {
+            assert!((slew.get_value(i) - (i + 1) as f32 * 0.001).abs() < 1e-5, "sample {}", i);
+        }
+
+        let top = slew.get_value(BUFFER_SIZE - 1);
+        slew.source_mut().0 = 0.0;
+        slew.update(48_000.0, None);
+        assert!((slew.get_value(9) - (top - 0.02)).abs() < 1e-5);
+        assert!(slew.is_active());
+    }
+}
@@ -0,0 +1,198 @@
+//! Flattened graph execution
+//!
+//! `serial!` and `parallel!` describe their structure as a `Graph`, which
+//! `Schedule::compile` flattens into a linear list of operations over one shared
+//! buffer pool, executed by a single loop. Nested invocations are spliced into
+//! the enclosing graph; other sub-graphs, like `wet_dry!` or a component built
+//! by a function, run as a single opaque node.
+
+use crate::core::*;
+
+// === Graph Description ===
+pub enum Graph<E: 'static> {
+    Component(ComponentFn<E>),
+    Serial(Vec<Graph<E>>),
+    Parallel(Vec<(f32, Graph<E>)>),
+}
+
+impl<E: 'static> From<ComponentFn<E>> for Graph<E> {
+    fn from(component: ComponentFn<E>) -> Self {
+        Graph::Component(component)
+    }
+}
+
+// === Schedule ===
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Buf {
+    Input,
+    Output,
+    Pool(usize),
+}
+
+enum Op {
+    Process { component: usize, input: Buf, output: Buf },
+    Accumulate { input: Buf, output: Buf, weight: f32 },
+    Copy { input: Buf, output: Buf },
+    Clear(Buf),
+}
+
+pub struct Schedule<E: 'static> {
+    components: Vec<ComponentFn<E>>,
+    ops: Vec<Op>,
+    pool: Vec<Vec<f32>>,
+}
+
+// Hands out pool buffers during compilation, reusing released ones
+struct PoolAllocator {
+    free: Vec<usize>,
+    len: usize,
+}
+
+impl PoolAllocator {
+    fn alloc(&mut self) -> Buf {
+        let index = self.free.pop().unwrap_or_else(|| {
+            self.len += 1;
+            self.len - 1
+        });
+        Buf::Pool(index)
+    }
+
+    fn release(&mut self, buf: Buf) {
+        if let Buf::Pool(index) = buf {
+            self.free.push(index);
+        }
+    }
+}
+
+impl<E: Send + 'static> Schedule<E> {
+    pub fn compile(graph: Graph<E>) -> Self {
+        let mut schedule = Schedule {
+            components: Vec::new(),
+            ops: Vec::new(),
+            pool: Vec::new(),
+        };
+        let mut allocator = PoolAllocator { free: Vec::new(), len: 0 };
+        schedule.emit(graph, Buf::Input, Buf::Output, &mut allocator);
+        schedule.pool = vec![Vec::new(); allocator.len];
+        schedule
+    }
+
+    fn emit(&mut self, graph: Graph<E>, input: Buf, output: Buf, allocator: &mut PoolAllocator) {
+        match graph {
+            Graph::Component(component) => {
+                self.components.push(component);
+                self.ops.push(Op::Process { component: self.components.len() - 1, input, output });
+            }
+            Graph::Serial(stages) => {
+                let stage_count = stages.len();
+                if stage_count == 0 {
+                    self.ops.push(Op::Copy { input, output });
+                    return;
+                }
+
+                let mut current = input;
+                for (i, stage) in stages.into_iter().enumerate() {
+                    let target = if i + 1 == stage_count { output } else { allocator.alloc() };
+                    self.emit(stage, current, target, allocator);
+                    if current != input {
+                        allocator.release(current);
+                    }
+                    current = target;
+                }
+            }
+            Graph::Parallel(branches) => {
+                self.ops.push(Op::Clear(output));
+                for (weight, branch) in branches {
+                    let scratch = allocator.alloc();
+                    self.emit(branch, input, scratch, allocator);
+                    self.ops.push(Op::Accumulate { input: scratch, output, weight });
+                    allocator.release(scratch);
+                }
+            }
+        }
+    }
+
+    pub fn process(&mut self, runtime: &mut Runtime<E>, input: &[f32], output: &mut [f32], sample_rate: f32) {
+        for buf in &mut self.pool {
+            if buf.len() != output.len() {
+                buf.resize(output.len(), 0.0);
+            }
+        }
+
+        for op in &self.ops {
+            let (source, target) = match *op {
+                Op::Process { input, output, .. } => (Some(input), output),
+                Op::Accumulate { input, output, .. } => (Some(input), output),
+                Op::Copy { input, output } => (Some(input), output),
+                Op::Clear(output) => (None, output),
+            };
+
+            // Take the target out of the pool so it can be borrowed alongside the source
+            let mut pooled = match target {
+                Buf::Pool(index) => Some(std::mem::take(&mut self.pool[index])),
+                _ => None,
+            };
+            let dst: &mut [f32] = match &mut pooled {
+                Some(buf) => buf,
+                None => &mut *output,
+            };
+            let src: &[f32] = match source {
+                Some(Buf::Pool(index)) => &self.pool[index],
+                _ => input,
+            };
+
+            match *op {
+                Op::Process { component, .. } => {
+                    dst.fill(0.0);
+                    (self.components[component])(runtime, src, dst, sample_rate);
+                }
+                Op::Accumulate { weight, .. } => mix_weighted(dst, src, weight),
+                Op::Copy { .. } => dst.copy_from_slice(src),
+                Op::Clear(_) => dst.fill(0.0),
+            }
+
+            if let (Buf::Pool(index), Some(buf)) = (target, pooled) {
+                self.pool[index] = buf;
+            }
+        }
+    }
+
+    pub fn into_component(mut self) -> ComponentFn<E> {
+        Box::new(move |runtime, input, output, sample_rate| {
+            self.process(runtime, input, output, sample_rate);
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serial;
+
+    fn gain<E>(amount: f32) -> impl FnOnce(&mut Builder<E>) -> ComponentFn<E> {
+        move |_builder| Box::new(move |_runtime, input, output, _sample_rate| {
+            for (out, &sample) in output.iter_mut().zip(input) {
+                *out = sample * amount;
+            }
+        })
+    }
+
+    #[test]
+    fn nested_macros_splice_into_one_schedule() {
+        let (_bus, mut builder) = new::<()>();
+        let builder = &mut builder;
+        let graph = crate::__serial_graph!(builder; []; gain(2.0), parallel!((0.5, gain(1.0)), (0.5, serial!(gain(3.0), gain(1.0)))));
+        let schedule = Schedule::compile(graph);
+        // Every leaf is a component of the one schedule, none is a nested schedule
+        assert_eq!(schedule.components.len(), 4);
+    }
+
+    #[test]
+    fn flattened_graph_computes_the_nested_result() {
+        let (_bus, builder) = new::<()>();
+        let mut runtime = builder.build(serial!(gain(2.0), parallel!((0.5, gain(1.0)), (0.5, serial!(gain(3.0), gain(1.0))))));
+        let mut output = [0.0; BUFFER_SIZE];
+        runtime.tick(48_000.0, None, &[1.0; BUFFER_SIZE], &mut output);
+        assert!(output.iter().all(|&sample| (sample - 4.0).abs() < 1e-6));
+    }
+}